@@ -0,0 +1,253 @@
+use crate::cartridge::{is_bs93, BS93_HEADER_LENGTH};
+use crate::mikey::uart::comlynx_device::ComlynxDevice;
+use crate::mikey::uart::redeye_status::RedeyeStatus;
+use alloc::{sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Upload command of the BLL loaders, `0x81 'P'`.
+pub const BLL_UPLOAD_COMMAND: [u8; 2] = [0x81, b'P'];
+/// Bits of a `ComLynx` frame: start, 8 data bits, parity and stop.
+const FRAME_BITS: u32 = 11;
+
+/// Streams a BS93 `.o` program to the BLL upload loader running on the console, the PC side
+/// of the protocol.
+///
+/// The loader waits for:
+/// ```text
+/// 0x81 'P'
+/// load address, big endian
+/// length, big endian, complemented
+/// data
+/// ```
+/// then jumps to the load address. Bytes are sent at the console baud rate, no faster than
+/// the loader reads them.
+pub struct BllUpload {
+    bytes: Vec<u8>,
+    sent: Arc<AtomicUsize>,
+    bits: u32,
+}
+
+impl BllUpload {
+    /// Uploads the program up to the length in the BS93 header, the bytes past it are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bs93` is not a BS93 file or is shorter than its header says.
+    pub fn new(bs93: &[u8]) -> Result<Self, &'static str> {
+        if !is_bs93(bs93) {
+            return Err("Not a BS93 file");
+        }
+        // file length, header included
+        let file_len = u16::from_be_bytes([bs93[4], bs93[5]]);
+        let Some(data) = bs93.get(BS93_HEADER_LENGTH..usize::from(file_len)) else {
+            return Err("Truncated BS93 file");
+        };
+        let len = file_len - BS93_HEADER_LENGTH as u16;
+        let mut bytes = Vec::with_capacity(data.len() + 6);
+        bytes.extend_from_slice(&BLL_UPLOAD_COMMAND);
+        bytes.extend_from_slice(&bs93[2..4]);
+        bytes.extend_from_slice(&(!len).to_be_bytes());
+        bytes.extend_from_slice(data);
+        Ok(Self {
+            bytes,
+            sent: Arc::new(AtomicUsize::new(0)),
+            bits: FRAME_BITS,
+        })
+    }
+
+    #[must_use]
+    pub fn load_address(&self) -> u16 {
+        u16::from_be_bytes([self.bytes[2], self.bytes[3]])
+    }
+
+    /// Bytes on the line, command and header included.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Follows the upload once the device is plugged in.
+    #[must_use]
+    pub fn progress(&self) -> BllUploadProgress {
+        BllUploadProgress {
+            sent: self.sent.clone(),
+            len: self.bytes.len(),
+        }
+    }
+}
+
+impl ComlynxDevice for BllUpload {
+    fn tick(&mut self, _line: RedeyeStatus) -> RedeyeStatus {
+        self.bits = self.bits.saturating_add(1);
+        RedeyeStatus::High
+    }
+
+    fn transmit(&mut self) -> Option<u8> {
+        if self.bits < FRAME_BITS {
+            return None;
+        }
+        let sent = self.sent.load(Ordering::Acquire);
+        let data = *self.bytes.get(sent)?;
+        self.sent.store(sent + 1, Ordering::Release);
+        self.bits = 0;
+        Some(data)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BllUploadProgress {
+    sent: Arc<AtomicUsize>,
+    len: usize,
+}
+
+impl BllUploadProgress {
+    #[must_use]
+    pub fn sent(&self) -> usize {
+        self.sent.load(Ordering::Acquire)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn done(&self) -> bool {
+        self.sent() >= self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lynx::Lynx;
+    use crate::test_rom::{lynx_running, store};
+
+    fn read_byte(code: &mut Vec<u8>) {
+        // wait for RXRDY, read SERDAT
+        code.extend_from_slice(&[0xAD, 0x8C, 0xFD, 0x29, 0x40, 0xF0, 0xF9, 0xAD, 0x8D, 0xFD]);
+    }
+
+    fn branch(code: &mut Vec<u8>, opcode: u8, target: usize) {
+        let offset = target as isize - (code.len() + 2) as isize;
+        code.extend_from_slice(&[opcode, offset as u8]);
+    }
+
+    /// A console running the BLL upload loader, pointers in zero page.
+    fn lynx_loader() -> Lynx {
+        let mut code = vec![];
+        store(&mut code, 0xFD10, 1); // TIM4BKUP
+        store(&mut code, 0xFD11, 0x18); // TIM4CTLA
+        store(&mut code, 0xFD8C, 0x1D); // SERCTL
+        let command = code.len();
+        read_byte(&mut code);
+        code.extend_from_slice(&[0xC9, 0x81]); // CMP #$81
+        branch(&mut code, 0xD0, command); // BNE
+        read_byte(&mut code);
+        code.extend_from_slice(&[0xC9, b'P']); // CMP #'P'
+        branch(&mut code, 0xD0, command);
+        for zp in [[0x01, 0x03], [0x00, 0x02]] {
+            read_byte(&mut code);
+            code.extend_from_slice(&[0x85, zp[0], 0x85, zp[1]]); // STA ptr, STA start
+        }
+        for zp in [0x05, 0x04] {
+            read_byte(&mut code);
+            code.extend_from_slice(&[0x85, zp]); // STA len
+        }
+        code.extend_from_slice(&[0xA0, 0x00]); // LDY #0
+        let next = code.len();
+        // INC len, BNE data, INC len+1, BEQ run
+        code.extend_from_slice(&[0xE6, 0x04, 0xD0, 0x04, 0xE6, 0x05, 0xF0, 0x00]);
+        let run = code.len() - 1;
+        read_byte(&mut code);
+        code.extend_from_slice(&[0x91, 0x00, 0xC8]); // STA (ptr),Y, INY
+        branch(&mut code, 0xD0, next);
+        code.extend_from_slice(&[0xE6, 0x01]); // INC ptr+1
+        branch(&mut code, 0x80, next); // BRA
+        code[run] = (code.len() - run - 1) as u8;
+        code.extend_from_slice(&[0x6C, 0x02, 0x00]); // JMP (start)
+        lynx_running(&code)
+    }
+
+    #[test]
+    fn header() {
+        assert!(BllUpload::new(&[0; 64]).is_err());
+        let mut bs93 = vec![0x80, 0x08, 0x12, 0x34, 0x00, 0x0D];
+        bs93.extend_from_slice(b"BS93");
+        bs93.extend_from_slice(&[1, 2, 3]);
+        let mut upload = BllUpload::new(&bs93).unwrap();
+        assert_eq!(upload.load_address(), 0x1234);
+        let progress = upload.progress();
+        let mut bytes = vec![];
+        while let Some(data) = upload.transmit() {
+            bytes.push(data);
+            for _ in 0..FRAME_BITS {
+                upload.tick(RedeyeStatus::High);
+            }
+        }
+        assert_eq!(bytes, [0x81, b'P', 0x12, 0x34, 0xFF, 0xFC, 1, 2, 3]);
+        assert!(progress.done());
+    }
+
+    #[test]
+    fn header_length() {
+        let mut bs93 = vec![0x80, 0x08, 0x12, 0x34, 0x00, 0x0C];
+        bs93.extend_from_slice(b"BS93");
+        bs93.extend_from_slice(&[1, 2, 3, 4]);
+        // the bytes past the length are left out
+        let upload = BllUpload::new(&bs93).unwrap();
+        assert_eq!(upload.bytes, [0x81, b'P', 0x12, 0x34, 0xFF, 0xFD, 1, 2]);
+
+        bs93[5] = 0x0F;
+        assert_eq!(
+            BllUpload::new(&bs93).map(|_| ()),
+            Err("Truncated BS93 file")
+        );
+        bs93[5] = 0x09;
+        assert_eq!(
+            BllUpload::new(&bs93).map(|_| ()),
+            Err("Truncated BS93 file")
+        );
+        bs93[5] = 0x0A;
+        let upload = BllUpload::new(&bs93).unwrap();
+        assert_eq!(upload.bytes, [0x81, b'P', 0x12, 0x34, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn upload() {
+        // store a marker, then loop
+        let mut program = vec![0xA9, 0x5A, 0x8D, 0x00, 0x30, 0x4C, 0x05, 0x20];
+        program.extend((0..292).map(|i| i as u8));
+        let mut bs93 = vec![0x80, 0x08, 0x20, 0x00];
+        bs93.extend_from_slice(&(program.len() as u16 + 10).to_be_bytes());
+        bs93.extend_from_slice(b"BS93");
+        bs93.extend_from_slice(&program);
+        // trailing bytes, left out
+        bs93.extend_from_slice(&[0xEE; 4]);
+
+        let mut lynx = lynx_loader();
+        let progress = lynx.upload_bll(&bs93).unwrap();
+        while !progress.done() {
+            lynx.tick();
+        }
+        for _ in 0..20_000 {
+            lynx.tick();
+        }
+
+        for (addr, &data) in (0x2000..).zip(&program) {
+            assert_eq!(lynx.ram().get(addr), data);
+        }
+        assert_eq!(lynx.ram().get(0x3000), 0x5A);
+        assert_eq!(lynx.mikey().uart().received().0, 306);
+    }
+}
@@ -129,6 +129,10 @@ impl CartridgeGeneric {
         (block * self.bank_size + addr) as usize
     }
 
+    pub fn data_size(&self) -> usize {
+        self.banks.first().map_or(0, Vec::len)
+    }
+
     fn read(&mut self, pins: u32) -> u32 {
         let addr = self.data_address(pins);
         let data = *self.banks[0].get(addr).unwrap_or(&0xff);
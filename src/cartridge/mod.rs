@@ -3,7 +3,7 @@ mod eeprom;
 pub mod lnx_header;
 mod no_intro;
 
-use crate::{alloc, bus, consts, mikey, suzy, TO_U16};
+use crate::{alloc, bus, consts, mikey, suzy};
 use alloc::{string::String, vec::Vec};
 use bus::{Bus, BusStatus};
 use cartridge_generic::{CartridgeGeneric, _1024KAUDIN_PINS, _128K_PINS, _256K_PINS, _512K_PINS};
@@ -23,7 +23,7 @@ use serde::{Deserialize, Serialize};
 use suzy::registers::Switches;
 
 const LNX_HEADER_LENGTH: usize = 64;
-const BS93_HEADER_LENGTH: usize = 10;
+pub(crate) const BS93_HEADER_LENGTH: usize = 10;
 
 const DATA_PINS: [u32; 8] = [
     CART_PIN_D0,
@@ -175,7 +175,7 @@ impl Default for Cartridge {
     }
 }
 
-fn is_bs93(file_content: &[u8]) -> bool {
+pub(crate) fn is_bs93(file_content: &[u8]) -> bool {
     file_content.len() > BS93_HEADER_LENGTH && &file_content[6..=9] == b"BS93"
 }
 
@@ -280,6 +280,15 @@ impl Cartridge {
         }
     }
 
+    /// Size of the cartridge data addressable through `data_address`.
+    #[must_use]
+    pub fn data_size(&self) -> usize {
+        match &self.cart {
+            CartType::Generic(c) => c.data_size(),
+            CartType::None(..) => 0,
+        }
+    }
+
     fn lnx(&mut self, file_content: &[u8]) {
         self.load_lnx_header(file_content);
 
@@ -0,0 +1,436 @@
+use crate::lynx::Lynx;
+use alloc::{collections::BTreeMap, vec::Vec};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+
+/// Size of a frame: magic, sequence number, tick and data byte.
+pub const COMLYNX_FRAME_LEN: usize = 14;
+/// Default jitter buffer, ~2 bytes at 62500 bauds.
+pub const COMLYNX_DEFAULT_JITTER_TICKS: u64 = 10_240;
+
+const FRAME_MAGIC: u8 = 0xC1;
+const UDP_MAX_DATAGRAM: usize = 1024;
+
+/// A byte sent by the remote UART.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComlynxFrame {
+    pub sequence: u32,
+    /// Remote tick of the transmission.
+    pub tick: u64,
+    pub data: u8,
+}
+
+impl ComlynxFrame {
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; COMLYNX_FRAME_LEN] {
+        let mut bytes = [0; COMLYNX_FRAME_LEN];
+        bytes[0] = FRAME_MAGIC;
+        bytes[1..5].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[5..13].copy_from_slice(&self.tick.to_le_bytes());
+        bytes[13] = self.data;
+        bytes
+    }
+
+    /// Decodes a frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` is not a frame.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() != COMLYNX_FRAME_LEN || bytes[0] != FRAME_MAGIC {
+            return Err("Invalid ComLynx frame");
+        }
+        Ok(Self {
+            sequence: u32::from_le_bytes(bytes[1..5].try_into().unwrap()),
+            tick: u64::from_le_bytes(bytes[5..13].try_into().unwrap()),
+            data: bytes[13],
+        })
+    }
+}
+
+/// Frames accounting of a link.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComlynxLinkStats {
+    pub sent: u64,
+    pub received: u64,
+    pub delivered: u64,
+    /// Frames received after a later one was delivered, or twice.
+    pub late: u64,
+    /// Sequence numbers given up on.
+    pub lost: u64,
+    pub invalid: u64,
+}
+
+enum Socket {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+/// `ComLynx` link to another holani instance over TCP or UDP.
+///
+/// Every UART byte is sent with a sequence number and the tick it was transmitted at.
+/// Received bytes are held in a jitter buffer and handed to the UART in sequence order,
+/// keeping the remote spacing, `jitter` ticks after the remote transmission.
+pub struct ComlynxLink {
+    socket: Socket,
+    tx_sequence: u32,
+    rx_sequence: u32,
+    jitter: u64,
+    /// Local tick minus remote tick, set by the first frame.
+    tick_offset: Option<i128>,
+    pending: BTreeMap<u32, ComlynxFrame>,
+    rx_bytes: Vec<u8>,
+    tx_bytes: Vec<u8>,
+    stats: ComlynxLinkStats,
+}
+
+impl ComlynxLink {
+    fn new(socket: Socket) -> Self {
+        Self {
+            socket,
+            tx_sequence: 0,
+            rx_sequence: 0,
+            jitter: COMLYNX_DEFAULT_JITTER_TICKS,
+            tick_offset: None,
+            pending: BTreeMap::new(),
+            rx_bytes: vec![],
+            tx_bytes: vec![],
+            stats: ComlynxLinkStats::default(),
+        }
+    }
+
+    /// Waits for a peer to connect over TCP.
+    ///
+    /// # Errors
+    ///
+    /// Returns the socket errors.
+    pub fn tcp_accept(listener: &TcpListener) -> io::Result<Self> {
+        let (stream, _) = listener.accept()?;
+        Self::tcp(stream)
+    }
+
+    /// Connects to a peer over TCP.
+    ///
+    /// # Errors
+    ///
+    /// Returns the socket errors.
+    pub fn tcp_connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Self::tcp(TcpStream::connect(addr)?)
+    }
+
+    fn tcp(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self::new(Socket::Tcp(stream)))
+    }
+
+    /// Exchanges datagrams with `peer` over UDP.
+    ///
+    /// # Errors
+    ///
+    /// Returns the socket errors.
+    pub fn udp(socket: UdpSocket, peer: SocketAddr) -> io::Result<Self> {
+        socket.connect(peer)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self::new(Socket::Udp(socket)))
+    }
+
+    /// Delay, in ticks, between a remote transmission and its local reception.
+    pub fn set_jitter_buffer(&mut self, ticks: u64) {
+        self.jitter = ticks;
+    }
+
+    #[must_use]
+    pub fn jitter_buffer(&self) -> u64 {
+        self.jitter
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> ComlynxLinkStats {
+        self.stats
+    }
+
+    /// Sends a byte transmitted at `tick`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the socket errors.
+    pub fn send(&mut self, tick: u64, data: u8) -> io::Result<()> {
+        let frame = ComlynxFrame {
+            sequence: self.tx_sequence,
+            tick,
+            data,
+        };
+        self.tx_sequence = self.tx_sequence.wrapping_add(1);
+        self.stats.sent += 1;
+        match &mut self.socket {
+            Socket::Tcp(_) => {
+                self.tx_bytes.extend_from_slice(&frame.to_bytes());
+                self.flush()
+            }
+            Socket::Udp(socket) => match socket.send(&frame.to_bytes()) {
+                Err(e) if e.kind() != ErrorKind::WouldBlock => Err(e),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let Socket::Tcp(stream) = &mut self.socket else {
+            return Ok(());
+        };
+        while !self.tx_bytes.is_empty() {
+            match stream.write(&self.tx_bytes) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.tx_bytes.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the frames received so far into the jitter buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the socket errors, and `UnexpectedEof` once the TCP peer is gone.
+    pub fn poll(&mut self, now: u64) -> io::Result<()> {
+        self.flush()?;
+        let mut buffer = [0; UDP_MAX_DATAGRAM];
+        loop {
+            match &mut self.socket {
+                Socket::Tcp(stream) => match stream.read(&mut buffer) {
+                    Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                    Ok(n) => self.rx_bytes.extend_from_slice(&buffer[..n]),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) => return Err(e),
+                },
+                Socket::Udp(socket) => match socket.recv(&mut buffer) {
+                    Ok(n) => match ComlynxFrame::from_bytes(&buffer[..n]) {
+                        Ok(frame) => self.push(frame, now),
+                        Err(_) => self.stats.invalid += 1,
+                    },
+                    Err(e)
+                        if matches!(
+                            e.kind(),
+                            ErrorKind::WouldBlock | ErrorKind::ConnectionRefused
+                        ) =>
+                    {
+                        break
+                    }
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+        self.read_stream_frames(now);
+        Ok(())
+    }
+
+    fn read_stream_frames(&mut self, now: u64) {
+        let mut start = 0;
+        while self.rx_bytes.len() - start >= COMLYNX_FRAME_LEN {
+            if let Ok(frame) =
+                ComlynxFrame::from_bytes(&self.rx_bytes[start..start + COMLYNX_FRAME_LEN])
+            {
+                self.push(frame, now);
+                start += COMLYNX_FRAME_LEN;
+            } else {
+                // resynchronise on the next magic byte
+                self.stats.invalid += 1;
+                start += 1;
+            }
+        }
+        self.rx_bytes.drain(..start);
+    }
+
+    fn push(&mut self, frame: ComlynxFrame, now: u64) {
+        self.stats.received += 1;
+        if self.tick_offset.is_none() {
+            self.tick_offset = Some(i128::from(now) - i128::from(frame.tick));
+            self.rx_sequence = frame.sequence;
+        }
+        if frame.sequence.wrapping_sub(self.rx_sequence) > u32::MAX / 2
+            || self.pending.insert(frame.sequence, frame).is_some()
+        {
+            self.stats.late += 1;
+        }
+    }
+
+    fn delivery_tick(&self, frame: &ComlynxFrame) -> u64 {
+        let offset = self.tick_offset.unwrap_or_default();
+        (i128::from(frame.tick) + offset + i128::from(self.jitter)).max(0) as u64
+    }
+
+    /// Next byte due at `now`, in sequence order.
+    ///
+    /// A missing byte is given up on once a later one is due.
+    pub fn receive(&mut self, now: u64) -> Option<u8> {
+        if let Some(frame) = self.pending.remove(&self.rx_sequence) {
+            if self.delivery_tick(&frame) > now {
+                self.pending.insert(frame.sequence, frame);
+                return None;
+            }
+            self.rx_sequence = self.rx_sequence.wrapping_add(1);
+            self.stats.delivered += 1;
+            return Some(frame.data);
+        }
+
+        let (&sequence, frame) = self
+            .pending
+            .range(self.rx_sequence..)
+            .chain(self.pending.range(..self.rx_sequence))
+            .next()?;
+        if self.delivery_tick(frame) > now {
+            return None;
+        }
+        self.stats.lost += u64::from(sequence.wrapping_sub(self.rx_sequence));
+        self.rx_sequence = sequence;
+        self.receive(now)
+    }
+
+    /// Sends the bytes transmitted by `lynx` and hands it the remote bytes that are due.
+    ///
+    /// Call it regularly, e.g. every few hundred ticks or once per frame for low baud rates.
+    ///
+    /// # Errors
+    ///
+    /// Returns the socket errors.
+    pub fn pump(&mut self, lynx: &mut Lynx) -> io::Result<()> {
+        let now = lynx.mikey().ticks();
+        while let Some((tick, data)) = lynx.comlynx_ext_tx() {
+            self.send(tick, data)?;
+        }
+        self.poll(now)?;
+        while let Some(data) = self.receive(now) {
+            lynx.comlynx_ext_rx(data);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "comlynx_shared_memory")]
+    use crate::mikey::uart::comlynx_cable_shared_memory::ComlynxCable;
+    use crate::test_rom::lynx_sending;
+
+    /// A console on a link of its own, every console is on the default shared memory link.
+    #[cfg(feature = "comlynx_shared_memory")]
+    fn unplugged(mut lynx: Lynx, link: &str) -> Lynx {
+        lynx.set_comlynx_cable(ComlynxCable::new(link).unwrap());
+        lynx
+    }
+
+    #[cfg(not(feature = "comlynx_shared_memory"))]
+    fn unplugged(lynx: Lynx, _link: &str) -> Lynx {
+        lynx
+    }
+
+    fn run(lynxes: &mut [Lynx], links: &mut [ComlynxLink], ticks: u64) {
+        for _ in 0..ticks / 256 {
+            for (lynx, link) in lynxes.iter_mut().zip(links.iter_mut()) {
+                for _ in 0..256 {
+                    lynx.tick();
+                }
+                link.pump(lynx).unwrap();
+            }
+        }
+    }
+
+    fn udp_pair() -> (ComlynxLink, ComlynxLink) {
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (a_addr, b_addr) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+        (
+            ComlynxLink::udp(a, b_addr).unwrap(),
+            ComlynxLink::udp(b, a_addr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn tcp_localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || ComlynxLink::tcp_connect(addr).unwrap());
+        let server = ComlynxLink::tcp_accept(&listener).unwrap();
+        let mut links = [server, client.join().unwrap()];
+        let mut lynxes = [
+            unplugged(lynx_sending(&[0x12, 0x34, 0x56]), "holani_test_tcp_0"),
+            unplugged(lynx_sending(&[]), "holani_test_tcp_1"),
+        ];
+
+        run(&mut lynxes, &mut links, 100_000);
+
+        assert_eq!(lynxes[1].mikey().uart().received(), (3, 0x56));
+        assert_eq!(links[1].stats().delivered, 3);
+    }
+
+    #[test]
+    fn udp_localhost() {
+        let (a, b) = udp_pair();
+        let mut links = [a, b];
+        let mut lynxes = [
+            unplugged(lynx_sending(&[0xA5]), "holani_test_udp_0"),
+            unplugged(lynx_sending(&[0x5A]), "holani_test_udp_1"),
+        ];
+
+        run(&mut lynxes, &mut links, 100_000);
+
+        assert_eq!(lynxes[0].mikey().uart().received().0, 2);
+        assert_eq!(lynxes[1].mikey().uart().received().0, 2);
+    }
+
+    #[test]
+    fn jitter_buffer_order() {
+        let (mut a, mut b) = udp_pair();
+        b.set_jitter_buffer(100);
+        let frames = [(1, 1_010, 0x02), (0, 1_000, 0x01), (3, 1_030, 0x04)];
+        for (sequence, tick, data) in frames {
+            let frame = ComlynxFrame {
+                sequence,
+                tick,
+                data,
+            };
+            let Socket::Udp(socket) = &a.socket else {
+                unreachable!()
+            };
+            socket.send(&frame.to_bytes()).unwrap();
+            a.stats.sent += 1;
+        }
+        while b.stats().received < 3 {
+            b.poll(0).unwrap();
+        }
+
+        // the first frame received sets the tick offset, remote 1010 is local 0
+        assert_eq!(b.receive(99), None);
+        assert_eq!(b.receive(100), Some(0x02));
+        assert_eq!(b.receive(100), None);
+        assert_eq!(b.stats().late, 1);
+        assert_eq!(b.receive(119), None);
+        assert_eq!(b.receive(120), Some(0x04));
+        assert_eq!(b.stats().lost, 1);
+    }
+
+    #[test]
+    fn transmit_ticks() {
+        let (mut a, mut b) = udp_pair();
+        let mut lynx = unplugged(lynx_sending(&[0x01, 0x02, 0x03]), "holani_test_ticks");
+        for _ in 0..100_000 {
+            lynx.tick();
+        }
+        a.pump(&mut lynx).unwrap();
+        while b.stats().received < 3 {
+            b.poll(0).unwrap();
+        }
+
+        // each byte is written once the previous one is sent, 11 bits of 16us
+        let ticks: Vec<u64> = b.pending.values().map(|frame| frame.tick).collect();
+        assert!(ticks[2] < 100_000);
+        assert!(ticks[1] - ticks[0] >= 11 * 16 * 16);
+        assert_eq!(ticks[1] - ticks[0], ticks[2] - ticks[1]);
+    }
+}
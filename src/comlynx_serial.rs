@@ -0,0 +1,270 @@
+use crate::consts::{CRYSTAL_FREQ, TIM4BKUP, TIM4CTLA};
+use crate::lynx::Lynx;
+use crate::mikey::registers::SerCtlW;
+use crate::mikey::uart::comlynx_device::ComlynxDevice;
+use crate::mikey::uart::redeye_status::RedeyeStatus;
+use alloc::{boxed::Box, collections::vec_deque::VecDeque, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::io::{self, ErrorKind, Read, Write};
+
+/// Bits of a `ComLynx` frame: start, 8 data bits, parity and stop.
+const FRAME_BITS: u64 = 11;
+const READ_LEN: usize = 256;
+
+/// Parity bit of the UART frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SerialParity {
+    Even,
+    Odd,
+    /// Always 1.
+    Mark,
+    /// Always 0.
+    Space,
+}
+
+/// Host serial line settings matching the console UART.
+///
+/// Frames are always 8 data bits, a parity bit and a stop bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SerialSettings {
+    /// `CLOCK4 / (TIM4BKUP + 1) / 8`.
+    pub baud: u32,
+    pub parity: SerialParity,
+    /// The console sends a break, `TXBRK`.
+    pub line_break: bool,
+}
+
+impl SerialSettings {
+    #[must_use]
+    pub fn from_lynx(lynx: &Lynx) -> Self {
+        let timers = lynx.mikey().timers();
+        let clock4 = 1_000_000 >> (timers.peek(TIM4CTLA) & 0x07).min(6);
+        let baud = clock4 / (u32::from(timers.peek(TIM4BKUP)) + 1) / 8;
+
+        let regs = lynx.mikey().registers();
+        let parity = match (
+            regs.serctl_w_is_flag_set(SerCtlW::par_en),
+            regs.serctl_w_is_flag_set(SerCtlW::par_even),
+        ) {
+            (true, true) => SerialParity::Even,
+            (true, false) => SerialParity::Odd,
+            (false, true) => SerialParity::Mark,
+            (false, false) => SerialParity::Space,
+        };
+        Self {
+            baud,
+            parity,
+            line_break: regs.serctl_w_is_flag_set(SerCtlW::tx_brk),
+        }
+    }
+
+    /// Console ticks per bit.
+    #[must_use]
+    pub fn bit_ticks(&self) -> u64 {
+        u64::from(CRYSTAL_FREQ / self.baud.max(1))
+    }
+}
+
+/// Holds the line `Low` while the host sends a break.
+struct HostBreak(Arc<AtomicBool>);
+
+impl ComlynxDevice for HostBreak {
+    fn tick(&mut self, _line: RedeyeStatus) -> RedeyeStatus {
+        RedeyeStatus::from(!self.0.load(Ordering::Acquire))
+    }
+}
+
+/// Bridges the console UART to a host byte stream, e.g. a serial adapter or the master side
+/// of a pseudo-terminal, so PC tools can talk to the console.
+///
+/// The stream should be non blocking. Host bytes reach the console no faster than its baud
+/// rate. The line settings are reported for the caller to apply to a real serial port.
+pub struct SerialBridge<S: Read + Write> {
+    stream: S,
+    settings: Option<SerialSettings>,
+    to_host: Vec<u8>,
+    to_console: VecDeque<u8>,
+    next_rx_tick: u64,
+    host_break: Arc<AtomicBool>,
+}
+
+impl<S: Read + Write> SerialBridge<S> {
+    #[must_use]
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            settings: None,
+            to_host: vec![],
+            to_console: VecDeque::new(),
+            next_rx_tick: 0,
+            host_break: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Plugs the bridge in the console redeye port.
+    pub fn attach(&mut self, lynx: &mut Lynx) {
+        lynx.add_comlynx_device(Box::new(HostBreak(self.host_break.clone())));
+    }
+
+    /// Exchanges the pending bytes, returns `true` when the line settings changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the stream errors.
+    pub fn pump(&mut self, lynx: &mut Lynx) -> io::Result<bool> {
+        let now = lynx.mikey().ticks();
+        let settings = SerialSettings::from_lynx(lynx);
+        let changed = self.settings != Some(settings);
+        self.settings = Some(settings);
+
+        while let Some((_, data)) = lynx.comlynx_ext_tx() {
+            self.to_host.push(data);
+        }
+        self.flush()?;
+        self.read()?;
+
+        while now >= self.next_rx_tick {
+            let Some(data) = self.to_console.pop_front() else {
+                break;
+            };
+            lynx.comlynx_ext_rx(data);
+            self.next_rx_tick = now + FRAME_BITS * settings.bit_ticks();
+        }
+        Ok(changed)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.to_host.is_empty() {
+            match self.stream.write(&self.to_host) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.to_host.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        self.stream.flush()
+    }
+
+    fn read(&mut self) -> io::Result<()> {
+        let mut buffer = [0; READ_LEN];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => self.to_console.extend(&buffer[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Settings seen at the last `pump`.
+    #[must_use]
+    pub fn settings(&self) -> Option<SerialSettings> {
+        self.settings
+    }
+
+    /// The host sends a break, the console sees the line `Low`. Needs `attach`.
+    pub fn set_host_break(&self, line_break: bool) {
+        self.host_break.store(line_break, Ordering::Release);
+    }
+
+    /// Host bytes not handed to the console yet.
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.to_console.len()
+    }
+
+    pub fn stream_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rom::lynx_sending;
+
+    /// Host side of the line, what it sends is read by the bridge.
+    #[derive(Default)]
+    struct Host {
+        sent: VecDeque<u8>,
+        received: Vec<u8>,
+    }
+
+    impl Read for Host {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.sent.is_empty() {
+                return Err(ErrorKind::WouldBlock.into());
+            }
+            let n = buf.len().min(self.sent.len());
+            for (b, data) in buf.iter_mut().zip(self.sent.drain(..n)) {
+                *b = data;
+            }
+            Ok(n)
+        }
+    }
+
+    impl Write for Host {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.received.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn bridge() {
+        let mut lynx = lynx_sending(&[0x12, 0x34]);
+        let mut bridge = SerialBridge::new(Host {
+            sent: VecDeque::from([0xAA, 0xBB, 0xCC]),
+            received: vec![],
+        });
+        bridge.attach(&mut lynx);
+
+        let mut changes = 0;
+        for _ in 0..200 {
+            for _ in 0..256 {
+                lynx.tick();
+            }
+            changes += usize::from(bridge.pump(&mut lynx).unwrap());
+        }
+
+        let settings = bridge.settings().unwrap();
+        assert_eq!(settings.baud, 62_500);
+        assert_eq!(settings.parity, SerialParity::Even);
+        assert!(!settings.line_break);
+        assert!(changes >= 2);
+        assert_eq!(bridge.stream_mut().received, [0x12, 0x34]);
+        assert_eq!(bridge.pending(), 0);
+        assert_eq!(lynx.mikey().uart().received().1, 0xCC);
+    }
+
+    #[test]
+    fn host_break() {
+        let mut lynx = lynx_sending(&[]);
+        let mut bridge = SerialBridge::new(Host::default());
+        bridge.attach(&mut lynx);
+        bridge.set_host_break(true);
+        for _ in 0..20_000 {
+            lynx.tick();
+        }
+        assert_ne!(lynx.mikey().registers().serctl() & 0x02, 0);
+        bridge.set_host_break(false);
+        for _ in 0..2_000 {
+            lynx.tick();
+        }
+        assert_eq!(lynx.mikey().registers().serctl() & 0x02, 0);
+    }
+}
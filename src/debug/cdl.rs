@@ -0,0 +1,162 @@
+use crate::alloc::vec::Vec;
+use crate::ram::RAM_MAX;
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct CdlFlags: u8 {
+        const opcode = 0b0000_0001;
+        const operand = 0b0000_0010;
+        const data = 0b0000_0100;
+        const sprite = 0b0000_1000;
+        const video = 0b0001_0000;
+    }
+}
+
+/// Code/Data Logger.
+///
+/// Keeps one byte of [`CdlFlags`] per RAM address and per cartridge data address
+/// (`Cartridge::data_address`). Flags are only ever added, so the maps can be
+/// accumulated over several sessions with `merge_ram_map`/`merge_cart_map`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CodeDataLogger {
+    ram: Vec<u8>,
+    cart: Vec<u8>,
+}
+
+impl CodeDataLogger {
+    #[must_use]
+    pub fn new(cart_size: usize) -> Self {
+        Self {
+            ram: vec![0; RAM_MAX as usize + 1],
+            cart: vec![0; cart_size],
+        }
+    }
+
+    #[inline]
+    pub fn log_ram(&mut self, addr: u16, flags: CdlFlags) {
+        self.ram[addr as usize] |= flags.bits();
+    }
+
+    #[inline]
+    pub fn log_cart(&mut self, addr: usize, flags: CdlFlags) {
+        if addr >= self.cart.len() {
+            self.cart.resize(addr + 1, 0);
+        }
+        self.cart[addr] |= flags.bits();
+    }
+
+    #[must_use]
+    pub fn ram_flags(&self, addr: u16) -> CdlFlags {
+        CdlFlags::from_bits_truncate(self.ram[addr as usize])
+    }
+
+    #[must_use]
+    pub fn cart_flags(&self, addr: usize) -> CdlFlags {
+        CdlFlags::from_bits_truncate(self.cart.get(addr).copied().unwrap_or_default())
+    }
+
+    /// One [`CdlFlags`] byte per RAM address, 64K long.
+    #[must_use]
+    pub fn ram_map(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// One [`CdlFlags`] byte per cartridge data address.
+    #[must_use]
+    pub fn cart_map(&self) -> &[u8] {
+        &self.cart
+    }
+
+    pub fn merge_ram_map(&mut self, map: &[u8]) {
+        self.ram
+            .iter_mut()
+            .zip(map)
+            .for_each(|(flags, other)| *flags |= other);
+    }
+
+    pub fn merge_cart_map(&mut self, map: &[u8]) {
+        if map.len() > self.cart.len() {
+            self.cart.resize(map.len(), 0);
+        }
+        self.cart
+            .iter_mut()
+            .zip(map)
+            .for_each(|(flags, other)| *flags |= other);
+    }
+
+    pub fn clear(&mut self) {
+        self.ram.fill(0);
+        self.cart.fill(0);
+    }
+}
+
+impl Default for CodeDataLogger {
+    fn default() -> Self {
+        CodeDataLogger::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rom::lynx_running;
+
+    #[test]
+    fn merge() {
+        let mut cdl = CodeDataLogger::new(4);
+        cdl.log_ram(0x0200, CdlFlags::opcode);
+        cdl.log_cart(6, CdlFlags::data);
+        let mut other = CodeDataLogger::new(2);
+        other.log_ram(0x0200, CdlFlags::data);
+        other.log_cart(1, CdlFlags::sprite);
+
+        cdl.merge_ram_map(other.ram_map());
+        cdl.merge_cart_map(other.cart_map());
+        assert_eq!(cdl.ram_flags(0x0200), CdlFlags::opcode | CdlFlags::data);
+        assert_eq!(cdl.cart_map(), [0, 0x08, 0, 0, 0, 0, 0x04]);
+        assert_eq!(cdl.cart_flags(100), CdlFlags::empty());
+        cdl.clear();
+        assert!(cdl.ram_map().iter().chain(cdl.cart_map()).all(|&f| f == 0));
+    }
+
+    #[test]
+    fn dummy_reads() {
+        let mut lynx = lynx_running(&[0x4C, 0x00, 0x02]); // JMP $0200
+        let program: [(u16, &[u8]); 3] = [
+            // NOP, JSR $0210, JMP *
+            (0x0200, &[0xEA, 0x20, 0x10, 0x02, 0x4C, 0x04, 0x02]),
+            // LDA #$5A, PHA, PLA, LDA $0220, RTS
+            (0x0210, &[0xA9, 0x5A, 0x48, 0x68, 0xAD, 0x20, 0x02, 0x60]),
+            (0x0220, &[0x42]),
+        ];
+        for (addr, bytes) in program {
+            for (addr, &data) in (addr..).zip(bytes) {
+                lynx.ram_mut().set(addr, data);
+            }
+        }
+        lynx.set_cdl_enabled(true);
+        for _ in 0..2_000 {
+            lynx.tick();
+        }
+
+        let cdl = lynx.cdl().unwrap();
+        let flags = |addr| cdl.ram_flags(addr);
+        for addr in [0x0200, 0x0201, 0x0204, 0x0213, 0x0214, 0x0217] {
+            assert_eq!(flags(addr), CdlFlags::opcode, "{addr:04x}");
+        }
+        // the return address read by RTS
+        assert_eq!(flags(0x0203), CdlFlags::operand);
+        assert_eq!(flags(0x0218), CdlFlags::empty());
+        assert_eq!(flags(0x0220), CdlFlags::data);
+
+        let s = lynx.mikey().cpu().s();
+        let stack = |offset: u8| 0x0100 | u16::from(s.wrapping_sub(offset));
+        for addr in [stack(0), stack(1), stack(2)] {
+            assert_eq!(flags(addr), CdlFlags::data, "{addr:04x}");
+        }
+        // below the pulled bytes, only read by PLA as a dummy cycle
+        assert_eq!(flags(stack(3)), CdlFlags::empty());
+    }
+}
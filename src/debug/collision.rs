@@ -0,0 +1,124 @@
+use crate::alloc::vec::Vec;
+use crate::debug::sprite::Palette;
+use crate::mikey::video::{LYNX_SCREEN_WIDTH, SCREEN_BUFFER_LEN};
+use crate::ram::Ram;
+use serde::{Deserialize, Serialize};
+
+pub use crate::suzy::sprite_log::SpriteCollision;
+
+/// Collision buffer and depositories of a frame.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollisionFrame {
+    /// `COLLBAS` at the end of the frame.
+    pub base: u16,
+    /// 160x102 collision numbers, one per pixel.
+    pub buffer: Vec<u8>,
+    /// Sprites in drawing order.
+    pub sprites: Vec<SpriteCollision>,
+}
+
+impl CollisionFrame {
+    #[must_use]
+    pub fn capture(ram: &Ram, base: u16, sprites: &[SpriteCollision]) -> Self {
+        let buffer = (0..SCREEN_BUFFER_LEN)
+            .map(|i| {
+                let data = ram.get(base.wrapping_add((i / 2) as u16));
+                if i & 1 == 0 {
+                    data >> 4
+                } else {
+                    data & 0x0F
+                }
+            })
+            .collect();
+        Self {
+            base,
+            buffer,
+            sprites: sprites.to_vec(),
+        }
+    }
+
+    #[must_use]
+    pub fn number(&self, x: usize, y: usize) -> u8 {
+        self.buffer[y * LYNX_SCREEN_WIDTH as usize + x]
+    }
+
+    /// RGBA image of the buffer, one palette entry per collision number.
+    #[must_use]
+    pub fn rgba(&self, palette: &Palette) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.buffer.len() * 4);
+        for &number in &self.buffer {
+            rgba.extend_from_slice(&palette[number as usize]);
+            rgba.push(0xFF);
+        }
+        rgba
+    }
+}
+
+/// Keeps the collision results of the last complete frame.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CollisionMonitor {
+    last_frame: CollisionFrame,
+}
+
+impl CollisionMonitor {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            last_frame: CollisionFrame::default(),
+        }
+    }
+
+    pub fn end_frame(&mut self, ram: &Ram, base: u16, sprites: &[SpriteCollision]) {
+        self.last_frame = CollisionFrame::capture(ram, base, sprites);
+    }
+
+    #[must_use]
+    pub fn last_frame(&self) -> &CollisionFrame {
+        &self.last_frame
+    }
+}
+
+impl Default for CollisionMonitor {
+    fn default() -> Self {
+        CollisionMonitor::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lynx::Lynx;
+    use crate::test_rom::{draw_sprite, lynx_drawing, SCB};
+
+    /// A 2x2 sprite with the collision number 3, over a collision buffer holding 5 at its
+    /// top left pixel.
+    fn draw(lynx: &mut Lynx) {
+        let ram = lynx.ram_mut();
+        ram.copy(0x4000, &[0; 102 * 80]);
+        ram.set(0x4000 + 10 * 80 + 5, 0x50);
+        // 1 bpp, literal
+        draw_sprite(lynx, 0x90, 3, 1, |_| ());
+    }
+
+    #[test]
+    fn depositories_after_reset() {
+        let mut lynx = lynx_drawing(SCB);
+        lynx.set_collisions_enabled(true);
+        draw(&mut lynx);
+        let expected = [SpriteCollision {
+            scb: SCB,
+            number: 3,
+            depository: 5,
+        }];
+        assert_eq!(lynx.suzy().collision_log(), expected);
+        let frame = CollisionFrame::capture(lynx.ram(), 0x4000, lynx.suzy().collision_log());
+        assert_eq!(frame.number(10, 10), 3);
+        assert_eq!(frame.number(11, 11), 3);
+        assert_eq!(frame.number(12, 10), 0);
+        assert_eq!(frame.number(10, 9), 0);
+
+        lynx.reset();
+        draw(&mut lynx);
+        assert_eq!(lynx.suzy().collision_log(), expected);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::alloc::collections::VecDeque;
+use crate::alloc::string::String;
+use crate::alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// An address no Mikey register uses, between `DISPADR` and `MTEST0`.
+pub const DEBUG_PORT_DEFAULT_ADDRESS: u16 = 0xFD97;
+/// Text kept by the debug port, the oldest bytes are dropped.
+pub const DEBUG_PORT_LOG_LEN: usize = 64 * 1024;
+
+/// Control byte `BEL`, requests a breakpoint.
+pub const DEBUG_PORT_BREAKPOINT: u8 = 0x07;
+/// `FF`, requests a screenshot.
+pub const DEBUG_PORT_SCREENSHOT: u8 = 0x0C;
+/// `ACK`, the test passed, exit code 0.
+pub const DEBUG_PORT_PASS: u8 = 0x06;
+/// `NAK`, the test failed, the next byte is the exit code.
+pub const DEBUG_PORT_FAIL: u8 = 0x15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DebugPortTrigger {
+    /// Bytes written by the CPU to this address, e.g. `DEBUG_PORT_DEFAULT_ADDRESS`.
+    Address(u16),
+    /// `BRK` followed by this signature byte sends the accumulator. The `BRK` then goes
+    /// through the IRQ vector as on the hardware, the handler returns after the signature.
+    Brk(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DebugSignalKind {
+    Breakpoint,
+    Screenshot,
+    /// End of a test, 0 when it passed.
+    Exit(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebugSignal {
+    pub tick: u64,
+    pub pc: u16,
+    pub kind: DebugSignalKind,
+}
+
+/// Virtual port homebrew programs can print to and signal the host through.
+///
+/// Bytes are text, except the `DEBUG_PORT_*` control bytes. Not a hardware feature, the
+/// console only gets one when it is enabled.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DebugPort {
+    trigger: DebugPortTrigger,
+    log: VecDeque<u8>,
+    signals: VecDeque<DebugSignal>,
+    exit: Option<u8>,
+    fail_pending: bool,
+    screenshot: Option<Vec<u8>>,
+}
+
+impl DebugPort {
+    #[must_use]
+    pub fn new(trigger: DebugPortTrigger) -> Self {
+        Self {
+            trigger,
+            log: VecDeque::new(),
+            signals: VecDeque::new(),
+            exit: None,
+            fail_pending: false,
+            screenshot: None,
+        }
+    }
+
+    #[must_use]
+    pub fn trigger(&self) -> DebugPortTrigger {
+        self.trigger
+    }
+
+    /// A byte sent by the program, returns the signal it raised.
+    pub fn write(&mut self, tick: u64, pc: u16, data: u8) -> Option<DebugSignalKind> {
+        let kind = if self.fail_pending {
+            self.fail_pending = false;
+            DebugSignalKind::Exit(data)
+        } else {
+            match data {
+                DEBUG_PORT_BREAKPOINT => DebugSignalKind::Breakpoint,
+                DEBUG_PORT_SCREENSHOT => DebugSignalKind::Screenshot,
+                DEBUG_PORT_PASS => DebugSignalKind::Exit(0),
+                DEBUG_PORT_FAIL => {
+                    self.fail_pending = true;
+                    return None;
+                }
+                _ => {
+                    if self.log.len() == DEBUG_PORT_LOG_LEN {
+                        self.log.pop_front();
+                    }
+                    self.log.push_back(data);
+                    return None;
+                }
+            }
+        };
+        if let DebugSignalKind::Exit(code) = kind {
+            self.exit = Some(code);
+        }
+        self.signals.push_back(DebugSignal { tick, pc, kind });
+        Some(kind)
+    }
+
+    /// Text bytes sent so far.
+    #[must_use]
+    pub fn log(&self) -> &VecDeque<u8> {
+        &self.log
+    }
+
+    /// The log as text, invalid UTF-8 replaced.
+    #[must_use]
+    pub fn text(&self) -> String {
+        let log: Vec<u8> = self.log.iter().copied().collect();
+        String::from_utf8_lossy(&log).into_owned()
+    }
+
+    pub fn clear_log(&mut self) {
+        self.log.clear();
+    }
+
+    /// Signals not taken yet.
+    #[must_use]
+    pub fn signals(&self) -> &VecDeque<DebugSignal> {
+        &self.signals
+    }
+
+    pub fn take_signal(&mut self) -> Option<DebugSignal> {
+        self.signals.pop_front()
+    }
+
+    /// Exit code of the last pass or fail signal.
+    #[must_use]
+    pub fn exit_code(&self) -> Option<u8> {
+        self.exit
+    }
+
+    /// RGBA screen at the last screenshot request.
+    #[must_use]
+    pub fn screenshot(&self) -> Option<&[u8]> {
+        self.screenshot.as_deref()
+    }
+
+    pub(crate) fn set_screenshot(&mut self, screen: Vec<u8>) {
+        self.screenshot = Some(screen);
+    }
+}
+
+impl Default for DebugPort {
+    fn default() -> Self {
+        DebugPort::new(DebugPortTrigger::Address(DEBUG_PORT_DEFAULT_ADDRESS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rom::{lynx_running, store};
+
+    #[test]
+    fn control_bytes() {
+        let mut port = DebugPort::default();
+        for (tick, &data) in (0..).zip(b"ok\n\x07\x0C\x15\x2A\x15\x07\x06") {
+            port.write(tick, 0x200, data);
+        }
+        assert_eq!(port.text(), "ok\n");
+        let kinds: Vec<DebugSignalKind> = port.signals().iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DebugSignalKind::Breakpoint,
+                DebugSignalKind::Screenshot,
+                DebugSignalKind::Exit(0x2A),
+                DebugSignalKind::Exit(0x07),
+                DebugSignalKind::Exit(0),
+            ]
+        );
+        assert_eq!(port.signals()[2].tick, 6);
+        assert_eq!(port.exit_code(), Some(0));
+    }
+
+    #[test]
+    fn address() {
+        let mut code = vec![];
+        for &data in b"Hello\n\x0C\x06" {
+            store(&mut code, DEBUG_PORT_DEFAULT_ADDRESS, data);
+        }
+        let mut lynx = lynx_running(&code);
+        lynx.set_debug_port_enabled(true, DebugPortTrigger::Address(DEBUG_PORT_DEFAULT_ADDRESS));
+        for _ in 0..2_000 {
+            lynx.tick();
+        }
+        let port = lynx.debug_port().unwrap();
+        assert_eq!(port.text(), "Hello\n");
+        assert_eq!(port.exit_code(), Some(0));
+        assert_eq!(port.signals()[0].kind, DebugSignalKind::Screenshot);
+        assert_eq!(port.screenshot().unwrap().len(), lynx.screen_rgba().len());
+    }
+
+    #[test]
+    fn brk() {
+        let mut code = vec![];
+        for &data in b"BRK" {
+            // LDA #data, BRK, signature
+            code.extend_from_slice(&[0xA9, data, 0x00, 0x5A]);
+        }
+        // NAK 3
+        code.extend_from_slice(&[0xA9, 0x15, 0x00, 0x5A, 0xA9, 0x03, 0x00, 0x5A]);
+        let mut lynx = lynx_running(&code);
+        lynx.set_debug_port_enabled(true, DebugPortTrigger::Brk(0x5A));
+        for _ in 0..2_000 {
+            lynx.tick();
+        }
+        let port = lynx.debug_port().unwrap();
+        assert_eq!(port.text(), "BRK");
+        assert_eq!(port.exit_code(), Some(3));
+        assert_eq!(port.signals()[0].pc, 0xFE12);
+
+        let mut lynx = lynx_running(&code);
+        for _ in 0..2_000 {
+            lynx.tick();
+        }
+        assert!(lynx.debug_port().is_none());
+    }
+}
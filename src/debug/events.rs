@@ -0,0 +1,304 @@
+use crate::alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Events kept per frame, the extra ones are dropped (e.g. when the display timers are stopped).
+pub const EVENT_LOG_FRAME_CAPACITY: usize = 0x10000;
+
+const HSYNC_TIMER: u8 = 0;
+const VSYNC_TIMER: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventKind {
+    /// Timer id, audio timers are 8 to 11.
+    TimerUnderflow(u8),
+    /// `INTSET` bits raised.
+    Interrupt(u8),
+    Hsync,
+    Vsync,
+    /// Start address of the fetch.
+    VideoDma(u16),
+    SpriteStart,
+    SpriteEnd,
+    CpuSleep,
+    CpuWake,
+    UartTx(u8),
+    UartRx(u8),
+    CartRead {
+        bank: u8,
+        address: usize,
+    },
+    CartWrite {
+        bank: u8,
+        address: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub tick: u64,
+    /// Line counted from the last vsync.
+    pub scanline: u16,
+    /// Ticks since the last hsync.
+    pub tick_in_line: u32,
+    pub kind: EventKind,
+}
+
+/// State of the event sources on one tick, the log turns the changes into events.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct EventSample {
+    pub timer_underflows: u16,
+    pub interrupts: u8,
+    pub video_dma: Option<u16>,
+    pub sprite_working: bool,
+    pub cpu_asleep: bool,
+    pub uart_transmitted: (u64, u8),
+    pub uart_received: (u64, u8),
+    pub cart: Option<EventKind>,
+}
+
+/// Per frame timed events log, for raster timing diagrams.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EventLog {
+    frame: Vec<Event>,
+    last_frame: Vec<Event>,
+    dropped: usize,
+    scanline: u16,
+    line_start: u64,
+    #[serde(skip)]
+    previous: Option<EventSample>,
+}
+
+impl EventLog {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            frame: vec![],
+            last_frame: vec![],
+            dropped: 0,
+            scanline: 0,
+            line_start: 0,
+            previous: None,
+        }
+    }
+
+    fn push(&mut self, tick: u64, kind: EventKind) {
+        if self.frame.len() >= EVENT_LOG_FRAME_CAPACITY {
+            self.dropped += 1;
+            return;
+        }
+        self.frame.push(Event {
+            tick,
+            scanline: self.scanline,
+            tick_in_line: u32::try_from(tick - self.line_start).unwrap_or(u32::MAX),
+            kind,
+        });
+    }
+
+    pub fn tick(&mut self, tick: u64, sample: &EventSample) {
+        let Some(previous) = self.previous.replace(*sample) else {
+            return;
+        };
+
+        if sample.timer_underflows != 0 {
+            for id in 0..16 {
+                if sample.timer_underflows & (1 << id) != 0 {
+                    self.push(tick, EventKind::TimerUnderflow(id));
+                }
+            }
+            let hsync = sample.timer_underflows & (1 << HSYNC_TIMER) != 0;
+            if hsync {
+                self.push(tick, EventKind::Hsync);
+            }
+            if sample.timer_underflows & (1 << VSYNC_TIMER) != 0 {
+                self.push(tick, EventKind::Vsync);
+                self.scanline = 0;
+                self.line_start = tick;
+            } else if hsync {
+                self.scanline = self.scanline.saturating_add(1);
+                self.line_start = tick;
+            }
+        }
+        if sample.interrupts != 0 {
+            self.push(tick, EventKind::Interrupt(sample.interrupts));
+        }
+        if let Some(addr) = sample.video_dma {
+            self.push(tick, EventKind::VideoDma(addr));
+        }
+        if sample.sprite_working != previous.sprite_working {
+            let kind = if sample.sprite_working {
+                EventKind::SpriteStart
+            } else {
+                EventKind::SpriteEnd
+            };
+            self.push(tick, kind);
+        }
+        if sample.cpu_asleep != previous.cpu_asleep {
+            let kind = if sample.cpu_asleep {
+                EventKind::CpuSleep
+            } else {
+                EventKind::CpuWake
+            };
+            self.push(tick, kind);
+        }
+        if sample.uart_transmitted.0 != previous.uart_transmitted.0 {
+            self.push(tick, EventKind::UartTx(sample.uart_transmitted.1));
+        }
+        if sample.uart_received.0 != previous.uart_received.0 {
+            self.push(tick, EventKind::UartRx(sample.uart_received.1));
+        }
+        if let Some(kind) = sample.cart {
+            self.push(tick, kind);
+        }
+    }
+
+    /// Ends the frame with the video one, as the other monitors do.
+    pub fn end_frame(&mut self) {
+        self.last_frame = core::mem::take(&mut self.frame);
+        self.dropped = 0;
+    }
+
+    /// Events of the frame in progress.
+    #[must_use]
+    pub fn frame(&self) -> &[Event] {
+        &self.frame
+    }
+
+    /// Events of the last complete frame.
+    #[must_use]
+    pub fn last_frame(&self) -> &[Event] {
+        &self.last_frame
+    }
+
+    /// Events dropped from the frame in progress.
+    #[must_use]
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn clear(&mut self) {
+        let previous = self.previous.take();
+        *self = Self::new();
+        self.previous = previous;
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        EventLog::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::{CPUSLEEP, SUZYBUSEN, TIM0BKUP, TIM0CTLA, TIM2BKUP, TIM2CTLA};
+    use crate::lynx::Lynx;
+    use crate::test_rom::{lynx_running, store};
+
+    fn sleep_events(lynx: &mut Lynx, ticks: u32) -> Vec<Event> {
+        lynx.set_events_enabled(true);
+        for _ in 0..ticks {
+            lynx.tick();
+        }
+        let events = lynx.events().unwrap();
+        events
+            .last_frame()
+            .iter()
+            .chain(events.frame())
+            .filter(|e| matches!(e.kind, EventKind::CpuSleep | EventKind::CpuWake))
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn sleep_through_video_dma() {
+        let mut code = vec![];
+        // Suzy off the bus, nothing wakes the CPU up
+        store(&mut code, SUZYBUSEN, 0);
+        store(&mut code, CPUSLEEP, 0);
+        let mut lynx = lynx_running(&code);
+        let events = sleep_events(&mut lynx, 20_000);
+        assert!(lynx.mikey().cpu_asleep());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::CpuSleep);
+    }
+
+    #[test]
+    fn wake_on_interrupt() {
+        let mut code = vec![];
+        store(&mut code, TIM0BKUP, 0x80);
+        store(&mut code, TIM0CTLA, 0x98); // interrupt, reload, count, 1us
+        store(&mut code, CPUSLEEP, 0);
+        let mut lynx = lynx_running(&code);
+        let events = sleep_events(&mut lynx, 2_000);
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds[..2], [EventKind::CpuSleep, EventKind::CpuWake]);
+        // 128 us at 4 ticks per us
+        assert!(events[1].tick - events[0].tick < 4 * 129);
+        assert!(!lynx.mikey().cpu_asleep());
+    }
+
+    #[test]
+    fn frames_end_with_video() {
+        let mut code = vec![];
+        store(&mut code, TIM0BKUP, 0x9E);
+        store(&mut code, TIM0CTLA, 0x18); // reload, count, 1us
+        store(&mut code, TIM2BKUP, 0x68);
+        store(&mut code, TIM2CTLA, 0x1F); // reload, count, linked
+        let mut lynx = lynx_running(&code);
+        lynx.set_events_enabled(true);
+        let mut frame_ticks = vec![];
+        while frame_ticks.len() < 2 {
+            let frame_count = lynx.mikey().video().frame_count();
+            lynx.tick();
+            if lynx.mikey().video().frame_count() != frame_count {
+                frame_ticks.push(lynx.mikey().ticks());
+            }
+        }
+        // the ticks after the video frame count changed, up to the next change
+        let events = lynx.events().unwrap().last_frame();
+        assert!(events
+            .iter()
+            .all(|e| e.tick > frame_ticks[0] && e.tick <= frame_ticks[1]));
+        assert_eq!(events.last().unwrap().tick, frame_ticks[1]);
+        assert_eq!(
+            events.iter().filter(|e| e.kind == EventKind::Vsync).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn scanlines() {
+        let mut log = EventLog::new();
+        let sample = |timer_underflows, cpu_asleep| EventSample {
+            timer_underflows,
+            cpu_asleep,
+            ..EventSample::default()
+        };
+        log.tick(100, &sample(0, false));
+        log.tick(110, &sample(1 << HSYNC_TIMER, false));
+        log.tick(115, &sample(0, true));
+        log.tick(116, &sample(0, true));
+
+        let events = log.frame();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].kind, EventKind::TimerUnderflow(HSYNC_TIMER));
+        assert_eq!(events[1].kind, EventKind::Hsync);
+        assert_eq!(events[1].scanline, 0);
+        assert_eq!(
+            (events[2].kind, events[2].scanline, events[2].tick_in_line),
+            (EventKind::CpuSleep, 1, 5)
+        );
+
+        log.tick(120, &sample(1 << VSYNC_TIMER, true));
+        log.end_frame();
+        assert_eq!(log.last_frame().len(), 5);
+        assert_eq!(log.last_frame()[4].kind, EventKind::Vsync);
+        log.tick(121, &sample(0, false));
+        let wake = log.frame()[0];
+        assert_eq!(
+            (wake.kind, wake.scanline, wake.tick_in_line),
+            (EventKind::CpuWake, 0, 1)
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::alloc::collections::VecDeque;
+use crate::alloc::vec::Vec;
+use crate::ram::RAM_MAX;
+use serde::{Deserialize, Serialize};
+
+/// Self-modifying writes kept by the heatmap, the oldest ones are dropped.
+pub const HEATMAP_SMC_LOG_LEN: usize = 4096;
+/// Default heat kept from one frame to the next, out of 256.
+pub const HEATMAP_DEFAULT_DECAY: u8 = 224;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemoryAccess {
+    Read = 0,
+    Write = 1,
+    /// Opcode and operand fetches.
+    Execute = 2,
+}
+
+const RGBA_CHANNELS: [usize; 3] = [
+    MemoryAccess::Write as usize,
+    MemoryAccess::Read as usize,
+    MemoryAccess::Execute as usize,
+];
+
+/// A write into an address that was executed before.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmcWrite {
+    pub tick: u64,
+    /// Instruction that did the write.
+    pub pc: u16,
+    pub addr: u16,
+    pub data: u8,
+}
+
+/// CPU memory access heatmap over the whole 64K space.
+///
+/// Keeps running totals per address and access type plus a heat value that
+/// decays at every frame, and flags the executed addresses that got written
+/// to afterwards (self-modifying code, code decompressed over older code).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MemoryHeatmap {
+    totals: [Vec<u32>; 3],
+    heat: [Vec<u32>; 3],
+    executed: Vec<bool>,
+    modified: Vec<bool>,
+    smc_writes: VecDeque<SmcWrite>,
+    decay: u8,
+}
+
+impl MemoryHeatmap {
+    #[must_use]
+    pub fn new() -> Self {
+        let len = RAM_MAX as usize + 1;
+        Self {
+            totals: [vec![0; len], vec![0; len], vec![0; len]],
+            heat: [vec![0; len], vec![0; len], vec![0; len]],
+            executed: vec![false; len],
+            modified: vec![false; len],
+            smc_writes: VecDeque::new(),
+            decay: HEATMAP_DEFAULT_DECAY,
+        }
+    }
+
+    #[inline]
+    pub fn log(&mut self, addr: u16, access: MemoryAccess) {
+        let a = addr as usize;
+        let t = access as usize;
+        self.totals[t][a] = self.totals[t][a].saturating_add(1);
+        self.heat[t][a] = self.heat[t][a].saturating_add(1);
+        if access == MemoryAccess::Execute {
+            self.executed[a] = true;
+        }
+    }
+
+    #[inline]
+    pub fn log_write(&mut self, tick: u64, pc: u16, addr: u16, data: u8) {
+        self.log(addr, MemoryAccess::Write);
+        if !self.executed[addr as usize] {
+            return;
+        }
+        self.modified[addr as usize] = true;
+        if self.smc_writes.len() == HEATMAP_SMC_LOG_LEN {
+            self.smc_writes.pop_front();
+        }
+        self.smc_writes.push_back(SmcWrite {
+            tick,
+            pc,
+            addr,
+            data,
+        });
+    }
+
+    /// Applies the frame decay to the heat values.
+    pub fn end_frame(&mut self) {
+        let decay = u64::from(self.decay);
+        for heat in self.heat.iter_mut().flatten().filter(|h| **h != 0) {
+            *heat = ((u64::from(*heat) * decay) >> 8) as u32;
+        }
+    }
+
+    /// Heat kept from one frame to the next, out of 256.
+    pub fn set_decay(&mut self, decay: u8) {
+        self.decay = decay;
+    }
+
+    #[must_use]
+    pub fn decay(&self) -> u8 {
+        self.decay
+    }
+
+    /// Accesses since the heatmap was created or cleared, one counter per address.
+    #[must_use]
+    pub fn totals(&self, access: MemoryAccess) -> &[u32] {
+        &self.totals[access as usize]
+    }
+
+    /// Decaying heat, one value per address.
+    #[must_use]
+    pub fn heat(&self, access: MemoryAccess) -> &[u32] {
+        &self.heat[access as usize]
+    }
+
+    #[must_use]
+    pub fn executed(&self, addr: u16) -> bool {
+        self.executed[addr as usize]
+    }
+
+    /// `true` if the address was written to after being executed.
+    #[must_use]
+    pub fn self_modified(&self, addr: u16) -> bool {
+        self.modified[addr as usize]
+    }
+
+    /// The last [`HEATMAP_SMC_LOG_LEN`] writes into executed addresses, oldest first.
+    pub fn smc_writes(&self) -> impl Iterator<Item = &SmcWrite> {
+        self.smc_writes.iter()
+    }
+
+    /// 256x256 RGBA image of the heat, one pixel per address, one line per page.
+    /// Red is writes, green reads and blue executes, each scaled to its hottest address.
+    #[must_use]
+    pub fn rgba(&self) -> Vec<u8> {
+        let max = self
+            .heat
+            .each_ref()
+            .map(|h| u64::from(h.iter().copied().max().unwrap_or(0).max(1)));
+        let mut rgba = Vec::with_capacity(self.executed.len() * 4);
+        for a in 0..self.executed.len() {
+            for t in RGBA_CHANNELS {
+                rgba.push((u64::from(self.heat[t][a]) * 0xFF / max[t]) as u8);
+            }
+            rgba.push(0xFF);
+        }
+        rgba
+    }
+
+    pub fn clear(&mut self) {
+        let decay = self.decay;
+        *self = Self::new();
+        self.decay = decay;
+    }
+}
+
+impl Default for MemoryHeatmap {
+    fn default() -> Self {
+        MemoryHeatmap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rom::lynx_running;
+
+    #[test]
+    fn decay_and_smc() {
+        let mut heatmap = MemoryHeatmap::new();
+        heatmap.set_decay(128);
+        for _ in 0..4 {
+            heatmap.log(0x0200, MemoryAccess::Execute);
+        }
+        heatmap.log_write(10, 0x0300, 0x0201, 0x00);
+        heatmap.log_write(11, 0x0300, 0x0200, 0xEA);
+        heatmap.end_frame();
+
+        assert_eq!(heatmap.totals(MemoryAccess::Execute)[0x0200], 4);
+        assert_eq!(heatmap.heat(MemoryAccess::Execute)[0x0200], 2);
+        assert_eq!(heatmap.heat(MemoryAccess::Write)[0x0201], 0);
+        assert!(!heatmap.self_modified(0x0201));
+        assert!(heatmap.self_modified(0x0200));
+        let smc: Vec<_> = heatmap.smc_writes().copied().collect();
+        assert_eq!(
+            smc,
+            [SmcWrite {
+                tick: 11,
+                pc: 0x0300,
+                addr: 0x0200,
+                data: 0xEA
+            }]
+        );
+
+        let rgba = heatmap.rgba();
+        assert_eq!(rgba[0x0200 * 4..0x0201 * 4], [0, 0, 0xFF, 0xFF]);
+        heatmap.clear();
+        assert_eq!(heatmap.decay(), 128);
+        assert!(!heatmap.executed(0x0200));
+    }
+
+    #[test]
+    fn dummy_reads() {
+        // JMP $0200
+        let mut lynx = lynx_running(&[0x4C, 0x00, 0x02]);
+        // NOP, PHA, PLA, LDA $0220, JMP *
+        let code = [0xEA, 0x48, 0x68, 0xAD, 0x20, 0x02, 0x4C, 0x06, 0x02];
+        for (addr, &data) in (0x0200..).zip(&code) {
+            lynx.ram_mut().set(addr, data);
+        }
+        lynx.set_heatmap_enabled(true);
+        // up to the JMP *
+        while lynx.mikey().cpu().last_ir_pc != 0x0206 {
+            lynx.tick();
+        }
+
+        let heatmap = lynx.heatmap().unwrap();
+        let reads = heatmap.totals(MemoryAccess::Read);
+        let executes = heatmap.totals(MemoryAccess::Execute);
+        // the dummy reads after NOP and PHA, PLA's first stack read
+        assert_eq!(executes[0x0201..0x0203], [1, 1]);
+        assert_eq!(reads[0x0201..0x0203], [0, 0]);
+        let s = lynx.mikey().cpu().s();
+        assert_eq!(reads[0x0100 | usize::from(s)], 1);
+        assert_eq!(reads[0x0100 | usize::from(s.wrapping_sub(1))], 0);
+        assert_eq!(reads[0x0220], 1);
+    }
+}
@@ -0,0 +1,12 @@
+pub mod cdl;
+pub mod collision;
+pub mod debug_port;
+pub mod events;
+pub mod heatmap;
+pub mod overlay;
+pub mod profiler;
+pub mod sanitizer;
+pub mod scb;
+pub mod snapshot;
+pub mod sprite;
+pub mod sprite_cost;
@@ -0,0 +1,110 @@
+use crate::alloc::vec::Vec;
+use crate::mikey::video::{LYNX_SCREEN_WIDTH, SCREEN_BUFFER_LEN};
+use serde::{Deserialize, Serialize};
+
+pub use crate::suzy::sprite_log::{ScreenBounds, SpriteDraw, SpriteType};
+
+/// Outline colour of each sprite type in the overlay.
+const OVERLAY_COLORS: [[u8; 3]; 8] = [
+    [0x80, 0x80, 0x80],
+    [0xC0, 0xC0, 0xC0],
+    [0xFF, 0x80, 0x00],
+    [0xFF, 0xFF, 0x00],
+    [0x00, 0xFF, 0x00],
+    [0x00, 0xFF, 0xFF],
+    [0xFF, 0x00, 0xFF],
+    [0x40, 0x40, 0xFF],
+];
+
+/// Keeps the sprites processed during the last complete frame.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpriteDrawLog {
+    last_frame: Vec<SpriteDraw>,
+}
+
+impl SpriteDrawLog {
+    #[must_use]
+    pub fn new() -> Self {
+        Self { last_frame: vec![] }
+    }
+
+    pub fn end_frame(&mut self, sprites: &[SpriteDraw]) {
+        self.last_frame = sprites.to_vec();
+    }
+
+    /// Sprites of the last complete frame, in drawing order.
+    #[must_use]
+    pub fn last_frame(&self) -> &[SpriteDraw] {
+        &self.last_frame
+    }
+
+    /// 160x102 RGBA overlay with the outline of every sprite of the last
+    /// complete frame, coloured by sprite type. The rest is transparent.
+    #[must_use]
+    pub fn overlay_rgba(&self) -> Vec<u8> {
+        let mut rgba = vec![0; SCREEN_BUFFER_LEN * 4];
+        let mut plot = |x: u8, y: u8, color: &[u8; 3]| {
+            let i = (y as usize * LYNX_SCREEN_WIDTH as usize + x as usize) * 4;
+            rgba[i..i + 3].copy_from_slice(color);
+            rgba[i + 3] = 0xFF;
+        };
+        for sprite in &self.last_frame {
+            let Some(b) = sprite.bounds else {
+                continue;
+            };
+            let color = &OVERLAY_COLORS[sprite.sprite_type as usize];
+            for x in b.left..=b.right {
+                plot(x, b.top, color);
+                plot(x, b.bottom, color);
+            }
+            for y in b.top..=b.bottom {
+                plot(b.left, y, color);
+                plot(b.right, y, color);
+            }
+        }
+        rgba
+    }
+}
+
+impl Default for SpriteDrawLog {
+    fn default() -> Self {
+        SpriteDrawLog::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lynx::Lynx;
+    use crate::test_rom::{draw_sprite, lynx_drawing, SCB};
+
+    /// A 2 lines sprite drawn without a palette loaded.
+    fn draw(lynx: &mut Lynx) {
+        // 1 bpp, literal, reuse palette
+        draw_sprite(lynx, 0x98, 0, 1, |_| ());
+    }
+
+    #[test]
+    fn draws_after_reset() {
+        let mut lynx = lynx_drawing(SCB);
+        lynx.set_sprite_draws_enabled(true);
+        draw(&mut lynx);
+        let expected = [SpriteDraw {
+            scb: SCB,
+            sprite_type: SpriteType::Normal,
+            quadrants: vec![0],
+            // a literal line of one byte is 7 pixels wide
+            bounds: Some(ScreenBounds {
+                left: 10,
+                top: 10,
+                right: 16,
+                bottom: 11,
+            }),
+        }];
+        assert_eq!(lynx.suzy().draw_log(), expected);
+
+        lynx.reset();
+        draw(&mut lynx);
+        assert_eq!(lynx.suzy().draw_log(), expected);
+    }
+}
@@ -0,0 +1,607 @@
+use crate::alloc::collections::VecDeque;
+use crate::alloc::string::String;
+use crate::alloc::vec::Vec;
+use crate::ram::RAM_MAX;
+use serde::{Deserialize, Serialize};
+
+const OPCODE_BRK: u8 = 0x00;
+const OPCODE_JSR: u8 = 0x20;
+const OPCODE_NOP: u8 = 0xEA;
+
+/// Number of past frames kept by the profiler.
+pub const PROFILER_FRAME_HISTORY: usize = 256;
+
+/// What the system was doing during a tick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProfilerActivity {
+    /// The CPU owned the bus.
+    Cpu,
+    /// The CPU was off the bus while Suzy was drawing sprites.
+    Suzy,
+    /// The CPU was held off the bus by a video DMA fetch.
+    VideoDma,
+    /// The CPU was asleep and Suzy was idle.
+    Sleep,
+}
+
+/// Ticks spent at one instruction address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcStats {
+    /// Instructions started at this address.
+    pub executions: u64,
+    /// All the ticks spent on the instruction, stalls included.
+    pub ticks: u64,
+    /// Ticks the instruction waited for the bus (Suzy or video DMA).
+    pub stalled_ticks: u64,
+    /// Ticks spent asleep on the instruction.
+    pub sleep_ticks: u64,
+    /// Ticks spent on the instruction while servicing an interrupt.
+    pub irq_ticks: u64,
+}
+
+/// Split of a frame's ticks between the bus users.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameTime {
+    pub cpu: u64,
+    pub suzy: u64,
+    pub video_dma: u64,
+    pub sleep: u64,
+}
+
+impl FrameTime {
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.cpu + self.suzy + self.video_dma + self.sleep
+    }
+
+    fn add(&mut self, activity: ProfilerActivity) {
+        match activity {
+            ProfilerActivity::Cpu => self.cpu += 1,
+            ProfilerActivity::Suzy => self.suzy += 1,
+            ProfilerActivity::VideoDma => self.video_dma += 1,
+            ProfilerActivity::Sleep => self.sleep += 1,
+        }
+    }
+}
+
+/// A node of the call tree. `function` is `None` for the root (code running outside any call).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CallNode {
+    pub function: Option<u16>,
+    pub interrupt: bool,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub calls: u64,
+    pub self_ticks: u64,
+}
+
+/// One line of the call tree export, in depth first order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CallTreeEntry {
+    pub depth: usize,
+    pub function: Option<u16>,
+    pub name: Option<String>,
+    pub interrupt: bool,
+    pub calls: u64,
+    pub self_ticks: u64,
+    pub total_ticks: u64,
+}
+
+/// One line of the flat profile.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FunctionStats {
+    pub address: u16,
+    pub name: Option<String>,
+    pub calls: u64,
+    pub self_ticks: u64,
+    pub total_ticks: u64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct StackFrame {
+    node: usize,
+    sp: u8,
+}
+
+/// Cycle accurate CPU profiler.
+///
+/// Every tick is charged to the instruction in flight, stalls and sleep included.
+/// Calls are followed through `JSR` and interrupts and unwound from the stack pointer,
+/// so `RTS`/`RTI` as well as stack tricks (`PLA PLA RTS`, `TXS`) are handled alike.
+/// Functions are the `JSR`/interrupt targets, or the enclosing symbols when some are
+/// provided with `set_symbols`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Profiler {
+    pcs: Vec<PcStats>,
+    symbols: Vec<(u16, String)>,
+    nodes: Vec<CallNode>,
+    stack: Vec<StackFrame>,
+    irq_depth: usize,
+    current_pc: u16,
+    current_ir: u8,
+    current_sp: u8,
+    frame: FrameTime,
+    frames: VecDeque<FrameTime>,
+}
+
+impl Profiler {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pcs: vec![PcStats::default(); RAM_MAX as usize + 1],
+            symbols: vec![],
+            nodes: vec![Self::root()],
+            stack: vec![],
+            irq_depth: 0,
+            current_pc: 0,
+            // neither a call nor an interrupt before the first instruction
+            current_ir: OPCODE_NOP,
+            current_sp: 0xFF,
+            frame: FrameTime::default(),
+            frames: VecDeque::new(),
+        }
+    }
+
+    fn root() -> CallNode {
+        CallNode {
+            function: None,
+            interrupt: false,
+            parent: None,
+            children: vec![],
+            calls: 0,
+            self_ticks: 0,
+        }
+    }
+
+    /// Clears the collected data, symbols are kept.
+    pub fn reset(&mut self) {
+        let symbols = core::mem::take(&mut self.symbols);
+        *self = Self::new();
+        self.symbols = symbols;
+    }
+
+    /// Sets the symbols used to group the addresses into functions. Each symbol
+    /// covers the addresses up to the next one.
+    pub fn set_symbols(&mut self, symbols: impl IntoIterator<Item = (u16, String)>) {
+        self.symbols = symbols.into_iter().collect();
+        self.symbols.sort_by_key(|(addr, _)| *addr);
+        self.symbols.dedup_by_key(|(addr, _)| *addr);
+    }
+
+    #[must_use]
+    pub fn symbols(&self) -> &[(u16, String)] {
+        &self.symbols
+    }
+
+    fn symbol_index(&self, addr: u16) -> Option<usize> {
+        match self.symbols.binary_search_by_key(&addr, |(a, _)| *a) {
+            Ok(i) => Some(i),
+            Err(0) => None,
+            Err(i) => Some(i - 1),
+        }
+    }
+
+    fn function_of(&self, addr: u16) -> u16 {
+        self.symbol_index(addr).map_or(addr, |i| self.symbols[i].0)
+    }
+
+    fn name_of(&self, addr: u16) -> Option<String> {
+        self.symbols
+            .binary_search_by_key(&addr, |(a, _)| *a)
+            .ok()
+            .map(|i| self.symbols[i].1.clone())
+    }
+
+    fn current_node(&self) -> usize {
+        self.stack.last().map_or(0, |f| f.node)
+    }
+
+    fn enter(&mut self, target: u16, sp: u8, interrupt: bool) {
+        let function = Some(self.function_of(target));
+        let parent = self.current_node();
+        let existing =
+            self.nodes[parent].children.iter().copied().find(|&c| {
+                self.nodes[c].function == function && self.nodes[c].interrupt == interrupt
+            });
+        let node = if let Some(node) = existing {
+            node
+        } else {
+            self.nodes.push(CallNode {
+                function,
+                interrupt,
+                parent: Some(parent),
+                children: vec![],
+                calls: 0,
+                self_ticks: 0,
+            });
+            let node = self.nodes.len() - 1;
+            self.nodes[parent].children.push(node);
+            node
+        };
+        self.nodes[node].calls += 1;
+        if interrupt {
+            self.irq_depth += 1;
+        }
+        self.stack.push(StackFrame { node, sp });
+    }
+
+    fn unwind(&mut self, sp: u8) {
+        while let Some(frame) = self.stack.last() {
+            // Still inside the call while the stack holds up to half a page more than on entry.
+            if matches!(frame.sp.wrapping_sub(sp), 1..=0x7F) {
+                break;
+            }
+            if self.nodes[frame.node].interrupt {
+                self.irq_depth -= 1;
+            }
+            self.stack.pop();
+        }
+    }
+
+    /// Accounts one system tick. `new_instruction` is set on the tick the CPU starts
+    /// the instruction `ir` at `pc`, with the stack pointer `sp`.
+    pub fn tick(
+        &mut self,
+        new_instruction: bool,
+        pc: u16,
+        ir: u8,
+        sp: u8,
+        activity: ProfilerActivity,
+    ) {
+        if new_instruction {
+            match self.current_ir {
+                OPCODE_JSR => self.enter(pc, self.current_sp, false),
+                OPCODE_BRK => self.enter(pc, self.current_sp, true),
+                _ => (),
+            }
+            self.unwind(sp);
+            self.current_pc = pc;
+            self.current_ir = ir;
+            self.current_sp = sp;
+            self.pcs[pc as usize].executions += 1;
+        }
+
+        let in_irq = self.irq_depth > 0;
+        let stats = &mut self.pcs[self.current_pc as usize];
+        stats.ticks += 1;
+        match activity {
+            ProfilerActivity::Cpu => (),
+            ProfilerActivity::Suzy | ProfilerActivity::VideoDma => stats.stalled_ticks += 1,
+            ProfilerActivity::Sleep => stats.sleep_ticks += 1,
+        }
+        if in_irq {
+            stats.irq_ticks += 1;
+        }
+        let node = self.current_node();
+        self.nodes[node].self_ticks += 1;
+        self.frame.add(activity);
+    }
+
+    /// Closes the current frame split.
+    pub fn end_frame(&mut self) {
+        if self.frames.len() == PROFILER_FRAME_HISTORY {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(self.frame);
+        self.frame = FrameTime::default();
+    }
+
+    #[must_use]
+    pub fn pc_stats(&self, pc: u16) -> &PcStats {
+        &self.pcs[pc as usize]
+    }
+
+    /// Every address an instruction was executed from, with its stats.
+    pub fn executed_pcs(&self) -> impl Iterator<Item = (u16, &PcStats)> {
+        self.pcs
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.ticks != 0)
+            .map(|(pc, s)| (pc as u16, s))
+    }
+
+    /// The frame being accumulated.
+    #[must_use]
+    pub fn current_frame(&self) -> &FrameTime {
+        &self.frame
+    }
+
+    /// The last [`PROFILER_FRAME_HISTORY`] frames, oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &FrameTime> {
+        self.frames.iter()
+    }
+
+    #[must_use]
+    pub fn call_nodes(&self) -> &[CallNode] {
+        &self.nodes
+    }
+
+    fn total_ticks(&self, node: usize, totals: &mut [u64]) -> u64 {
+        let total = self.nodes[node].self_ticks
+            + self.nodes[node]
+                .children
+                .iter()
+                .map(|&c| self.total_ticks(c, totals))
+                .sum::<u64>();
+        totals[node] = total;
+        total
+    }
+
+    fn node_totals(&self) -> Vec<u64> {
+        let mut totals = vec![0; self.nodes.len()];
+        self.total_ticks(0, &mut totals);
+        totals
+    }
+
+    /// The call tree, depth first, starting with the root.
+    #[must_use]
+    pub fn call_tree(&self) -> Vec<CallTreeEntry> {
+        let totals = self.node_totals();
+        let mut entries = vec![];
+        let mut pending = vec![(0, 0)];
+        while let Some((node, depth)) = pending.pop() {
+            let n = &self.nodes[node];
+            entries.push(CallTreeEntry {
+                depth,
+                function: n.function,
+                name: n.function.and_then(|f| self.name_of(f)),
+                interrupt: n.interrupt,
+                calls: n.calls,
+                self_ticks: n.self_ticks,
+                total_ticks: totals[node],
+            });
+            let mut children = n.children.clone();
+            children.sort_by_key(|&c| totals[c]);
+            pending.extend(children.into_iter().map(|c| (c, depth + 1)));
+        }
+        entries
+    }
+
+    /// Per function totals, the most expensive first. With symbols the self ticks
+    /// are summed over the symbol's address range, otherwise they come from the call tree.
+    #[must_use]
+    pub fn flat_profile(&self) -> Vec<FunctionStats> {
+        let totals = self.node_totals();
+        let mut functions: Vec<FunctionStats> = vec![];
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let Some(address) = node.function else {
+                continue;
+            };
+            let on_stack_above = {
+                let mut parent = node.parent;
+                let mut recursive = false;
+                while let Some(p) = parent {
+                    if self.nodes[p].function == node.function {
+                        recursive = true;
+                        break;
+                    }
+                    parent = self.nodes[p].parent;
+                }
+                recursive
+            };
+            let index = if let Some(index) = functions.iter().position(|f| f.address == address) {
+                index
+            } else {
+                functions.push(FunctionStats {
+                    address,
+                    name: self.name_of(address),
+                    calls: 0,
+                    self_ticks: 0,
+                    total_ticks: 0,
+                });
+                functions.len() - 1
+            };
+            let entry = &mut functions[index];
+            entry.calls += node.calls;
+            entry.self_ticks += node.self_ticks;
+            if !on_stack_above {
+                entry.total_ticks += totals[i];
+            }
+        }
+
+        if !self.symbols.is_empty() {
+            for f in &mut functions {
+                f.self_ticks = 0;
+            }
+            for (pc, stats) in self.executed_pcs() {
+                let Some(i) = self.symbol_index(pc) else {
+                    continue;
+                };
+                let (address, name) = &self.symbols[i];
+                match functions.iter_mut().find(|f| f.address == *address) {
+                    Some(f) => f.self_ticks += stats.ticks,
+                    None => functions.push(FunctionStats {
+                        address: *address,
+                        name: Some(name.clone()),
+                        calls: 0,
+                        self_ticks: stats.ticks,
+                        total_ticks: stats.ticks,
+                    }),
+                }
+            }
+        }
+
+        functions.sort_by_key(|f| core::cmp::Reverse(f.self_ticks));
+        functions
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::REFRESH_AND_VIDEO_DMA_TICKS;
+    use crate::test_rom::lynx_running;
+
+    const OPCODE_RTS: u8 = 0x60;
+    const OPCODE_RTI: u8 = 0x40;
+
+    /// Runs the instruction `ir` at `pc` for `ticks` ticks.
+    fn run(profiler: &mut Profiler, pc: u16, ir: u8, sp: u8, ticks: u64) {
+        profiler.tick(true, pc, ir, sp, ProfilerActivity::Cpu);
+        for _ in 1..ticks {
+            profiler.tick(false, pc, ir, sp, ProfilerActivity::Cpu);
+        }
+    }
+
+    #[test]
+    fn call_tree() {
+        let mut profiler = Profiler::new();
+        for _ in 0..2 {
+            run(&mut profiler, 0x0200, OPCODE_JSR, 0xFF, 6);
+            run(&mut profiler, 0x0300, OPCODE_JSR, 0xFD, 6);
+            run(&mut profiler, 0x0400, OPCODE_NOP, 0xFB, 2);
+            run(&mut profiler, 0x0401, OPCODE_RTS, 0xFB, 6);
+            run(&mut profiler, 0x0303, OPCODE_RTS, 0xFD, 6);
+        }
+        run(&mut profiler, 0x0203, OPCODE_NOP, 0xFF, 2);
+
+        let tree = profiler.call_tree();
+        let summary: Vec<_> = tree
+            .iter()
+            .map(|e| (e.depth, e.function, e.calls, e.self_ticks, e.total_ticks))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (0, None, 0, 14, 54),
+                (1, Some(0x0300), 2, 24, 40),
+                (2, Some(0x0400), 2, 16, 16),
+            ]
+        );
+        assert_eq!(profiler.pc_stats(0x0400).executions, 2);
+        assert_eq!(profiler.pc_stats(0x0401).ticks, 12);
+    }
+
+    #[test]
+    fn interrupt_unwind() {
+        let mut profiler = Profiler::new();
+        run(&mut profiler, 0x0200, OPCODE_BRK, 0xFF, 7);
+        run(&mut profiler, 0xF000, OPCODE_RTI, 0xFC, 6);
+        run(&mut profiler, 0x0200, OPCODE_NOP, 0xFF, 2);
+
+        let tree = profiler.call_tree();
+        assert_eq!(tree.len(), 2);
+        assert!(tree[1].interrupt);
+        assert_eq!((tree[1].function, tree[1].self_ticks), (Some(0xF000), 6));
+        assert_eq!(profiler.pc_stats(0xF000).irq_ticks, 6);
+        assert_eq!(profiler.pc_stats(0x0200).irq_ticks, 0);
+        assert_eq!(profiler.pc_stats(0x0200).executions, 2);
+    }
+
+    #[test]
+    fn stack_tricks_unwind() {
+        let mut profiler = Profiler::new();
+        run(&mut profiler, 0x0200, OPCODE_JSR, 0xFF, 6);
+        run(&mut profiler, 0x0300, OPCODE_JSR, 0xFD, 6);
+        // PLA PLA RTS
+        run(&mut profiler, 0x0400, 0x68, 0xFB, 4);
+        run(&mut profiler, 0x0401, 0x68, 0xFC, 4);
+        run(&mut profiler, 0x0402, OPCODE_RTS, 0xFD, 6);
+        run(&mut profiler, 0x0203, OPCODE_NOP, 0xFF, 2);
+
+        assert_eq!(profiler.current_node(), 0);
+        assert_eq!(profiler.call_tree()[0].self_ticks, 8);
+    }
+
+    #[test]
+    fn flat_profile() {
+        let mut profiler = Profiler::new();
+        // recursive call of 0x0300
+        run(&mut profiler, 0x0200, OPCODE_JSR, 0xFF, 6);
+        run(&mut profiler, 0x0300, OPCODE_JSR, 0xFD, 6);
+        run(&mut profiler, 0x0300, OPCODE_RTS, 0xFB, 6);
+        run(&mut profiler, 0x0303, OPCODE_RTS, 0xFD, 6);
+        run(&mut profiler, 0x0203, OPCODE_NOP, 0xFF, 2);
+
+        let flat = profiler.flat_profile();
+        assert_eq!(flat.len(), 1);
+        assert_eq!(
+            (
+                flat[0].address,
+                flat[0].calls,
+                flat[0].self_ticks,
+                flat[0].total_ticks
+            ),
+            (0x0300, 2, 18, 18)
+        );
+
+        profiler.set_symbols([(0x0200, "main".into()), (0x0300, "recurse".into())]);
+        let flat = profiler.flat_profile();
+        let summary: Vec<_> = flat
+            .iter()
+            .map(|f| (f.name.as_deref(), f.calls, f.self_ticks))
+            .collect();
+        assert_eq!(summary, [(Some("recurse"), 2, 18), (Some("main"), 0, 8)]);
+    }
+
+    #[test]
+    fn frame_split() {
+        let mut profiler = Profiler::new();
+        run(&mut profiler, 0x0200, OPCODE_NOP, 0xFF, 1);
+        let activities = [
+            (ProfilerActivity::Cpu, 5),
+            (ProfilerActivity::Suzy, 3),
+            (ProfilerActivity::VideoDma, 2),
+            (ProfilerActivity::Sleep, 1),
+        ];
+        for (activity, ticks) in activities {
+            for _ in 0..ticks {
+                profiler.tick(false, 0x0200, OPCODE_NOP, 0xFF, activity);
+            }
+        }
+        profiler.end_frame();
+        profiler.tick(false, 0x0200, OPCODE_NOP, 0xFF, ProfilerActivity::Cpu);
+
+        let frames: Vec<_> = profiler.frames().copied().collect();
+        assert_eq!(
+            frames,
+            [FrameTime {
+                cpu: 6,
+                suzy: 3,
+                video_dma: 2,
+                sleep: 1
+            }]
+        );
+        assert_eq!(profiler.current_frame().total(), 1);
+        let stats = profiler.pc_stats(0x0200);
+        assert_eq!(
+            (stats.ticks, stats.stalled_ticks, stats.sleep_ticks),
+            (13, 5, 1)
+        );
+    }
+
+    #[test]
+    fn video_dma_window() {
+        let mut lynx = lynx_running(&[]);
+        // past the fetch of the first tick
+        for _ in 0..=REFRESH_AND_VIDEO_DMA_TICKS {
+            lynx.tick();
+        }
+        lynx.set_profiler_enabled(true);
+        lynx.tick();
+        while lynx.mikey().last_video_dma_tick() != lynx.mikey().ticks() {
+            assert!(!lynx.mikey().video_dma_active());
+            lynx.tick();
+        }
+        // the CPU is held for the tick of the fetch and the whole DMA delay
+        for delay in (1..=REFRESH_AND_VIDEO_DMA_TICKS).rev() {
+            assert!(lynx.mikey().video_dma_active());
+            assert_eq!(lynx.mikey().registers().ticks_delay(), delay);
+            lynx.tick();
+        }
+        assert!(lynx.mikey().video_dma_active());
+        assert_eq!(lynx.mikey().registers().ticks_delay(), 0);
+        let frame = lynx.profiler().unwrap().current_frame();
+        assert_eq!(frame.video_dma, u64::from(REFRESH_AND_VIDEO_DMA_TICKS) + 1);
+        lynx.tick();
+        let next_fetch = lynx.mikey().last_video_dma_tick() == lynx.mikey().ticks();
+        assert_eq!(lynx.mikey().video_dma_active(), next_fetch);
+    }
+}
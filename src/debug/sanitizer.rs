@@ -0,0 +1,230 @@
+use crate::alloc::collections::VecDeque;
+use crate::alloc::vec::Vec;
+use crate::ram::RAM_MAX;
+use crate::register_map::{register, RegisterAccess};
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+
+/// Reports kept by the sanitizer, the oldest ones are dropped.
+pub const SANITIZER_REPORT_LEN: usize = 1024;
+
+const OPCODE_TXS: u8 = 0x9A;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SanitizerIssue {
+    /// The stack pointer wrapped around page 1.
+    StackWrap { from: u8, to: u8 },
+    /// Read of a RAM address never written since reset.
+    UninitializedRead(u16),
+    /// Write to a read-only Mikey/Suzy register.
+    ReadOnlyWrite(u16),
+    /// Read of a write-only Mikey/Suzy register.
+    WriteOnlyRead(u16),
+    /// Access to Suzy while the sprite engine is busy.
+    SuzyBusy(u16),
+    /// Opcode fetch from the hardware register area.
+    ExecuteRegister(u16),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SanitizerReport {
+    pub tick: u64,
+    pub pc: u16,
+    pub issue: SanitizerIssue,
+}
+
+/// `true` for the registers that can only be read.
+#[must_use]
+pub fn register_read_only(addr: u16) -> bool {
+    register(addr).is_some_and(|r| r.access == RegisterAccess::ReadOnly)
+}
+
+/// `true` for the registers that can only be written.
+#[must_use]
+pub fn register_write_only(addr: u16) -> bool {
+    register(addr).is_some_and(|r| r.access == RegisterAccess::WriteOnly)
+}
+
+/// Opt-in checker for suspicious homebrew behaviour.
+///
+/// Every issue is reported once per instruction address, with the PC and tick of its first occurrence.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Sanitizer {
+    written: Vec<bool>,
+    #[serde(skip)]
+    seen: HashSet<(u16, SanitizerIssue)>,
+    reports: VecDeque<SanitizerReport>,
+    count: usize,
+    last: Option<(u16, u8, u8)>,
+}
+
+impl Sanitizer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            written: vec![false; RAM_MAX as usize + 1],
+            seen: HashSet::new(),
+            reports: VecDeque::new(),
+            count: 0,
+            last: None,
+        }
+    }
+
+    pub fn report(&mut self, tick: u64, pc: u16, issue: SanitizerIssue) {
+        if !self.seen.insert((pc, issue)) {
+            return;
+        }
+        self.count += 1;
+        if self.reports.len() == SANITIZER_REPORT_LEN {
+            self.reports.pop_front();
+        }
+        self.reports.push_back(SanitizerReport { tick, pc, issue });
+    }
+
+    /// Forgets the RAM content, to be called on reset.
+    pub fn reset_memory(&mut self) {
+        self.written.fill(false);
+        self.last = None;
+    }
+
+    /// Takes the whole RAM as initialized, e.g. after loading it by other means.
+    pub fn assume_ram_written(&mut self) {
+        self.written.fill(true);
+    }
+
+    #[inline]
+    pub fn ram_written(&mut self, addr: u16) {
+        self.written[addr as usize] = true;
+    }
+
+    #[inline]
+    pub fn ram_read(&mut self, tick: u64, pc: u16, addr: u16) {
+        if !self.written[addr as usize] {
+            self.report(tick, pc, SanitizerIssue::UninitializedRead(addr));
+        }
+    }
+
+    /// Called when the instruction `ir` at `pc` starts, checks what the previous
+    /// instruction did to the stack pointer.
+    pub fn instruction(&mut self, tick: u64, pc: u16, ir: u8, sp: u8) {
+        let Some((previous_pc, previous_ir, from)) = self.last.replace((pc, ir, sp)) else {
+            return;
+        };
+        if previous_ir == OPCODE_TXS {
+            return;
+        }
+        // Instructions and interrupts move the stack pointer by 3 at most.
+        let delta = sp.wrapping_sub(from) as i8;
+        if delta.unsigned_abs() <= 3 && i16::from(from) + i16::from(delta) != i16::from(sp) {
+            self.report(
+                tick,
+                previous_pc,
+                SanitizerIssue::StackWrap { from, to: sp },
+            );
+        }
+    }
+
+    /// The last [`SANITIZER_REPORT_LEN`] reports, oldest first.
+    pub fn reports(&self) -> impl Iterator<Item = &SanitizerReport> {
+        self.reports.iter()
+    }
+
+    /// Number of reports since the sanitizer was created or cleared.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Drops the reports, the RAM content knowledge is kept.
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.reports.clear();
+        self.count = 0;
+    }
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Sanitizer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rom::lynx_running;
+
+    #[test]
+    fn stack_wrap() {
+        let mut sanitizer = Sanitizer::new();
+        // PHA with S at 0, then TXS
+        sanitizer.instruction(0, 0x0200, 0x48, 0x00);
+        sanitizer.instruction(3, 0x0201, OPCODE_TXS, 0xFF);
+        sanitizer.instruction(5, 0x0202, 0xEA, 0x10);
+        sanitizer.instruction(7, 0x0203, 0x68, 0x10);
+        sanitizer.instruction(11, 0x0200, 0x48, 0x11);
+        sanitizer.instruction(14, 0x0201, OPCODE_TXS, 0x10);
+        sanitizer.instruction(16, 0x0200, 0x48, 0x00);
+        sanitizer.instruction(19, 0x0201, 0xEA, 0xFF);
+
+        let reports: Vec<_> = sanitizer.reports().copied().collect();
+        assert_eq!(
+            reports,
+            [SanitizerReport {
+                tick: 3,
+                pc: 0x0200,
+                issue: SanitizerIssue::StackWrap { from: 0, to: 0xFF }
+            }]
+        );
+        sanitizer.clear();
+        assert_eq!(sanitizer.count(), 0);
+        assert_eq!(sanitizer.reports().count(), 0);
+    }
+
+    #[test]
+    fn memory_and_registers() {
+        let code = [
+            0xA2, 0xFF, // LDX #$FF
+            0x9A, // TXS
+            0x48, // PHA
+            0x68, // PLA
+            0xAD, 0x00, 0x03, // LDA $0300
+            0x8D, 0x01, 0x03, // STA $0301
+            0xAD, 0x01, 0x03, // LDA $0301
+            0x8D, 0x88, 0xFC, // STA SUZYHREV
+            0xAD, 0x80, 0xFC, // LDA SPRCTL0
+        ];
+        let mut lynx = lynx_running(&code);
+        lynx.set_sanitizer_enabled(true);
+        for _ in 0..2_000 {
+            lynx.tick();
+        }
+
+        let issues: Vec<_> = lynx
+            .sanitizer()
+            .unwrap()
+            .reports()
+            .map(|r| (r.pc, r.issue))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (0xFE05, SanitizerIssue::UninitializedRead(0x0300)),
+                (0xFE0E, SanitizerIssue::ReadOnlyWrite(0xFC88)),
+                (0xFE11, SanitizerIssue::WriteOnlyRead(0xFC80)),
+            ]
+        );
+    }
+
+    #[test]
+    fn enabled_late() {
+        // LDA $0300
+        let mut lynx = lynx_running(&[0xAD, 0x00, 0x03]);
+        lynx.tick();
+        lynx.set_sanitizer_enabled(true);
+        for _ in 0..2_000 {
+            lynx.tick();
+        }
+        assert_eq!(lynx.sanitizer().unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::alloc::vec::Vec;
+use crate::consts::{
+    HPOSSTRTL, SPRCOLL_DONT_COLLIDE, SPRCOLL_NUMBER, SPRCTL0_BPP, SPRCTL0_HFLIP, SPRCTL0_SPR_TYPE,
+    SPRCTL0_VFLIP, SPRCTL1_ALGO_3, SPRCTL1_DRAW_QUAD, SPRCTL1_LITERAL, SPRCTL1_RELOAD_HVS,
+    SPRCTL1_RELOAD_HVST, SPRCTL1_REUSE_PALETTE, SPRCTL1_SKIP_SPRITE, SPRHSIZL, SPRVSIZL, STRETCHL,
+    TILTL, VPOSSTRTL,
+};
+use crate::ram::{Ram, RAM_MAX};
+use crate::suzy::Suzy;
+use serde::{Deserialize, Serialize};
+
+/// SCBs walked at most, longer chains are reported as [`ScbChainError::TooLong`].
+pub const SCB_CHAIN_MAX_LEN: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScbIssue {
+    /// The header runs past the end of the address space.
+    HeaderWraps,
+    /// The sprite data pointer is in page 0.
+    NullData,
+    /// `SPRCTL1` algorithm 3 bit is set, "do not use".
+    Algo3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScbChainError {
+    /// The SCB at this address was already part of the chain.
+    Cycle(u16),
+    TooLong,
+}
+
+/// A decoded Sprite Control Block. The values not reloaded by the SCB are the
+/// ones inherited from the previous sprite, as the sprite engine would use them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScbRecord {
+    pub addr: u16,
+    /// Header bytes read by the sprite engine.
+    pub len: u16,
+    pub sprctl0: u8,
+    pub sprctl1: u8,
+    pub sprcoll: u8,
+    pub next: u16,
+    pub data: u16,
+    pub hpos: i16,
+    pub vpos: i16,
+    pub hsize: u16,
+    pub vsize: u16,
+    pub stretch: u16,
+    pub tilt: u16,
+    pub palette_reloaded: bool,
+    pub pens: [u8; 16],
+    pub issues: Vec<ScbIssue>,
+}
+
+impl ScbRecord {
+    #[must_use]
+    pub fn skipped(&self) -> bool {
+        self.sprctl1 & SPRCTL1_SKIP_SPRITE != 0
+    }
+
+    #[must_use]
+    pub fn bits_per_pixel(&self) -> u8 {
+        ((self.sprctl0 & SPRCTL0_BPP) >> 6) + 1
+    }
+
+    #[must_use]
+    pub fn sprite_type(&self) -> u8 {
+        self.sprctl0 & SPRCTL0_SPR_TYPE
+    }
+
+    #[must_use]
+    pub fn hflip(&self) -> bool {
+        self.sprctl0 & SPRCTL0_HFLIP != 0
+    }
+
+    #[must_use]
+    pub fn vflip(&self) -> bool {
+        self.sprctl0 & SPRCTL0_VFLIP != 0
+    }
+
+    #[must_use]
+    pub fn literal(&self) -> bool {
+        self.sprctl1 & SPRCTL1_LITERAL != 0
+    }
+
+    #[must_use]
+    pub fn start_quadrant(&self) -> u8 {
+        [0, 3, 1, 2][(self.sprctl1 & SPRCTL1_DRAW_QUAD) as usize]
+    }
+
+    #[must_use]
+    pub fn collision_number(&self) -> u8 {
+        self.sprcoll & SPRCOLL_NUMBER
+    }
+
+    #[must_use]
+    pub fn dont_collide(&self) -> bool {
+        self.sprcoll & SPRCOLL_DONT_COLLIDE != 0
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScbChain {
+    pub scbs: Vec<ScbRecord>,
+    pub error: Option<ScbChainError>,
+}
+
+struct ScbReader<'a> {
+    ram: &'a Ram,
+    addr: u16,
+    len: u16,
+    wrapped: bool,
+}
+
+impl ScbReader<'_> {
+    fn u8(&mut self) -> u8 {
+        let addr = self.addr.wrapping_add(self.len);
+        self.wrapped |= addr < self.addr;
+        self.len += 1;
+        self.ram.get(addr)
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from(self.u8()) | (u16::from(self.u8()) << 8)
+    }
+}
+
+impl ScbChain {
+    /// Walks the chain starting at `first`, reading the SCBs from `ram` the way
+    /// `Renderer::load_scb` does. Nothing is written, neither to RAM nor to Suzy.
+    #[must_use]
+    pub fn walk(ram: &Ram, suzy: &Suzy, first: u16) -> Self {
+        let regs = suzy.registers();
+        let mut hsize = regs.u16(SPRHSIZL);
+        let mut vsize = regs.u16(SPRVSIZL);
+        let mut stretch = regs.u16(STRETCHL);
+        let mut tilt = regs.u16(TILTL);
+        let mut hpos = regs.i16(HPOSSTRTL);
+        let mut vpos = regs.i16(VPOSSTRTL);
+        let mut data = 0;
+        let mut pens = *suzy.pens();
+
+        let mut visited = vec![false; RAM_MAX as usize + 1];
+        let mut chain = Self::default();
+        let mut addr = first;
+
+        // The sprite engine stops on a next SCB address in page 0.
+        while addr & 0xFF00 != 0 {
+            if visited[addr as usize] {
+                chain.error = Some(ScbChainError::Cycle(addr));
+                break;
+            }
+            if chain.scbs.len() == SCB_CHAIN_MAX_LEN {
+                chain.error = Some(ScbChainError::TooLong);
+                break;
+            }
+            visited[addr as usize] = true;
+
+            let mut reader = ScbReader {
+                ram,
+                addr,
+                len: 0,
+                wrapped: false,
+            };
+            let sprctl0 = reader.u8();
+            let sprctl1 = reader.u8();
+            let sprcoll = reader.u8();
+            let next = reader.u16();
+            let mut palette_reloaded = false;
+
+            if sprctl1 & SPRCTL1_SKIP_SPRITE == 0 {
+                data = reader.u16();
+                hpos = reader.u16() as i16;
+                vpos = reader.u16() as i16;
+                let reload = sprctl1 & SPRCTL1_RELOAD_HVST;
+                if reload != 0 {
+                    hsize = reader.u16();
+                    vsize = reader.u16();
+                }
+                if reload & SPRCTL1_RELOAD_HVS != 0 {
+                    stretch = reader.u16();
+                }
+                if reload == SPRCTL1_RELOAD_HVST {
+                    tilt = reader.u16();
+                }
+                if sprctl1 & SPRCTL1_REUSE_PALETTE == 0 {
+                    for i in 0..8 {
+                        let pen = reader.u8();
+                        pens[i * 2] = pen >> 4;
+                        pens[i * 2 + 1] = pen & 0x0F;
+                    }
+                    palette_reloaded = true;
+                }
+            }
+
+            let mut issues = vec![];
+            if reader.wrapped {
+                issues.push(ScbIssue::HeaderWraps);
+            }
+            if sprctl1 & SPRCTL1_SKIP_SPRITE == 0 && data & 0xFF00 == 0 {
+                issues.push(ScbIssue::NullData);
+            }
+            if sprctl1 & SPRCTL1_ALGO_3 != 0 {
+                issues.push(ScbIssue::Algo3);
+            }
+
+            chain.scbs.push(ScbRecord {
+                addr,
+                len: reader.len,
+                sprctl0,
+                sprctl1,
+                sprcoll,
+                next,
+                data,
+                hpos,
+                vpos,
+                hsize,
+                vsize,
+                stretch,
+                tilt,
+                palette_reloaded,
+                pens,
+                issues,
+            });
+            addr = next;
+        }
+
+        chain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::SPRCTL1_RELOAD_HV;
+
+    /// Writes an SCB at `addr` linking to `next`, `fields` are the 16-bit values from the
+    /// data pointer on and `pens` the palette bytes.
+    fn scb(ram: &mut Ram, addr: u16, sprctl1: u8, next: u16, fields: &[u16], pens: &[u8]) {
+        let mut bytes = vec![0x05, sprctl1, 0x02];
+        bytes.extend_from_slice(&next.to_le_bytes());
+        for field in fields {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.extend_from_slice(pens);
+        ram.copy(addr, &bytes);
+    }
+
+    #[test]
+    fn reload_combinations() {
+        let mut ram = Ram::new();
+        let reuse = SPRCTL1_REUSE_PALETTE;
+        let palette = [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF];
+        // data, hpos, vpos, hsize, vsize, stretch, tilt
+        let hvst = [0x3000, 1, 2, 0x0100, 0x0200, 0x0010, 0x0020];
+        scb(
+            &mut ram,
+            0x1000,
+            SPRCTL1_RELOAD_HVST,
+            0x1100,
+            &hvst,
+            &palette,
+        );
+        scb(
+            &mut ram,
+            0x1100,
+            SPRCTL1_RELOAD_HVS | reuse,
+            0x1200,
+            &[0x3100, 3, 4, 0x0300, 0x0400, 0x0030],
+            &[],
+        );
+        scb(
+            &mut ram,
+            0x1200,
+            SPRCTL1_RELOAD_HV | reuse,
+            0x1300,
+            &[0x3200, 5, 6, 0x0500, 0x0600],
+            &[],
+        );
+        scb(&mut ram, 0x1300, reuse, 0x1400, &[0x3300, 7, 8], &[]);
+        scb(&mut ram, 0x1400, SPRCTL1_SKIP_SPRITE, 0x1500, &[], &[]);
+        scb(&mut ram, 0x1500, 0, 0x0000, &[0x3400, 9, 10], &[0; 8]);
+
+        let chain = ScbChain::walk(&ram, &Suzy::new(), 0x1000);
+        assert_eq!(chain.error, None);
+        let summary: Vec<_> = chain
+            .scbs
+            .iter()
+            .map(|s| (s.addr, s.len, s.data, s.hsize, s.vsize, s.stretch, s.tilt))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (0x1000, 27, 0x3000, 0x0100, 0x0200, 0x0010, 0x0020),
+                (0x1100, 17, 0x3100, 0x0300, 0x0400, 0x0030, 0x0020),
+                (0x1200, 15, 0x3200, 0x0500, 0x0600, 0x0030, 0x0020),
+                (0x1300, 11, 0x3300, 0x0500, 0x0600, 0x0030, 0x0020),
+                (0x1400, 5, 0x3300, 0x0500, 0x0600, 0x0030, 0x0020),
+                (0x1500, 19, 0x3400, 0x0500, 0x0600, 0x0030, 0x0020),
+            ]
+        );
+        let first = &chain.scbs[0];
+        assert_eq!((first.hpos, first.vpos), (1, 2));
+        assert!(first.palette_reloaded);
+        assert_eq!(first.pens, core::array::from_fn(|i| i as u8));
+        assert_eq!(chain.scbs[3].pens, first.pens);
+        assert!(!chain.scbs[3].palette_reloaded);
+        assert!(chain.scbs[4].skipped());
+        assert_eq!(chain.scbs[5].pens, [0; 16]);
+        assert_eq!((first.bits_per_pixel(), first.sprite_type()), (1, 5));
+        assert_eq!(first.collision_number(), 2);
+        assert!(chain.scbs.iter().all(|s| s.issues.is_empty()));
+    }
+
+    #[test]
+    fn issues_and_cycles() {
+        let mut ram = Ram::new();
+        scb(
+            &mut ram,
+            0x1000,
+            SPRCTL1_ALGO_3 | SPRCTL1_REUSE_PALETTE,
+            0x1100,
+            &[0x0080, 0, 0],
+            &[],
+        );
+        scb(&mut ram, 0x1100, SPRCTL1_SKIP_SPRITE, 0x1000, &[], &[]);
+        let chain = ScbChain::walk(&ram, &Suzy::new(), 0x1000);
+        assert_eq!(chain.error, Some(ScbChainError::Cycle(0x1000)));
+        assert_eq!(chain.scbs.len(), 2);
+        assert_eq!(chain.scbs[0].issues, [ScbIssue::NullData, ScbIssue::Algo3]);
+
+        scb(&mut ram, 0xFFFA, SPRCTL1_SKIP_SPRITE, 0x0000, &[], &[]);
+        let chain = ScbChain::walk(&ram, &Suzy::new(), 0xFFFA);
+        assert_eq!(chain.scbs[0].issues, []);
+        let chain = ScbChain::walk(&ram, &Suzy::new(), 0xFFFC);
+        assert_eq!(chain.scbs[0].issues, [ScbIssue::HeaderWraps]);
+    }
+}
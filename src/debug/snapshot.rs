@@ -0,0 +1,384 @@
+use crate::consts::{
+    ATTEN_A, BLUERED0, COLLADRL, COLLBASL, COLLOFFL, GREEN0, HOFFL, HPOSSTRTL, HSIZOFFL, INTSET,
+    IODAT, IODIR, MATHA, MATHB, MATHC, MATHD, MATHE, MATHF, MATHG, MATHH, MATHJ, MATHK, MATHL,
+    MATHM, MATHN, MATHP, MPAN, MSTEREO, PBKUP, PROCADRL, SCBADRL, SCBNEXTL, SPRCOLL, SPRCTL0,
+    SPRCTL1, SPRDLINEL, SPRDOFFL, SPRGO, SPRHSIZL, SPRINIT, SPRVPOSL, SPRVSIZL, STRETCHL,
+    SUZYBUSEN, SYSCTL1, TILTACUML, TILTL, TMPADRL, VIDADRL, VIDBASL, VOFFL, VPOSSTRTL, VSIZACUML,
+    VSIZOFFL,
+};
+use crate::debug::sprite::{mikey_palette, Palette};
+use crate::mikey::timers::audio_timer_registers::AudioTimerRegisters;
+use crate::mikey::timers::timer::Timer;
+use crate::mikey::Mikey;
+use crate::ram::Ram;
+use crate::suzy::math::MathMode;
+use crate::suzy::registers::SuzyRegisters;
+use crate::suzy::Suzy;
+use core::num::NonZeroU8;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuSnapshot {
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub s: u8,
+    pub pc: u16,
+    /// `NV-BDIZC`.
+    pub flags: u8,
+    /// Opcode of the current instruction.
+    pub ir: u8,
+    /// Put to sleep by `CPUSLEEP`.
+    pub asleep: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerSnapshot {
+    pub backup: u8,
+    pub control_a: u8,
+    pub count: u8,
+    pub control_b: u8,
+    /// Timer clocked by the underflows of this one.
+    pub linked_timer: Option<u8>,
+    pub timer_done: bool,
+    pub borrow_in: bool,
+    pub borrow_out: bool,
+}
+
+impl From<&Timer> for TimerSnapshot {
+    fn from(timer: &Timer) -> Self {
+        Self {
+            backup: timer.backup(),
+            control_a: timer.control_a(),
+            count: timer.count(),
+            control_b: timer.control_b(),
+            linked_timer: timer.linked_timer().map(NonZeroU8::get),
+            timer_done: timer.timer_done(),
+            borrow_in: timer.borrow_in(),
+            borrow_out: timer.borrow_out(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioChannelSnapshot {
+    pub timer: TimerSnapshot,
+    pub volume: u8,
+    /// 12 bits feedback taps.
+    pub feedback: u16,
+    /// 12 bits shift register.
+    pub shift_register: u16,
+    pub output: i8,
+    /// `ATTEN_x`, left volume in the high nibble.
+    pub attenuation: u8,
+}
+
+impl AudioChannelSnapshot {
+    fn new(timer: &Timer, audio: AudioTimerRegisters, attenuation: u8) -> Self {
+        Self {
+            timer: TimerSnapshot::from(timer),
+            volume: audio.volume(),
+            feedback: audio.audio_feedback_taps(timer),
+            shift_register: audio.audio_shift_register(timer),
+            output: audio.output(),
+            attenuation,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UartSnapshot {
+    /// `SERCTL` as read, the status bits.
+    pub status: u8,
+    /// `SERCTL` as written, the control bits.
+    pub control: u8,
+    pub transmit_holding: Option<u8>,
+    pub transmitting: bool,
+    /// Last byte received, unread while `RXRDY` is set.
+    pub receive_holding: u8,
+    pub transmitted: u64,
+    pub received: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplaySnapshot {
+    pub dispctl: u8,
+    pub pbkup: u8,
+    pub dispadr: u16,
+    pub flipped: bool,
+    pub frame_count: u64,
+}
+
+/// Suzy sprite engine registers, the 16 bits ones as a whole.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteRegistersSnapshot {
+    pub tmpadr: u16,
+    pub tiltacum: u16,
+    pub hoff: u16,
+    pub voff: u16,
+    pub vidbas: u16,
+    pub collbas: u16,
+    pub vidadr: u16,
+    pub colladr: u16,
+    pub scbnext: u16,
+    pub sprdline: u16,
+    pub hposstrt: u16,
+    pub vposstrt: u16,
+    pub sprhsiz: u16,
+    pub sprvsiz: u16,
+    pub stretch: u16,
+    pub tilt: u16,
+    pub sprdoff: u16,
+    pub sprvpos: u16,
+    pub colloff: u16,
+    pub vsizacum: u16,
+    pub hsizoff: u16,
+    pub vsizoff: u16,
+    pub scbadr: u16,
+    pub procadr: u16,
+    pub sprctl0: u8,
+    pub sprctl1: u8,
+    pub sprcoll: u8,
+    pub sprinit: u8,
+    pub suzybusen: u8,
+    pub sprgo: u8,
+    /// `SPRSYS` as read, the status bits.
+    pub sprsys_status: u8,
+    /// `SPRSYS` as written, the control bits.
+    pub sprsys_control: u8,
+}
+
+impl From<&SuzyRegisters> for SpriteRegistersSnapshot {
+    fn from(regs: &SuzyRegisters) -> Self {
+        let s16 = |addr: u16| regs.u16(addr);
+        let s8 = |addr: u16| regs.data(addr);
+        Self {
+            tmpadr: s16(TMPADRL),
+            tiltacum: s16(TILTACUML),
+            hoff: s16(HOFFL),
+            voff: s16(VOFFL),
+            vidbas: s16(VIDBASL),
+            collbas: s16(COLLBASL),
+            vidadr: s16(VIDADRL),
+            colladr: s16(COLLADRL),
+            scbnext: s16(SCBNEXTL),
+            sprdline: s16(SPRDLINEL),
+            hposstrt: s16(HPOSSTRTL),
+            vposstrt: s16(VPOSSTRTL),
+            sprhsiz: s16(SPRHSIZL),
+            sprvsiz: s16(SPRVSIZL),
+            stretch: s16(STRETCHL),
+            tilt: s16(TILTL),
+            sprdoff: s16(SPRDOFFL),
+            sprvpos: s16(SPRVPOSL),
+            colloff: s16(COLLOFFL),
+            vsizacum: s16(VSIZACUML),
+            hsizoff: s16(HSIZOFFL),
+            vsizoff: s16(VSIZOFFL),
+            scbadr: s16(SCBADRL),
+            procadr: s16(PROCADRL),
+            sprctl0: s8(SPRCTL0),
+            sprctl1: s8(SPRCTL1),
+            sprcoll: s8(SPRCOLL),
+            sprinit: s8(SPRINIT),
+            suzybusen: s8(SUZYBUSEN),
+            sprgo: s8(SPRGO),
+            sprsys_status: regs.sprsys(),
+            sprsys_control: regs.sprsys_w(),
+        }
+    }
+}
+
+/// Suzy math registers. Multiplies `AB * CD` into `EFGH`, accumulated in `JKLM`.
+/// Divides `EFGH / NP` into `ABCD`, with the remainder in `JKLM`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MathRegistersSnapshot {
+    pub a: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub f: u8,
+    pub g: u8,
+    pub h: u8,
+    pub j: u8,
+    pub k: u8,
+    pub l: u8,
+    pub m: u8,
+    pub n: u8,
+    pub p: u8,
+    pub mode: MathMode,
+}
+
+impl MathRegistersSnapshot {
+    #[must_use]
+    pub fn new(regs: &SuzyRegisters, mode: MathMode) -> Self {
+        let s8 = |addr: u16| regs.data(addr);
+        Self {
+            a: s8(MATHA),
+            b: s8(MATHB),
+            c: s8(MATHC),
+            d: s8(MATHD),
+            e: s8(MATHE),
+            f: s8(MATHF),
+            g: s8(MATHG),
+            h: s8(MATHH),
+            j: s8(MATHJ),
+            k: s8(MATHK),
+            l: s8(MATHL),
+            m: s8(MATHM),
+            n: s8(MATHN),
+            p: s8(MATHP),
+            mode,
+        }
+    }
+}
+
+/// Hardware state at a point in time, for debugger frontends.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardwareSnapshot {
+    pub tick: u64,
+    pub cpu: CpuSnapshot,
+    pub mapctl: u8,
+    /// `INTSET`, the pending interrupts.
+    pub interrupts: u8,
+    pub sysctl1: u8,
+    pub iodir: u8,
+    pub iodat: u8,
+    pub timers: [TimerSnapshot; 8],
+    pub audio: [AudioChannelSnapshot; 4],
+    pub mstereo: u8,
+    pub mpan: u8,
+    pub uart: UartSnapshot,
+    pub display: DisplaySnapshot,
+    /// `GREENx` registers.
+    pub green: [u8; 16],
+    /// `BLUEREDx` registers.
+    pub bluered: [u8; 16],
+    pub palette: Palette,
+    pub sprite: SpriteRegistersSnapshot,
+    pub math: MathRegistersSnapshot,
+}
+
+impl HardwareSnapshot {
+    #[must_use]
+    pub fn capture(mikey: &Mikey, suzy: &Suzy, ram: &Ram) -> Self {
+        let cpu = mikey.cpu();
+        let regs = mikey.registers();
+        let timers = mikey.timers();
+        let uart = mikey.uart();
+        let suzy_regs = suzy.registers();
+
+        Self {
+            tick: mikey.ticks(),
+            cpu: CpuSnapshot {
+                a: cpu.a(),
+                x: cpu.x(),
+                y: cpu.y(),
+                s: cpu.s(),
+                pc: cpu.pc(),
+                flags: cpu.flags().bits(),
+                ir: cpu.ir(),
+                asleep: mikey.cpu_asleep(),
+            },
+            mapctl: ram.mmapctl(),
+            interrupts: regs.data(INTSET),
+            sysctl1: regs.data(SYSCTL1),
+            iodir: regs.data(IODIR),
+            iodat: regs.data(IODAT),
+            timers: core::array::from_fn(|i| TimerSnapshot::from(timers.timer(i))),
+            audio: core::array::from_fn(|i| {
+                AudioChannelSnapshot::new(
+                    timers.timer(8 + i),
+                    *timers.audio_timer(i),
+                    regs.data(ATTEN_A + i as u16),
+                )
+            }),
+            mstereo: regs.data(MSTEREO),
+            mpan: regs.data(MPAN),
+            uart: UartSnapshot {
+                status: regs.serctl(),
+                control: regs.serctl_w(),
+                transmit_holding: uart.transmit_holding_register(),
+                transmitting: uart.transmitting(),
+                receive_holding: uart.receive_holding_register(),
+                transmitted: uart.transmitted().0,
+                received: uart.received().0,
+            },
+            display: DisplaySnapshot {
+                dispctl: regs.dispctl(),
+                pbkup: regs.data(PBKUP),
+                dispadr: regs.disp_addr(),
+                flipped: regs.is_flipped(),
+                frame_count: mikey.video().frame_count(),
+            },
+            green: core::array::from_fn(|i| regs.data(GREEN0 + i as u16)),
+            bluered: core::array::from_fn(|i| regs.data(BLUERED0 + i as u16)),
+            palette: mikey_palette(regs),
+            sprite: SpriteRegistersSnapshot::from(suzy_regs),
+            math: MathRegistersSnapshot::new(suzy_regs, suzy.math_mode()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::{MATHN, MATHP, PBKUP, TIM1BKUP, TIM1CTLA, VIDBASL};
+    use crate::test_rom::{lynx_drawing, lynx_running, store};
+
+    #[test]
+    fn known_registers() {
+        let mut code = vec![0x78]; // SEI
+        store(&mut code, TIM1BKUP, 0x42);
+        store(&mut code, TIM1CTLA, 0x1E); // reload, count, 64us
+        store(&mut code, PBKUP, 0x29);
+        store(&mut code, GREEN0 + 1, 0x0A);
+        store(&mut code, BLUERED0 + 1, 0x5B);
+        store(&mut code, VIDBASL, 0x00);
+        store(&mut code, VIDBASL + 1, 0x20);
+        store(&mut code, MATHP, 0x34);
+        store(&mut code, MATHN, 0x12);
+        // LDA #$12, LDX #$34, LDY #$56
+        code.extend_from_slice(&[0xA9, 0x12, 0xA2, 0x34, 0xA0, 0x56]);
+        let mut lynx = lynx_running(&code);
+        for _ in 0..2_000 {
+            lynx.tick();
+        }
+        let snapshot = lynx.hardware_snapshot();
+
+        assert_eq!(snapshot.tick, lynx.mikey().ticks());
+        assert_eq!(snapshot.cpu.a, 0x12);
+        assert_eq!(snapshot.cpu.x, 0x34);
+        assert_eq!(snapshot.cpu.y, 0x56);
+        assert!(!snapshot.cpu.asleep);
+        assert_eq!(snapshot.timers[1].backup, 0x42);
+        assert_eq!(snapshot.timers[1].control_a, 0x1E);
+        assert_eq!(snapshot.display.pbkup, 0x29);
+        assert_eq!(snapshot.green[1], 0x0A);
+        assert_eq!(snapshot.bluered[1], 0x5B);
+        assert_eq!(snapshot.palette, mikey_palette(lynx.mikey().registers()));
+        assert_eq!(snapshot.sprite.vidbas, 0x2000);
+        assert_eq!((snapshot.math.n, snapshot.math.p), (0x12, 0x34));
+        assert_eq!(snapshot.math.mode, lynx.suzy().math_mode());
+    }
+
+    #[test]
+    fn asleep_while_suzy_draws() {
+        const SCB: u16 = 0x1000;
+        let mut lynx = lynx_drawing(SCB);
+        let ram = lynx.ram_mut();
+        // 1 bpp, normal, literal, reload HV
+        ram.copy(SCB, &[0x04, 0x90, 0x00, 0x00, 0x00, 0x00, 0x30]);
+        ram.copy(SCB + 7, &[10, 0, 10, 0, 0x00, 0x01, 0x00, 0x10]);
+        ram.copy(SCB + 15, &[0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF]);
+        ram.copy(0x3000, &[0x02, 0b1100_0000, 0x02, 0b1100_0000, 0x00]);
+        while lynx.suzy().registers().data(SPRGO) == 0 || !lynx.mikey().cpu_asleep() {
+            lynx.tick();
+        }
+        let snapshot = lynx.hardware_snapshot();
+        assert!(snapshot.cpu.asleep);
+        assert_eq!(snapshot.sprite.scbnext, SCB);
+        assert_eq!(snapshot.sprite.suzybusen, 1);
+    }
+}
@@ -0,0 +1,465 @@
+use crate::alloc::vec::Vec;
+use crate::consts::{
+    LINE_END, SPRCTL0, SPRCTL1, SPRCTL1_LITERAL, SPRCTL1_RELOAD_HVS, SPRCTL1_RELOAD_HVST,
+};
+use crate::debug::scb::ScbRecord;
+use crate::mikey::registers::MikeyRegisters;
+use crate::ram::{Ram, RAM_MAX};
+use crate::suzy::registers::SuzyRegisters;
+use crate::suzy::sprite_data::SpriteData;
+use serde::{Deserialize, Serialize};
+
+/// Pixels further than this from the sprite origin are dropped.
+pub const SPRITE_IMAGE_MAX_DISTANCE: i32 = 1024;
+
+/// RGB colour of each pen.
+pub type Palette = [[u8; 3]; 16];
+
+/// The palette currently set in the Mikey `GREEN` and `BLUERED` registers.
+#[must_use]
+pub fn mikey_palette(regs: &MikeyRegisters) -> Palette {
+    core::array::from_fn(|i| {
+        [
+            regs.palette_r()[i],
+            regs.palette_g()[i],
+            regs.palette_b()[i],
+        ]
+    })
+}
+
+/// A decoded sprite, laid out the way the sprite engine draws it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteImage {
+    pub width: usize,
+    pub height: usize,
+    /// Position of the sprite origin (`HPOSSTRT`, `VPOSSTRT`) in the image.
+    pub origin: (i32, i32),
+    /// Remapped pen of each pixel, `None` where nothing is drawn.
+    pub pixels: Vec<Option<u8>>,
+    /// Some pixels were too far away from the origin and got dropped.
+    pub clipped: bool,
+}
+
+impl SpriteImage {
+    #[must_use]
+    pub fn pen(&self, x: usize, y: usize) -> Option<u8> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.pixels[y * self.width + x]
+    }
+
+    /// RGBA pixels, the undrawn ones are fully transparent.
+    #[must_use]
+    pub fn rgba(&self, palette: &Palette) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.pixels.len() * 4);
+        for pixel in &self.pixels {
+            match pixel {
+                Some(pen) => {
+                    rgba.extend_from_slice(&palette[(pen & 0x0F) as usize]);
+                    rgba.push(0xFF);
+                }
+                None => rgba.extend_from_slice(&[0; 4]),
+            }
+        }
+        rgba
+    }
+}
+
+struct Quadrant {
+    hsign: i32,
+    vsign: i32,
+    lines: Vec<Vec<u8>>,
+}
+
+/// Standalone sprite decoder, renders the data of a single sprite off-screen.
+///
+/// Stretch and tilt are applied at every drawn line as the sprite engine does, the
+/// `SPRSYS` vertical stretching is not.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteDecoder {
+    /// 1 to 4.
+    pub bits_per_pixel: u8,
+    pub literal: bool,
+    /// `SPRCTL0` sprite type, selects the pens that are not drawn.
+    pub sprite_type: u8,
+    pub hflip: bool,
+    pub vflip: bool,
+    /// Quadrant drawn first, 0 is down-right, then counter clockwise.
+    pub start_quadrant: u8,
+    /// 8.8 fixed point, 0x100 is 1:1.
+    pub hsize: u16,
+    pub vsize: u16,
+    pub hsize_offset: u16,
+    pub vsize_offset: u16,
+    /// Added to `hsize` after every drawn line, 0 when the SCB does not reload it.
+    pub stretch: u16,
+    /// Horizontal shift added after every drawn line, 8.8 fixed point, 0 when the SCB does
+    /// not reload it.
+    pub tilt: u16,
+    /// Pen index remapping.
+    pub pens: [u8; 16],
+}
+
+impl SpriteDecoder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            bits_per_pixel: 4,
+            literal: false,
+            sprite_type: 4,
+            hflip: false,
+            vflip: false,
+            start_quadrant: 0,
+            hsize: 0x100,
+            vsize: 0x100,
+            hsize_offset: 0x7F,
+            vsize_offset: 0x7F,
+            stretch: 0,
+            tilt: 0,
+            pens: core::array::from_fn(|i| i as u8),
+        }
+    }
+
+    fn drawn(&self, pen: u8) -> bool {
+        match self.sprite_type {
+            0 | 1 => true,
+            2 => !matches!(pen, 0x00 | 0x0E | 0x0F),
+            3 => !matches!(pen, 0x00 | 0x0F),
+            _ => pen != 0x00,
+        }
+    }
+
+    /// Decodes the sprite data starting at the first line offset byte.
+    /// Bytes past the end of `data` read as 0, ending the sprite.
+    #[must_use]
+    pub fn decode(&self, data: &[u8]) -> SpriteImage {
+        let fetch = |addr: u16| data.get(addr as usize).copied().unwrap_or(0);
+        self.render(&self.decode_quadrants(fetch, 0))
+    }
+
+    /// Decodes the sprite data at `addr` in RAM.
+    #[must_use]
+    pub fn decode_ram(&self, ram: &Ram, addr: u16) -> SpriteImage {
+        self.render(&self.decode_quadrants(|a| ram.get(a), addr))
+    }
+
+    fn decode_quadrants(&self, fetch: impl Fn(u16) -> u8, addr: u16) -> Vec<Quadrant> {
+        let mut regs = SuzyRegisters::new();
+        regs.set_data(SPRCTL0, (self.bits_per_pixel.clamp(1, 4) - 1) << 6);
+        regs.set_data(SPRCTL1, if self.literal { SPRCTL1_LITERAL } else { 0 });
+
+        let mut sprite_data = SpriteData::new();
+        let mut quadrants = vec![];
+        let mut quadrant = self.start_quadrant & 0x03;
+        let mut lines = vec![];
+        let mut line_addr = addr;
+        let mut read = 0;
+
+        // Every line reads at least its offset byte, the whole space at most.
+        while read <= RAM_MAX as usize {
+            sprite_data.reset(&mut regs);
+            let mut next = line_addr;
+            let mut push = |sprite_data: &mut SpriteData| {
+                sprite_data.push_data(fetch(next));
+                next = next.wrapping_add(1);
+                read += 1;
+            };
+
+            let offset = loop {
+                match sprite_data.initialize(&mut regs, 0) {
+                    Ok(offset) => break offset,
+                    Err(_) => push(&mut sprite_data),
+                }
+            };
+
+            if offset <= 1 {
+                quadrants.push(self.quadrant(quadrant, core::mem::take(&mut lines)));
+                quadrant = (quadrant + 1) & 0x03;
+                if offset == 0 || quadrant == self.start_quadrant & 0x03 {
+                    break;
+                }
+                line_addr = line_addr.wrapping_add(1);
+                continue;
+            }
+
+            let mut line = vec![];
+            loop {
+                match sprite_data.line_get_pixel(&mut regs, &self.pens) {
+                    Ok(LINE_END) => break,
+                    Ok(pixel) => line.push(pixel),
+                    Err(_) => push(&mut sprite_data),
+                }
+            }
+            lines.push(line);
+            line_addr = line_addr.wrapping_add(offset);
+        }
+        quadrants
+    }
+
+    fn quadrant(&self, quadrant: u8, lines: Vec<Vec<u8>>) -> Quadrant {
+        let mut hsign = if quadrant == 0 || quadrant == 1 {
+            1
+        } else {
+            -1
+        };
+        let mut vsign = if quadrant == 0 || quadrant == 3 {
+            1
+        } else {
+            -1
+        };
+        if self.hflip {
+            hsign = -hsign;
+        }
+        if self.vflip {
+            vsign = -vsign;
+        }
+        Quadrant {
+            hsign,
+            vsign,
+            lines,
+        }
+    }
+
+    /// Calls `plot` for every pixel, at its position relative to the origin.
+    fn layout(&self, quadrants: &[Quadrant], mut plot: impl FnMut(i32, i32, u8)) {
+        let Some(first) = quadrants.first() else {
+            return;
+        };
+        // The quadrants drawn the other way start one pixel away from the origin.
+        let (hquadoff, vquadoff) = (first.hsign, first.vsign);
+        // Like `SPRHSIZ` and `HPOSSTRT`, the stretched size and the tilted start carry
+        // over from one quadrant to the next.
+        let mut hsize = self.hsize;
+        let mut hpos = 0;
+
+        for q in quadrants {
+            let mut y = if q.vsign == vquadoff { 0 } else { q.vsign };
+            let mut vsize_acc = if q.vsign > 0 { self.vsize_offset } else { 0 };
+            let mut tilt_acc = 0u16;
+            for line in &q.lines {
+                vsize_acc = vsize_acc.wrapping_add(self.vsize);
+                let height = vsize_acc >> 8;
+                vsize_acc &= 0xFF;
+                for _ in 0..height {
+                    hpos += i32::from(tilt_acc as i16 >> 8);
+                    tilt_acc &= 0xFF;
+                    let mut x = hpos + if q.hsign == hquadoff { 0 } else { q.hsign };
+                    let mut hsize_acc = if q.hsign > 0 { self.hsize_offset } else { 0 };
+                    for &pixel in line {
+                        hsize_acc = hsize_acc.wrapping_add(hsize);
+                        let width = hsize_acc >> 8;
+                        hsize_acc &= 0xFF;
+                        for _ in 0..width {
+                            plot(x, y, pixel);
+                            x += q.hsign;
+                        }
+                    }
+                    y += q.vsign;
+                    hsize = hsize.wrapping_add(self.stretch);
+                    tilt_acc = tilt_acc.wrapping_add(self.tilt);
+                }
+            }
+        }
+    }
+
+    fn render(&self, quadrants: &[Quadrant]) -> SpriteImage {
+        let range = -SPRITE_IMAGE_MAX_DISTANCE..SPRITE_IMAGE_MAX_DISTANCE;
+        let mut clipped = false;
+        let mut bounds: Option<(i32, i32, i32, i32)> = None;
+        self.layout(quadrants, |x, y, _| {
+            if !range.contains(&x) || !range.contains(&y) {
+                clipped = true;
+                return;
+            }
+            let (x0, y0, x1, y1) = bounds.get_or_insert((x, y, x, y));
+            *x0 = (*x0).min(x);
+            *y0 = (*y0).min(y);
+            *x1 = (*x1).max(x);
+            *y1 = (*y1).max(y);
+        });
+
+        let Some((x0, y0, x1, y1)) = bounds else {
+            return SpriteImage {
+                clipped,
+                ..SpriteImage::default()
+            };
+        };
+        let width = (x1 - x0 + 1) as usize;
+        let height = (y1 - y0 + 1) as usize;
+        let mut pixels = vec![None; width * height];
+        self.layout(quadrants, |x, y, pen| {
+            if range.contains(&x) && range.contains(&y) && self.drawn(pen) {
+                pixels[(y - y0) as usize * width + (x - x0) as usize] = Some(pen);
+            }
+        });
+
+        SpriteImage {
+            width,
+            height,
+            origin: (-x0, -y0),
+            pixels,
+            clipped,
+        }
+    }
+}
+
+impl Default for SpriteDecoder {
+    fn default() -> Self {
+        SpriteDecoder::new()
+    }
+}
+
+impl From<&ScbRecord> for SpriteDecoder {
+    fn from(scb: &ScbRecord) -> Self {
+        Self {
+            bits_per_pixel: scb.bits_per_pixel(),
+            literal: scb.literal(),
+            sprite_type: scb.sprite_type(),
+            hflip: scb.hflip(),
+            vflip: scb.vflip(),
+            start_quadrant: scb.start_quadrant(),
+            hsize: scb.hsize,
+            vsize: scb.vsize,
+            stretch: if scb.sprctl1 & SPRCTL1_RELOAD_HVS != 0 {
+                scb.stretch
+            } else {
+                0
+            },
+            tilt: if scb.sprctl1 & SPRCTL1_RELOAD_HVST == SPRCTL1_RELOAD_HVST {
+                scb.tilt
+            } else {
+                0
+            },
+            pens: scb.pens,
+            ..Self::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suzy::sprite_encoder::encode_packed;
+
+    /// One pen per row of a `width` wide image, row `y` uses pen `y + 1`.
+    fn bars(width: usize, height: usize) -> Vec<u8> {
+        let pixels: Vec<u8> = (0..width * height).map(|i| (i / width) as u8 + 1).collect();
+        encode_packed(&pixels, width, 4).unwrap()
+    }
+
+    fn rows(image: &SpriteImage) -> Vec<Vec<Option<u8>>> {
+        image
+            .pixels
+            .chunks(image.width)
+            .map(<[_]>::to_vec)
+            .collect()
+    }
+
+    #[test]
+    fn flip_and_scale() {
+        let data = bars(2, 2);
+        let image = SpriteDecoder::new().decode(&data);
+        assert_eq!((image.width, image.height, image.origin), (2, 2, (0, 0)));
+        assert_eq!(rows(&image), [[Some(1), Some(1)], [Some(2), Some(2)]]);
+
+        let decoder = SpriteDecoder {
+            vflip: true,
+            hsize: 0x200,
+            ..SpriteDecoder::new()
+        };
+        let image = decoder.decode(&data);
+        assert_eq!((image.width, image.height, image.origin), (4, 2, (0, 1)));
+        assert_eq!(rows(&image)[0], [Some(2); 4]);
+        assert!(!image.clipped);
+    }
+
+    #[test]
+    fn stretch() {
+        let decoder = SpriteDecoder {
+            stretch: 0x100,
+            ..SpriteDecoder::new()
+        };
+        let image = decoder.decode(&bars(1, 3));
+        assert_eq!(
+            rows(&image),
+            [
+                [Some(1), None, None],
+                [Some(2), Some(2), None],
+                [Some(3), Some(3), Some(3)],
+            ]
+        );
+    }
+
+    #[test]
+    fn tilt() {
+        let decoder = SpriteDecoder {
+            tilt: 0x0180,
+            ..SpriteDecoder::new()
+        };
+        let image = decoder.decode(&bars(1, 3));
+        // 0, then 1.5 and 3 pixels, the fraction carried over
+        assert_eq!(
+            rows(&image),
+            [
+                [Some(1), None, None, None],
+                [None, Some(2), None, None],
+                [None, None, None, Some(3)],
+            ]
+        );
+
+        let image = SpriteDecoder {
+            tilt: 0xFF00,
+            ..SpriteDecoder::new()
+        }
+        .decode(&bars(1, 2));
+        assert_eq!(image.origin, (1, 0));
+        assert_eq!(rows(&image), [[None, Some(1)], [Some(2), None]]);
+    }
+
+    #[test]
+    fn scb_reloads() {
+        let mut scb = ScbRecord {
+            addr: 0x1000,
+            len: 0,
+            sprctl0: 0xC4,
+            sprctl1: SPRCTL1_RELOAD_HVS,
+            sprcoll: 0,
+            next: 0,
+            data: 0x2000,
+            hpos: 0,
+            vpos: 0,
+            hsize: 0x100,
+            vsize: 0x100,
+            stretch: 0x10,
+            tilt: 0x20,
+            palette_reloaded: false,
+            pens: [0; 16],
+            issues: vec![],
+        };
+        let decoder = SpriteDecoder::from(&scb);
+        assert_eq!((decoder.stretch, decoder.tilt), (0x10, 0));
+        scb.sprctl1 = SPRCTL1_RELOAD_HVST;
+        let decoder = SpriteDecoder::from(&scb);
+        assert_eq!((decoder.stretch, decoder.tilt), (0x10, 0x20));
+        scb.sprctl1 = 0;
+        let decoder = SpriteDecoder::from(&scb);
+        assert_eq!((decoder.stretch, decoder.tilt), (0, 0));
+    }
+
+    #[test]
+    fn rgba() {
+        let image = SpriteDecoder {
+            tilt: 0x100,
+            ..SpriteDecoder::new()
+        }
+        .decode(&bars(1, 2));
+        let mut palette = [[0; 3]; 16];
+        palette[1] = [0x10, 0x20, 0x30];
+        palette[2] = [0x40, 0x50, 0x60];
+        assert_eq!(
+            image.rgba(&palette),
+            [0x10, 0x20, 0x30, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0, 0x40, 0x50, 0x60, 0xFF]
+        );
+    }
+}
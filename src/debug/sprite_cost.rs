@@ -0,0 +1,130 @@
+use crate::alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+pub use crate::suzy::sprite_log::{SpriteCost, SpriteCostRecord, SpriteRun};
+
+/// Sprite engine runs completed during a frame.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameSpriteCost {
+    pub runs: Vec<SpriteRun>,
+    pub total: SpriteCost,
+}
+
+/// Keeps the sprite engine costs of the last complete frame.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpriteCostMonitor {
+    last_frame: FrameSpriteCost,
+}
+
+impl SpriteCostMonitor {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            last_frame: FrameSpriteCost::default(),
+        }
+    }
+
+    pub fn end_frame(&mut self, runs: &[SpriteRun]) {
+        let mut total = SpriteCost::default();
+        for run in runs {
+            total += run.total;
+        }
+        self.last_frame = FrameSpriteCost {
+            runs: runs.to_vec(),
+            total,
+        };
+    }
+
+    #[must_use]
+    pub fn last_frame(&self) -> &FrameSpriteCost {
+        &self.last_frame
+    }
+}
+
+impl Default for SpriteCostMonitor {
+    fn default() -> Self {
+        SpriteCostMonitor::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::consts::{SUZY_SPRITE_SCB_ADDITIONAL_COST, SUZY_SPRITE_VERT_ADDITIONAL_COST};
+    use crate::lynx::Lynx;
+    use crate::test_rom::{draw_sprite, lynx_drawing, lynx_drawing_after, store, SCB};
+
+    /// Draws two lines of two pixels, each line `vsize` times, then returns the tick the
+    /// sleeping CPU first got woken up and the tick it got the bus back, if it did.
+    fn draw(lynx: &mut Lynx, vsize: u8) -> Option<(u64, u64)> {
+        let mut woken = None;
+        // 1 bpp, literal, 8 pens
+        draw_sprite(lynx, 0x90, 3, vsize, |lynx| {
+            let tick = lynx.mikey().ticks();
+            match woken {
+                None if lynx.mikey().cpu_asleep() && lynx.mikey().raised_interrupts() != 0 => {
+                    woken = Some((tick, 0));
+                }
+                Some((irq, 0)) if !lynx.mikey().cpu_asleep() => woken = Some((irq, tick)),
+                _ => (),
+            }
+        });
+        woken
+    }
+
+    #[test]
+    fn known_sprite() {
+        let mut lynx = lynx_drawing(SCB);
+        lynx.set_sprite_costs_enabled(true);
+        assert_eq!(draw(&mut lynx, 1), None);
+        let runs = lynx.suzy().cost_log();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].sprites.len(), 1);
+        assert_eq!(runs[0].sprites[0].scb, SCB);
+        let cost = runs[0].sprites[0].cost;
+        // 15 bytes of header and 8 pens, the 5 bytes of data with both line offsets read twice
+        assert_eq!(cost.scb_bytes_read, 23);
+        assert_eq!(cost.bytes_read, 23 + 7);
+        // 2 pixels per line, each one checked against the collision buffer
+        assert_eq!(cost.pixels_written, 4);
+        assert_eq!(cost.collision_pixels_written, 4);
+        assert_eq!(cost.pixels_read, 4);
+        assert_eq!(cost.read_modify_writes(), 8);
+        assert_eq!(
+            cost.scb_overhead_ticks,
+            u32::from(SUZY_SPRITE_SCB_ADDITIONAL_COST)
+        );
+        assert_eq!(cost.line_overhead_ticks, 0);
+        assert_eq!(cost.bus_ticks, 245);
+        assert_eq!(cost.cpu_held_ticks, 0);
+        // the end of the chain
+        assert_eq!(runs[0].total.bus_ticks, 250);
+        assert_eq!(runs[0].total.bytes_read, cost.bytes_read);
+
+        let run = runs[0].clone();
+        lynx.reset();
+        draw(&mut lynx, 1);
+        assert_eq!(lynx.suzy().cost_log(), [run]);
+    }
+
+    #[test]
+    fn cpu_held() {
+        let mut setup = vec![0x78]; // SEI, the CPU only wakes up
+        store(&mut setup, 0xFD04, 0x10); // TIM1BKUP
+        store(&mut setup, 0xFD05, 0x98); // TIM1CTLA, interrupt, reload, count, 1us
+        let mut lynx = lynx_drawing_after(&setup, SCB);
+        lynx.set_sprite_costs_enabled(true);
+        let (irq, wake) = draw(&mut lynx, 0x10).unwrap();
+        let runs = lynx.suzy().cost_log();
+        assert_eq!(runs.len(), 1);
+        let total = runs[0].total;
+        // each line drawn 16 times
+        assert_eq!(total.pixels_written, 64);
+        assert_eq!(
+            total.line_overhead_ticks,
+            30 * u32::from(SUZY_SPRITE_VERT_ADDITIONAL_COST)
+        );
+        // the sprite engine only gives the bus back at the end of the run
+        assert_eq!(u64::from(total.cpu_held_ticks), wake - irq);
+        assert!(total.cpu_held_ticks < total.bus_ticks);
+    }
+}
@@ -0,0 +1,357 @@
+use crate::cartridge::{is_bs93, BS93_HEADER_LENGTH};
+use crate::consts::{CRYSTAL_FREQ, SUZ_ADDR};
+use crate::debug::debug_port::{
+    DebugPort, DebugPortTrigger, DebugSignal, DebugSignalKind, DEBUG_PORT_DEFAULT_ADDRESS,
+};
+use crate::lynx::Lynx;
+use alloc::{string::String, vec::Vec};
+
+/// Ticks a test runs for by default, 10 seconds.
+pub const HARNESS_DEFAULT_BUDGET: u64 = 10 * CRYSTAL_FREQ as u64;
+/// Ticks between two hashed audio samples, 16 kHz.
+const AUDIO_SAMPLE_TICKS: u64 = CRYSTAL_FREQ as u64 / 16_000;
+const OPCODE_JMP: u8 = 0x4C;
+const OPCODE_RTI: u8 = 0x40;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HarnessStop {
+    /// Pass or fail signal on the debug port.
+    Signal,
+    /// The CPU reached the magic PC.
+    MagicPc,
+    /// An instruction jumped or branched to itself.
+    InfiniteLoop,
+    /// The tick budget ran out.
+    Timeout,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HarnessResult {
+    pub stop: HarnessStop,
+    /// The debug port exit code, else the accumulator at the magic PC or in the loop. `None`
+    /// on timeout.
+    pub exit_code: Option<u8>,
+    /// Instruction the CPU was at.
+    pub pc: u16,
+    /// Ticks the test ran for.
+    pub ticks: u64,
+    /// Text written to the debug port.
+    pub text: String,
+    /// Debug port signals taken during the run, up to the exit one. The later ones are left
+    /// for the next run.
+    pub signals: Vec<DebugSignal>,
+    /// MD5 of the RGBA screen.
+    pub frame_hash: [u8; 16],
+    /// MD5 of the left and right samples, little endian `i16`, taken at 16 kHz.
+    pub audio_hash: [u8; 16],
+}
+
+impl HarnessResult {
+    /// The test stopped by itself with a 0 exit code.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Runs a test program headless until it signals its end, for CI.
+///
+/// The program ends by sending a pass or fail signal to the debug port, by reaching the
+/// magic PC or by looping on a single instruction. A program idling on a single instruction
+/// while waiting for interrupts needs the loop detection off.
+pub struct TestHarness {
+    lynx: Lynx,
+    budget: u64,
+    magic_pc: Option<u16>,
+    loop_detection: bool,
+}
+
+impl TestHarness {
+    /// Takes a console ready to run, its debug port is enabled at
+    /// `DEBUG_PORT_DEFAULT_ADDRESS` unless it already has one.
+    #[must_use]
+    pub fn new(mut lynx: Lynx) -> Self {
+        if lynx.debug_port().is_none() {
+            lynx.set_debug_port_enabled(
+                true,
+                DebugPortTrigger::Address(DEBUG_PORT_DEFAULT_ADDRESS),
+            );
+        }
+        Self {
+            lynx,
+            budget: HARNESS_DEFAULT_BUDGET,
+            magic_pc: None,
+            loop_detection: true,
+        }
+    }
+
+    /// Boots a cartridge through the boot ROM.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file format cannot be identified.
+    pub fn from_cart(data: &[u8]) -> Result<Self, &'static str> {
+        let mut lynx = Lynx::new();
+        lynx.load_cart_from_slice(data)?;
+        Ok(Self::new(lynx))
+    }
+
+    /// Copies a BS93 program to RAM and starts it, without a cartridge nor the boot ROM.
+    ///
+    /// The ROM is replaced by a stub jumping to the load address, its interrupt handler
+    /// returns at once. The hardware is left as at power on.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bs93` is not a BS93 file or the program overlaps the hardware
+    /// registers.
+    pub fn from_bs93(bs93: &[u8]) -> Result<Self, &'static str> {
+        if !is_bs93(bs93) {
+            return Err("Not a BS93 file");
+        }
+        let load_address = u16::from_be_bytes([bs93[2], bs93[3]]);
+        let data = &bs93[BS93_HEADER_LENGTH..];
+        if usize::from(load_address) + data.len() > usize::from(SUZ_ADDR) {
+            return Err("BS93 program overlaps the hardware registers");
+        }
+
+        let mut lynx = Lynx::new();
+        for (addr, &byte) in (load_address..).zip(data) {
+            lynx.ram_mut().set(addr, byte);
+        }
+        let mut rom = vec![0u8; 512];
+        let [lo, hi] = load_address.to_le_bytes();
+        rom[..3].copy_from_slice(&[OPCODE_JMP, lo, hi]);
+        rom[0x1F0] = OPCODE_RTI;
+        rom[0x1FA..].copy_from_slice(&[0xF0, 0xFF, 0x00, 0xFE, 0xF0, 0xFF]);
+        lynx.load_rom_from_slice(&rom)?;
+        Ok(Self::new(lynx))
+    }
+
+    /// Ticks a run can last.
+    pub fn set_budget(&mut self, ticks: u64) {
+        self.budget = ticks;
+    }
+
+    pub fn set_magic_pc(&mut self, pc: Option<u16>) {
+        self.magic_pc = pc;
+    }
+
+    pub fn set_loop_detection(&mut self, enabled: bool) {
+        self.loop_detection = enabled;
+    }
+
+    /// Runs until the program ends or the budget runs out. Can be called again to go on.
+    pub fn run(&mut self) -> HarnessResult {
+        let start = self.lynx.mikey().ticks();
+        let mut audio = md5::Context::new();
+        let mut previous_pc = None;
+        let mut signals = vec![];
+        let mut signal_exit = None;
+
+        let stop = loop {
+            if self.lynx.mikey().ticks() - start >= self.budget {
+                break HarnessStop::Timeout;
+            }
+            self.lynx.tick();
+            let tick = self.lynx.mikey().ticks();
+            if tick % AUDIO_SAMPLE_TICKS == 0 {
+                let (left, right) = self.lynx.audio_sample();
+                audio.consume(left.to_le_bytes());
+                audio.consume(right.to_le_bytes());
+            }
+            signal_exit = self.take_signals(&mut signals);
+            if signal_exit.is_some() {
+                break HarnessStop::Signal;
+            }
+            if self.lynx.mikey().last_instruction_tick() != tick {
+                continue;
+            }
+            let cpu = self.lynx.mikey().cpu();
+            let pc = cpu.last_ir_pc;
+            if self.magic_pc == Some(pc) {
+                break HarnessStop::MagicPc;
+            }
+            // interrupts start as a BRK at the interrupted instruction
+            if !cpu.break_flags().is_empty() {
+                continue;
+            }
+            // same test as `Lynx::step_instruction`, which never returns on such a loop
+            if self.loop_detection && previous_pc == Some(pc) {
+                break HarnessStop::InfiniteLoop;
+            }
+            previous_pc = Some(pc);
+        };
+
+        let cpu = self.lynx.mikey().cpu();
+        let exit_code = match stop {
+            HarnessStop::Signal => signal_exit,
+            HarnessStop::MagicPc | HarnessStop::InfiniteLoop => Some(cpu.a()),
+            HarnessStop::Timeout => None,
+        };
+        HarnessResult {
+            stop,
+            exit_code,
+            pc: cpu.last_ir_pc,
+            ticks: self.lynx.mikey().ticks() - start,
+            text: self
+                .lynx
+                .debug_port()
+                .map(DebugPort::text)
+                .unwrap_or_default(),
+            signals,
+            frame_hash: md5::compute(self.lynx.screen_rgba()).0,
+            audio_hash: audio.finalize().0,
+        }
+    }
+
+    /// Takes the debug port signals up to the first exit one, returns its code.
+    fn take_signals(&mut self, signals: &mut Vec<DebugSignal>) -> Option<u8> {
+        let port = self.lynx.debug_port_mut()?;
+        while let Some(signal) = port.take_signal() {
+            signals.push(signal);
+            if let DebugSignalKind::Exit(code) = signal.kind {
+                return Some(code);
+            }
+        }
+        None
+    }
+
+    #[must_use]
+    pub fn lynx(&self) -> &Lynx {
+        &self.lynx
+    }
+
+    pub fn lynx_mut(&mut self) -> &mut Lynx {
+        &mut self.lynx
+    }
+
+    #[must_use]
+    pub fn into_lynx(self) -> Lynx {
+        self.lynx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rom::{lynx_running, store};
+    use alloc::vec::Vec;
+
+    const LOAD_ADDRESS: u16 = 0x0400;
+
+    /// A BS93 file loading `code` at `LOAD_ADDRESS`.
+    fn bs93(code: &[u8]) -> Vec<u8> {
+        let mut file = vec![0x80, 0x08];
+        file.extend_from_slice(&LOAD_ADDRESS.to_be_bytes());
+        file.extend_from_slice(&(code.len() as u16 + 10).to_be_bytes());
+        file.extend_from_slice(b"BS93");
+        file.extend_from_slice(code);
+        file
+    }
+
+    /// Writes `text` to the debug port.
+    fn print(code: &mut Vec<u8>, text: &[u8]) {
+        for &data in text {
+            store(code, DEBUG_PORT_DEFAULT_ADDRESS, data);
+        }
+    }
+
+    #[test]
+    fn pass_signal() {
+        let mut code = vec![];
+        print(&mut code, b"hello\n");
+        print(&mut code, b"\x06");
+        // JMP *
+        let here = LOAD_ADDRESS + code.len() as u16;
+        code.extend_from_slice(&[0x4C, here as u8, (here >> 8) as u8]);
+
+        let mut harness = TestHarness::from_bs93(&bs93(&code)).unwrap();
+        let result = harness.run();
+        assert_eq!(result.stop, HarnessStop::Signal);
+        assert!(result.passed());
+        assert_eq!(result.text, "hello\n");
+        assert!(result.pc < here);
+    }
+
+    #[test]
+    fn fail_signal() {
+        let mut code = vec![];
+        print(&mut code, b"\x15\x02");
+        let result = TestHarness::new(lynx_running(&code)).run();
+        assert_eq!(result.stop, HarnessStop::Signal);
+        assert_eq!(result.exit_code, Some(2));
+        assert!(!result.passed());
+    }
+
+    #[test]
+    fn signals_kept() {
+        let mut code = vec![];
+        // breakpoint, screenshot, pass, then fail with 3
+        print(&mut code, b"\x07\x0C\x06\x15\x03");
+        let mut harness = TestHarness::new(lynx_running(&code));
+        let result = harness.run();
+        assert_eq!(result.stop, HarnessStop::Signal);
+        assert!(result.passed());
+        let kinds: Vec<_> = result.signals.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DebugSignalKind::Breakpoint,
+                DebugSignalKind::Screenshot,
+                DebugSignalKind::Exit(0)
+            ]
+        );
+
+        let result = harness.run();
+        assert_eq!(result.stop, HarnessStop::Signal);
+        assert_eq!(result.exit_code, Some(3));
+        assert_eq!(result.signals.len(), 1);
+        assert_eq!(harness.run().stop, HarnessStop::InfiniteLoop);
+    }
+
+    #[test]
+    fn infinite_loop() {
+        // LDA #5, then the JMP * of lynx_running
+        let result = TestHarness::new(lynx_running(&[0xA9, 0x05])).run();
+        assert_eq!(result.stop, HarnessStop::InfiniteLoop);
+        assert_eq!(result.exit_code, Some(5));
+        assert_eq!(result.pc, 0xFE02);
+    }
+
+    #[test]
+    fn magic_pc_and_timeout() {
+        // INX, BRA -3
+        let code = [0xE8, 0x80, 0xFD];
+        let mut harness = TestHarness::new(lynx_running(&code));
+        harness.set_budget(10_000);
+        let result = harness.run();
+        assert_eq!(result.stop, HarnessStop::Timeout);
+        assert_eq!(result.exit_code, None);
+        assert_eq!(result.ticks, 10_000);
+
+        harness.set_magic_pc(Some(0xFE01));
+        let result = harness.run();
+        assert_eq!(result.stop, HarnessStop::MagicPc);
+        assert_eq!(result.pc, 0xFE01);
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut code = vec![];
+        print(&mut code, b"frame\n");
+        // JMP *
+        let here = LOAD_ADDRESS + code.len() as u16;
+        code.extend_from_slice(&[0x4C, here as u8, (here >> 8) as u8]);
+        let run = || {
+            let mut harness = TestHarness::from_bs93(&bs93(&code)).unwrap();
+            harness.set_budget(CRYSTAL_FREQ.into());
+            harness.run()
+        };
+        assert!(TestHarness::from_bs93(&[0; 64]).is_err());
+        let result = run();
+        assert_eq!(result.stop, HarnessStop::InfiniteLoop);
+        assert_eq!(result.text, "frame\n");
+        assert_eq!(result, run());
+    }
+}
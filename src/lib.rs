@@ -1,18 +1,41 @@
 #![no_std]
 #[macro_use]
 extern crate alloc;
+#[cfg(any(
+    feature = "comlynx_net",
+    feature = "comlynx_serial",
+    feature = "comlynx_shared_memory"
+))]
+extern crate std;
 
+#[cfg(feature = "bll_upload")]
+pub mod bll_upload;
 pub mod bus;
 pub mod cartridge;
+#[cfg(feature = "comlynx_net")]
+pub mod comlynx_net;
+#[cfg(feature = "comlynx_serial")]
+pub mod comlynx_serial;
 pub mod consts;
+pub mod debug;
+#[cfg(feature = "harness")]
+pub mod harness;
 pub mod lynx;
 pub mod mikey;
+#[cfg(not(feature = "comlynx_shared_memory"))]
+pub mod network;
 pub mod ram;
+pub mod register_map;
 pub mod rom;
 pub mod shared_memory;
 pub mod suzy;
+#[cfg(test)]
+mod test_rom;
 pub mod vectors;
 
+/// Leads every save state, the last byte is the format version, bumped when the layout changes.
+pub const SAVESTATE_HEADER: &[u8] = b"HLNX\x01";
+
 /// Serializes a Lynx instance into a byte array.
 ///
 /// # Errors
@@ -22,6 +45,10 @@ pub mod vectors;
 /// - There are encoding issues with the data
 /// - The postcard serialization encounters an error
 pub fn serialize(lynx: &lynx::Lynx, data: &mut [u8]) -> Result<(), &'static str> {
+    let Some((header, data)) = data.split_at_mut_checked(SAVESTATE_HEADER.len()) else {
+        return Err("Serialization error.");
+    };
+    header.copy_from_slice(SAVESTATE_HEADER);
     match postcard::to_slice(&lynx, data) {
         Err(_) => Err("Serialization error."),
         Ok(_) => Ok(()),
@@ -33,13 +60,18 @@ pub fn serialize(lynx: &lynx::Lynx, data: &mut [u8]) -> Result<(), &'static str>
 /// # Errors
 ///
 /// Returns `Err` with a descriptive message if:
+/// - The save state was made by another version of the format
 /// - The deserialization operation fails due to invalid data format
 /// - The postcard deserialization encounters an error
 pub fn deserialize(data: &[u8], source: &lynx::Lynx) -> Result<lynx::Lynx, &'static str> {
+    let Some(data) = data.strip_prefix(SAVESTATE_HEADER) else {
+        return Err("Unsupported save state version");
+    };
     let Ok(mut lynx) = postcard::from_bytes::<lynx::Lynx>(data) else {
         return Err("Deserialization error");
     };
     lynx.cart_mut().copy_from(source.cart());
+    lynx.restore();
     Ok(lynx)
 }
 
@@ -52,3 +84,36 @@ pub const fn info() -> (&'static str, &'static str) {
 pub const fn valid_extensions() -> &'static [&'static str] {
     &["lnx", "o"]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rom::lynx_drawing;
+
+    #[test]
+    fn asleep_after_load() {
+        let mut lynx = lynx_drawing(0x1000);
+        while !lynx.mikey().cpu_asleep() {
+            lynx.tick();
+        }
+        let mut data = vec![0; lynx.serialize_size()];
+        serialize(&lynx, &mut data).unwrap();
+        let loaded = deserialize(&data, &lynx).unwrap();
+        assert!(loaded.mikey().cpu_asleep());
+        assert_eq!(loaded.mikey().ticks(), lynx.mikey().ticks());
+    }
+
+    #[test]
+    fn other_version_rejected() {
+        let lynx = lynx_drawing(0x1000);
+        let mut data = vec![0; lynx.serialize_size()];
+        serialize(&lynx, &mut data).unwrap();
+        assert!(deserialize(&data, &lynx).is_ok());
+        data[SAVESTATE_HEADER.len() - 1] = 0;
+        assert_eq!(
+            deserialize(&data, &lynx).err(),
+            Some("Unsupported save state version")
+        );
+        assert!(deserialize(&data[SAVESTATE_HEADER.len()..], &lynx).is_err());
+    }
+}
@@ -1,16 +1,33 @@
+#[cfg(feature = "bll_upload")]
+use crate::bll_upload::{BllUpload, BllUploadProgress};
 use crate::bus::{Bus, BusStatus};
 use crate::cartridge::lnx_header::LNXRotation;
 use crate::cartridge::Cartridge;
 use crate::consts::{
-    INTV_ADDR_A, M6502_RDY, MAPCTL_MIK_BIT, MAPCTL_ROM_BIT, MAPCTL_SUZ_BIT, MAPCTL_VEC_BIT,
-    MIK_ADDR, MIK_ADDR_B, MMC_ADDR, MMC_ADDR_B, NMIV_ADDR, ROM_ADDR, ROM_ADDR_B, SUZ_ADDR,
-    SUZ_ADDR_B, TIM0BKUP,
+    COLLBASL, HSIZOFFL, INTV_ADDR_A, M6502_RDY, M6502_SYNC, MAPCTL_MIK_BIT, MAPCTL_ROM_BIT,
+    MAPCTL_SUZ_BIT, MAPCTL_VEC_BIT, MIK_ADDR, MIK_ADDR_B, MMC_ADDR, MMC_ADDR_B, NMIV_ADDR,
+    ROM_ADDR, ROM_ADDR_B, SPRSYS, SUZ_ADDR, SUZ_ADDR_B, SYSCTL1, SYSCTL1_POWER, TIM0BKUP, VSIZOFFL,
 };
+use crate::debug::cdl::{CdlFlags, CodeDataLogger};
+use crate::debug::collision::{CollisionFrame, CollisionMonitor};
+use crate::debug::debug_port::{DebugPort, DebugPortTrigger, DebugSignalKind};
+use crate::debug::events::{EventKind, EventLog, EventSample};
+use crate::debug::heatmap::{MemoryAccess, MemoryHeatmap};
+use crate::debug::overlay::{SpriteDraw, SpriteDrawLog};
+use crate::debug::profiler::{Profiler, ProfilerActivity};
+use crate::debug::sanitizer::{register_read_only, register_write_only, Sanitizer, SanitizerIssue};
+use crate::debug::scb::{ScbChain, ScbRecord};
+use crate::debug::snapshot::HardwareSnapshot;
+use crate::debug::sprite::{mikey_palette, Palette, SpriteDecoder, SpriteImage};
+use crate::debug::sprite_cost::{FrameSpriteCost, SpriteCostMonitor};
+use crate::mikey::uart::comlynx_capture::{ComlynxCapture, ComlynxReplay};
+use crate::mikey::uart::comlynx_device::ComlynxDevice;
 #[cfg(not(feature = "comlynx_shared_memory"))]
 use crate::mikey::uart::comlynx_cable_mutex::ComlynxCable;
 #[cfg(feature = "comlynx_shared_memory")]
 use crate::mikey::uart::comlynx_cable_shared_memory::ComlynxCable;
 use crate::mikey::{
+    cpu::instruction_length,
     video::{LYNX_SCREEN_HEIGHT, LYNX_SCREEN_WIDTH},
     Mikey,
 };
@@ -18,14 +35,17 @@ use crate::ram::{Ram, RAM_MAX};
 use crate::rom::Rom;
 use crate::shared_memory::SharedMemory;
 use crate::suzy::{
+    math::MathMode,
     registers::{joystick_swap, Joystick, Switches},
     Suzy,
 };
 use crate::vectors::Vectors;
-use alloc::vec::Vec;
+use alloc::{boxed::Box, vec::Vec};
 use log::trace;
 use serde::{Deserialize, Serialize};
 
+const OPCODE_BRK: u8 = 0x00;
+
 #[derive(Serialize, Deserialize)]
 pub struct Lynx {
     ram: Ram,
@@ -39,17 +59,43 @@ pub struct Lynx {
     switches_cache: Switches,
     #[cfg(feature = "comlynx_external")]
     #[serde(skip)]
-    comlynx_ext_tx: Option<kanal::Receiver<u8>>,
+    comlynx_ext_tx: Option<kanal::Receiver<(u64, u8)>>,
     #[cfg(feature = "comlynx_external")]
     #[serde(skip)]
     comlynx_ext_rx: Option<kanal::Sender<u8>>,
+    #[serde(skip)]
+    cdl: Option<CodeDataLogger>,
+    #[serde(skip)]
+    profiler: Option<Profiler>,
+    #[serde(skip)]
+    heatmap: Option<MemoryHeatmap>,
+    #[serde(skip)]
+    sanitizer: Option<Sanitizer>,
+    #[serde(skip)]
+    frame_count: u64,
+    #[serde(skip)]
+    events: Option<EventLog>,
+    #[serde(skip)]
+    cart_access: Option<EventKind>,
+    #[serde(skip)]
+    collisions: Option<CollisionMonitor>,
+    #[serde(skip)]
+    sprite_draws: Option<SpriteDrawLog>,
+    #[serde(skip)]
+    sprite_costs: Option<SpriteCostMonitor>,
+    #[serde(skip)]
+    cpu_wake_pending: bool,
+    #[serde(skip)]
+    debug_port: Option<DebugPort>,
+    #[serde(skip)]
+    monitors_enabled: bool,
 }
 
 impl Lynx {
     #[must_use]
     pub fn new() -> Self {
         #[cfg(feature = "comlynx_external")]
-        let (comlynx_ext_tx_tx, comlynx_ext_tx_rx) = kanal::unbounded::<u8>();
+        let (comlynx_ext_tx_tx, comlynx_ext_tx_rx) = kanal::unbounded::<(u64, u8)>();
         #[cfg(feature = "comlynx_external")]
         let (comlynx_ext_rx_tx, comlynx_ext_rx_rx) = kanal::unbounded::<u8>();
 
@@ -67,6 +113,19 @@ impl Lynx {
             comlynx_ext_tx: Some(comlynx_ext_tx_rx),
             #[cfg(feature = "comlynx_external")]
             comlynx_ext_rx: Some(comlynx_ext_rx_tx),
+            cdl: None,
+            profiler: None,
+            heatmap: None,
+            sanitizer: None,
+            frame_count: 0,
+            events: None,
+            cart_access: None,
+            collisions: None,
+            sprite_draws: None,
+            sprite_costs: None,
+            cpu_wake_pending: false,
+            debug_port: None,
+            monitors_enabled: false,
         };
 
         #[cfg(feature = "comlynx_external")]
@@ -122,6 +181,33 @@ impl Lynx {
         self.ram.mmapctl() & bit != 0
     }
 
+    /// Whether a CPU access to `addr` ends up in RAM with the current MAPCTL.
+    fn ram_mapped(&self, addr: u16, write: bool) -> bool {
+        match addr {
+            0..=SUZ_ADDR_B | MMC_ADDR => true,
+            SUZ_ADDR..=MIK_ADDR_B => self.mmap_ram(MAPCTL_SUZ_BIT),
+            MIK_ADDR..=ROM_ADDR_B => self.mmap_ram(MAPCTL_MIK_BIT),
+            ROM_ADDR..=MMC_ADDR_B => write || self.mmap_ram(MAPCTL_ROM_BIT),
+            NMIV_ADDR..=INTV_ADDR_A => self.mmap_ram(MAPCTL_VEC_BIT),
+        }
+    }
+
+    /// Classifies the CPU read currently on the bus, `None` for a dummy read.
+    fn cpu_read_flags(&self, addr: u16) -> Option<CdlFlags> {
+        if self.mikey.cpu_pins().is_set(M6502_SYNC) {
+            return Some(CdlFlags::opcode);
+        }
+        let cpu = self.mikey.cpu();
+        let offset = addr.wrapping_sub(cpu.last_ir_pc);
+        if offset != 0 && offset < u16::from(instruction_length(cpu.ir())) {
+            Some(CdlFlags::operand)
+        } else if cpu.read_used() {
+            Some(CdlFlags::data)
+        } else {
+            None
+        }
+    }
+
     pub fn poke(&mut self) {
         self.bus.set_status(BusStatus::Poke);
         self.mikey().cpu_pins().pin_on(M6502_RDY);
@@ -156,6 +242,27 @@ impl Lynx {
                 }
             }
         }
+
+        if self.sanitizer.is_some() {
+            self.sanitize_access(true);
+        }
+
+        if self
+            .debug_port
+            .as_ref()
+            .is_some_and(|port| port.trigger() == DebugPortTrigger::Address(self.bus.addr()))
+        {
+            self.debug_port_write(self.bus.data());
+        }
+
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.log_write(
+                self.mikey.ticks(),
+                self.mikey.cpu().last_ir_pc,
+                self.bus.addr(),
+                self.bus.data(),
+            );
+        }
     }
 
     pub fn peek(&mut self) {
@@ -198,6 +305,75 @@ impl Lynx {
                 }
             }
         }
+
+        if self.cdl.is_some() {
+            let addr = self.bus.addr();
+            if self.ram_mapped(addr, false) {
+                if let (Some(flags), Some(cdl)) = (self.cpu_read_flags(addr), &mut self.cdl) {
+                    cdl.log_ram(addr, flags);
+                }
+            }
+        }
+
+        if self.sanitizer.is_some() && !self.mikey.cpu_stalled() {
+            self.sanitize_access(false);
+        }
+
+        if self.heatmap.is_some() && !self.mikey.cpu_stalled() {
+            let addr = self.bus.addr();
+            if let (Some(flags), Some(heatmap)) = (self.cpu_read_flags(addr), &mut self.heatmap) {
+                let access = if flags.intersects(CdlFlags::opcode | CdlFlags::operand) {
+                    MemoryAccess::Execute
+                } else {
+                    MemoryAccess::Read
+                };
+                heatmap.log(addr, access);
+            }
+        }
+    }
+
+    fn sanitize_access(&mut self, write: bool) {
+        let addr = self.bus.addr();
+        if !write && self.cpu_read_flags(addr).is_none() {
+            return;
+        }
+        let tick = self.mikey.ticks();
+        let pc = self.mikey.cpu().last_ir_pc;
+        let ram = self.ram_mapped(addr, write);
+        let mut issues: [Option<SanitizerIssue>; 2] = [None; 2];
+        if !ram {
+            issues[0] = if write && register_read_only(addr) {
+                Some(SanitizerIssue::ReadOnlyWrite(addr))
+            } else if !write && register_write_only(addr) {
+                Some(SanitizerIssue::WriteOnlyRead(addr))
+            } else if !write
+                && self.mikey.cpu_pins().is_set(M6502_SYNC)
+                && (SUZ_ADDR..=ROM_ADDR_B).contains(&addr)
+            {
+                Some(SanitizerIssue::ExecuteRegister(addr))
+            } else {
+                None
+            };
+            if (SUZ_ADDR..=MIK_ADDR_B).contains(&addr)
+                && addr != SPRSYS
+                && self.suzy.sprite_working()
+            {
+                issues[1] = Some(SanitizerIssue::SuzyBusy(addr));
+            }
+        }
+        let Some(sanitizer) = &mut self.sanitizer else {
+            return;
+        };
+        if ram {
+            if write {
+                sanitizer.ram_written(addr);
+            } else {
+                sanitizer.ram_read(tick, pc, addr);
+            }
+        }
+        for issue in issues.into_iter().flatten() {
+            sanitizer.report(tick, pc, issue);
+        }
     }
 
     pub fn cpu_mem(&self, addr: u16) -> u8 {
@@ -268,15 +444,34 @@ impl Lynx {
         self.ram.tick(&mut self.bus);
         self.rom.tick(&mut self.bus);
         self.vectors.tick(&mut self.bus);
+        if self.monitors_enabled && self.sprite_costs.is_some() {
+            self.suzy.count_bus_tick(&self.bus, self.cpu_wake_pending);
+        }
         self.suzy.tick(&mut self.bus, &mut self.ram);
+        if self.suzy.dma_log_enabled() {
+            self.suzy_dma_done();
+        }
         let mut switches = self.switches_cache;
+        let cart_status = self.bus.status();
         self.cart
             .tick(&mut self.bus, self.mikey.registers_mut(), &mut switches);
+        if self.monitors_enabled
+            && cart_status != self.bus.status()
+            && matches!(
+                self.bus.status(),
+                BusStatus::PeekIncCartRipple | BusStatus::PokeIncCartRipple
+            )
+        {
+            self.cart_accessed(cart_status);
+        }
         if self.switches_cache != switches {
             self.switches_cache = switches;
             self.suzy.set_switches(switches.bits());
         }
         self.mikey.tick(&mut self.bus, &mut self.cart, &self.ram);
+        if self.monitors_enabled {
+            self.monitor();
+        }
 
         // #[cfg(debug_assertions)]
         // if self.last_ir_pc != self.mikey.cpu().last_ir_pc {
@@ -291,6 +486,414 @@ impl Lynx {
         // }
     }
 
+    fn monitor(&mut self) {
+        if self.sprite_costs.is_some() {
+            // Woken up by an interrupt, the CPU waits until it gets the bus back.
+            self.cpu_wake_pending = self.mikey.cpu_asleep()
+                && (self.cpu_wake_pending || self.mikey.raised_interrupts() != 0);
+        }
+        if self.cdl.is_some() && self.mikey.last_video_dma_tick() == self.mikey.ticks() {
+            self.video_dma_done();
+        }
+        if self.profiler.is_some() {
+            self.profile();
+        }
+        if self.sanitizer.is_some() && self.mikey.last_instruction_tick() == self.mikey.ticks() {
+            let cpu = self.mikey.cpu();
+            let (tick, pc, ir, sp) = (self.mikey.ticks(), cpu.last_ir_pc, cpu.ir(), cpu.s());
+            if let Some(sanitizer) = &mut self.sanitizer {
+                sanitizer.instruction(tick, pc, ir, sp);
+            }
+        }
+        if let Some(DebugPortTrigger::Brk(signature)) =
+            self.debug_port.as_ref().map(DebugPort::trigger)
+        {
+            if self.mikey.last_instruction_tick() == self.mikey.ticks() {
+                self.debug_port_brk(signature);
+            }
+        }
+        if self.events.is_some() {
+            self.log_events();
+        }
+        let frame_count = self.mikey.video().frame_count();
+        if frame_count != self.frame_count {
+            self.frame_count = frame_count;
+            self.frame_done();
+        }
+    }
+
+    fn debug_port_brk(&mut self, signature: u8) {
+        let cpu = self.mikey.cpu();
+        // interrupts run as a forced BRK, with a break flag
+        if cpu.ir() != OPCODE_BRK
+            || !cpu.break_flags().is_empty()
+            || self.cpu_mem(cpu.last_ir_pc.wrapping_add(1)) != signature
+        {
+            return;
+        }
+        self.debug_port_write(cpu.a());
+    }
+
+    fn debug_port_write(&mut self, data: u8) {
+        let (tick, pc) = (self.mikey.ticks(), self.mikey.cpu().last_ir_pc);
+        let Some(port) = &mut self.debug_port else {
+            return;
+        };
+        if port.write(tick, pc, data) == Some(DebugSignalKind::Screenshot) {
+            port.set_screenshot(self.mikey.video().rgba_screen().clone());
+        }
+    }
+
+    fn suzy_dma_done(&mut self) {
+        if let Some(cdl) = &mut self.cdl {
+            for &addr in self.suzy.dma_log() {
+                cdl.log_ram(addr, CdlFlags::sprite);
+            }
+        }
+        if let Some(sanitizer) = &mut self.sanitizer {
+            for &addr in self.suzy.dma_write_log() {
+                sanitizer.ram_written(addr);
+            }
+        }
+        self.suzy.clear_dma_log();
+    }
+
+    fn cart_accessed(&mut self, status: BusStatus) {
+        let address = self.cart.data_address();
+        if self.events.is_some() {
+            self.cart_access = match status {
+                BusStatus::PeekCart0 => Some(EventKind::CartRead { bank: 0, address }),
+                BusStatus::PeekCart1 => Some(EventKind::CartRead { bank: 1, address }),
+                BusStatus::PokeCart0 => Some(EventKind::CartWrite { bank: 0, address }),
+                BusStatus::PokeCart1 => Some(EventKind::CartWrite { bank: 1, address }),
+                _ => None,
+            };
+        }
+        if status != BusStatus::PeekCart0
+            || self.mikey.registers().data(SYSCTL1) & SYSCTL1_POWER == 0
+        {
+            return;
+        }
+        if let Some(cdl) = &mut self.cdl {
+            cdl.log_cart(address, CdlFlags::data);
+        }
+    }
+
+    fn video_dma_done(&mut self) {
+        if let Some(cdl) = &mut self.cdl {
+            for addr in self.mikey.video_dma_addresses() {
+                cdl.log_ram(addr, CdlFlags::video);
+            }
+        }
+    }
+
+    fn profile(&mut self) {
+        let activity = if self.mikey.video_dma_active() {
+            ProfilerActivity::VideoDma
+        } else if self.bus.grant() {
+            ProfilerActivity::Cpu
+        } else if self.suzy.sprite_working() {
+            ProfilerActivity::Suzy
+        } else {
+            ProfilerActivity::Sleep
+        };
+        let cpu = self.mikey.cpu();
+        let new_instruction = self.mikey.last_instruction_tick() == self.mikey.ticks();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.tick(new_instruction, cpu.last_ir_pc, cpu.ir(), cpu.s(), activity);
+        }
+    }
+
+    fn frame_done(&mut self) {
+        if let Some(events) = &mut self.events {
+            events.end_frame();
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end_frame();
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.end_frame();
+        }
+        if let Some(collisions) = &mut self.collisions {
+            let base = self.suzy.registers().u16(COLLBASL);
+            collisions.end_frame(&self.ram, base, self.suzy.collision_log());
+            self.suzy.clear_collision_log();
+        }
+        if let Some(sprite_draws) = &mut self.sprite_draws {
+            sprite_draws.end_frame(self.suzy.draw_log());
+            self.suzy.clear_draw_log();
+        }
+        if let Some(sprite_costs) = &mut self.sprite_costs {
+            sprite_costs.end_frame(self.suzy.cost_log());
+            self.suzy.clear_cost_log();
+        }
+    }
+
+    fn log_events(&mut self) {
+        let timers = self.mikey.timers();
+        let uart = self.mikey.uart();
+        let sample = EventSample {
+            timer_underflows: timers.underflows(),
+            interrupts: self.mikey.raised_interrupts(),
+            video_dma: if self.mikey.last_video_dma_tick() == self.mikey.ticks() {
+                self.mikey.video_dma_addresses().next()
+            } else {
+                None
+            },
+            sprite_working: self.suzy.sprite_working(),
+            cpu_asleep: self.mikey.cpu_asleep(),
+            uart_transmitted: uart.transmitted(),
+            uart_received: uart.received(),
+            cart: self.cart_access.take(),
+        };
+        if let Some(events) = &mut self.events {
+            events.tick(self.mikey.ticks(), &sample);
+        }
+    }
+
+    /// Enables or disables the event log. Disabling it drops the collected events.
+    pub fn set_events_enabled(&mut self, enabled: bool) {
+        self.events = if enabled {
+            Some(self.events.take().unwrap_or_default())
+        } else {
+            None
+        };
+        self.update_monitors();
+    }
+
+    #[must_use]
+    pub fn events(&self) -> Option<&EventLog> {
+        self.events.as_ref()
+    }
+
+    pub fn events_mut(&mut self) -> Option<&mut EventLog> {
+        self.events.as_mut()
+    }
+
+    /// Enables or disables the memory access heatmap. Disabling it drops the collected data.
+    pub fn set_heatmap_enabled(&mut self, enabled: bool) {
+        self.heatmap = if enabled {
+            Some(self.heatmap.take().unwrap_or_default())
+        } else {
+            None
+        };
+        self.update_monitors();
+    }
+
+    #[must_use]
+    pub fn heatmap(&self) -> Option<&MemoryHeatmap> {
+        self.heatmap.as_ref()
+    }
+
+    pub fn heatmap_mut(&mut self) -> Option<&mut MemoryHeatmap> {
+        self.heatmap.as_mut()
+    }
+
+    /// Enables or disables the CPU profiler. Disabling it drops the collected data.
+    pub fn set_profiler_enabled(&mut self, enabled: bool) {
+        self.profiler = if enabled {
+            Some(self.profiler.take().unwrap_or_default())
+        } else {
+            None
+        };
+        self.update_monitors();
+    }
+
+    #[must_use]
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    pub fn profiler_mut(&mut self) -> Option<&mut Profiler> {
+        self.profiler.as_mut()
+    }
+
+    /// Enables or disables the Code/Data Logger. Disabling it drops the collected maps.
+    pub fn set_cdl_enabled(&mut self, enabled: bool) {
+        self.cdl = if enabled {
+            Some(
+                self.cdl
+                    .take()
+                    .unwrap_or_else(|| CodeDataLogger::new(self.cart.data_size())),
+            )
+        } else {
+            None
+        };
+        self.update_dma_log();
+        self.update_monitors();
+    }
+
+    fn update_monitors(&mut self) {
+        self.monitors_enabled = self.cdl.is_some()
+            || self.profiler.is_some()
+            || self.heatmap.is_some()
+            || self.sanitizer.is_some()
+            || self.events.is_some()
+            || self.collisions.is_some()
+            || self.sprite_draws.is_some()
+            || self.sprite_costs.is_some()
+            || self.debug_port.is_some();
+        // the frame count is only followed while monitoring
+        self.frame_count = self.mikey.video().frame_count();
+    }
+
+    fn update_dma_log(&mut self) {
+        self.suzy
+            .set_dma_log_enabled(self.cdl.is_some() || self.sanitizer.is_some());
+    }
+
+    /// Turns the Suzy logs the enabled monitors need back on, after Suzy got replaced.
+    fn update_suzy_logs(&mut self) {
+        self.update_dma_log();
+        self.suzy
+            .set_collision_log_enabled(self.collisions.is_some());
+        self.suzy.set_draw_log_enabled(self.sprite_draws.is_some());
+        self.suzy.set_cost_log_enabled(self.sprite_costs.is_some());
+    }
+
+    /// Enables or disables the sanitizer. Disabling it drops the reports.
+    ///
+    /// Enabled before the console first runs, the whole RAM starts uninitialized. Enabled
+    /// later, the RAM is assumed initialized until the next reset.
+    pub fn set_sanitizer_enabled(&mut self, enabled: bool) {
+        self.sanitizer = if enabled {
+            Some(self.sanitizer.take().unwrap_or_else(|| {
+                let mut sanitizer = Sanitizer::new();
+                if self.mikey.ticks() != 0 {
+                    sanitizer.assume_ram_written();
+                }
+                sanitizer
+            }))
+        } else {
+            None
+        };
+        self.update_dma_log();
+        self.update_monitors();
+    }
+
+    #[must_use]
+    pub fn sanitizer(&self) -> Option<&Sanitizer> {
+        self.sanitizer.as_ref()
+    }
+
+    pub fn sanitizer_mut(&mut self) -> Option<&mut Sanitizer> {
+        self.sanitizer.as_mut()
+    }
+
+    /// Enables or disables the virtual debug port, off on stock hardware. Disabling it drops
+    /// the log and the signals.
+    pub fn set_debug_port_enabled(&mut self, enabled: bool, trigger: DebugPortTrigger) {
+        self.debug_port = enabled.then(|| DebugPort::new(trigger));
+        self.update_monitors();
+    }
+
+    #[must_use]
+    pub fn debug_port(&self) -> Option<&DebugPort> {
+        self.debug_port.as_ref()
+    }
+
+    pub fn debug_port_mut(&mut self) -> Option<&mut DebugPort> {
+        self.debug_port.as_mut()
+    }
+
+    #[must_use]
+    pub fn cdl(&self) -> Option<&CodeDataLogger> {
+        self.cdl.as_ref()
+    }
+
+    pub fn cdl_mut(&mut self) -> Option<&mut CodeDataLogger> {
+        self.cdl.as_mut()
+    }
+
+    /// Enables or disables the collision buffer capture at the end of every frame.
+    pub fn set_collisions_enabled(&mut self, enabled: bool) {
+        self.collisions = if enabled {
+            Some(self.collisions.take().unwrap_or_default())
+        } else {
+            None
+        };
+        self.suzy.set_collision_log_enabled(enabled);
+        self.update_monitors();
+    }
+
+    /// Collision buffer and per sprite collision results of the last complete frame.
+    #[must_use]
+    pub fn collisions(&self) -> Option<&CollisionFrame> {
+        self.collisions.as_ref().map(CollisionMonitor::last_frame)
+    }
+
+    /// Enables or disables the recording of the sprites drawn during every frame.
+    pub fn set_sprite_draws_enabled(&mut self, enabled: bool) {
+        self.sprite_draws = if enabled {
+            Some(self.sprite_draws.take().unwrap_or_default())
+        } else {
+            None
+        };
+        self.suzy.set_draw_log_enabled(enabled);
+        self.update_monitors();
+    }
+
+    /// Sprites of the last complete frame in drawing order, with their screen bounds.
+    #[must_use]
+    pub fn sprite_draws(&self) -> Option<&[SpriteDraw]> {
+        self.sprite_draws.as_ref().map(SpriteDrawLog::last_frame)
+    }
+
+    /// RGBA overlay with the outlines of the sprites of the last complete frame.
+    #[must_use]
+    pub fn sprite_draws_overlay(&self) -> Option<Vec<u8>> {
+        self.sprite_draws.as_ref().map(SpriteDrawLog::overlay_rgba)
+    }
+
+    /// Enables or disables the sprite engine bus usage and cost statistics.
+    pub fn set_sprite_costs_enabled(&mut self, enabled: bool) {
+        self.sprite_costs = if enabled {
+            Some(self.sprite_costs.take().unwrap_or_default())
+        } else {
+            None
+        };
+        self.suzy.set_cost_log_enabled(enabled);
+        self.update_monitors();
+    }
+
+    /// Per run and per sprite costs of the `SPRGO` runs completed during the last complete frame.
+    #[must_use]
+    pub fn sprite_costs(&self) -> Option<&FrameSpriteCost> {
+        self.sprite_costs
+            .as_ref()
+            .map(SpriteCostMonitor::last_frame)
+    }
+
+    /// Decodes the sprite chain starting at `SCBNEXT`, without touching the emulation state.
+    #[must_use]
+    pub fn scb_chain(&self) -> ScbChain {
+        ScbChain::walk(&self.ram, &self.suzy, self.suzy.registers().sbc_next())
+    }
+
+    /// Decodes the sprite of `scb` from RAM, with the current Suzy size offsets.
+    #[must_use]
+    pub fn decode_sprite(&self, scb: &ScbRecord) -> SpriteImage {
+        let regs = self.suzy.registers();
+        let decoder = SpriteDecoder {
+            hsize_offset: regs.u16(HSIZOFFL),
+            vsize_offset: regs.u16(VSIZOFFL),
+            ..SpriteDecoder::from(scb)
+        };
+        decoder.decode_ram(&self.ram, scb.data)
+    }
+
+    /// The current Mikey palette.
+    #[must_use]
+    pub fn palette(&self) -> Palette {
+        mikey_palette(self.mikey.registers())
+    }
+
+    /// Named state of the CPU, Mikey and Suzy registers.
+    #[must_use]
+    pub fn hardware_snapshot(&self) -> HardwareSnapshot {
+        HardwareSnapshot::capture(&self.mikey, &self.suzy, &self.ram)
+    }
+
     pub fn bus(&self) -> &Bus {
         &self.bus
     }
@@ -299,6 +902,10 @@ impl Lynx {
         &self.ram
     }
 
+    pub fn ram_mut(&mut self) -> &mut Ram {
+        &mut self.ram
+    }
+
     pub fn rom(&self) -> &Rom {
         &self.rom
     }
@@ -386,16 +993,29 @@ impl Lynx {
     pub fn reset(&mut self) {
         self.bus = Bus::new();
         self.ram = Ram::new();
+        self.rom.reset();
         self.vectors = Vectors::new();
+        let math_mode = self.suzy.math_mode();
         self.suzy = Suzy::new();
+        self.suzy.set_math_mode(math_mode);
+        self.update_suzy_logs();
+        self.cpu_wake_pending = false;
+        if let Some(sanitizer) = &mut self.sanitizer {
+            sanitizer.reset_memory();
+        }
         self.mikey.reset();
         self.cart.reset();
         self.last_ir_pc = 0;
         self.initialize();
     }
 
+    /// Rebuilds the state left out of the save states, after a load.
+    pub(crate) fn restore(&mut self) {
+        self.mikey.restore(&self.bus);
+    }
+
     pub fn serialize_size(&self) -> usize {
-        postcard::experimental::serialized_size(&self).unwrap()
+        crate::SAVESTATE_HEADER.len() + postcard::experimental::serialized_size(&self).unwrap()
     }
 
     pub fn audio_sample(&self) -> (i16, i16) {
@@ -422,6 +1042,11 @@ impl Lynx {
         self.ram.data()
     }
 
+    /// Selects whether the Suzy math unit reproduces the hardware bugs.
+    pub fn set_math_mode(&mut self, mode: MathMode) {
+        self.suzy.set_math_mode(mode);
+    }
+
     pub fn set_comlynx_cable_present(&mut self, present: bool) {
         self.mikey.set_comlynx_cable_present(present);
     }
@@ -432,10 +1057,62 @@ impl Lynx {
         self.mikey.set_comlynx_cable_present(true);
     }
 
+    /// Plugs the console in a shared memory link, see `ComlynxCable::new`.
+    #[cfg(feature = "comlynx_shared_memory")]
+    pub fn set_comlynx_cable(&mut self, cable: ComlynxCable) {
+        self.mikey.set_comlynx_cable(cable);
+        self.mikey.set_comlynx_cable_present(true);
+    }
+
     pub fn comlynx_cable(&self) -> &ComlynxCable {
         self.mikey.comlynx_cable()
     }
 
+    /// Plugs an emulated device in the redeye port, next to the cable. Returns its index.
+    pub fn add_comlynx_device(&mut self, device: Box<dyn ComlynxDevice>) -> usize {
+        self.mikey.set_comlynx_cable_present(true);
+        self.mikey.uart_mut().add_device(device)
+    }
+
+    pub fn remove_comlynx_device(&mut self, index: usize) -> Box<dyn ComlynxDevice> {
+        self.mikey.uart_mut().remove_device(index)
+    }
+
+    /// Uploads a BS93 program to the BLL loader running on the console, without a reset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bs93` is not a BS93 file or is shorter than its header says.
+    #[cfg(feature = "bll_upload")]
+    pub fn upload_bll(&mut self, bs93: &[u8]) -> Result<BllUploadProgress, &'static str> {
+        let upload = BllUpload::new(bs93)?;
+        let progress = upload.progress();
+        self.add_comlynx_device(Box::new(upload));
+        Ok(progress)
+    }
+
+    pub fn comlynx_device_count(&self) -> usize {
+        self.mikey.uart().device_count()
+    }
+
+    /// Starts or stops recording the `ComLynx` traffic, `node` identifies the console in the capture.
+    pub fn set_comlynx_capture_enabled(&mut self, enabled: bool, node: u8) {
+        self.mikey.uart_mut().set_capture_enabled(enabled, node);
+    }
+
+    pub fn comlynx_capture(&self) -> Option<&ComlynxCapture> {
+        self.mikey.uart().capture()
+    }
+
+    pub fn comlynx_capture_mut(&mut self) -> Option<&mut ComlynxCapture> {
+        self.mikey.uart_mut().capture_mut()
+    }
+
+    /// Injects the remote traffic of a capture at its recorded ticks, `None` stops it.
+    pub fn set_comlynx_replay(&mut self, replay: Option<ComlynxReplay>) {
+        self.mikey.uart_mut().set_replay(replay);
+    }
+
     pub fn cart_mut(&mut self) -> &mut Cartridge {
         &mut self.cart
     }
@@ -445,8 +1122,9 @@ impl Lynx {
         let _ = self.comlynx_ext_rx.as_ref().unwrap().send(data);
     }
 
+    /// Next byte transmitted, with the tick it was loaded in the transmit shift register.
     #[cfg(feature = "comlynx_external")]
-    pub fn comlynx_ext_tx(&mut self) -> Option<u8> {
+    pub fn comlynx_ext_tx(&mut self) -> Option<(u64, u8)> {
         self.comlynx_ext_tx
             .as_ref()
             .unwrap()
@@ -535,6 +535,18 @@ impl M6502 {
         self.ir_step
     }
 
+    #[must_use]
+    pub fn ir(&self) -> u8 {
+        self.ir
+    }
+
+    /// `false` when the read on the bus is a dummy cycle: the next step of the instruction
+    /// does not use its data.
+    #[must_use]
+    pub fn read_used(&self) -> bool {
+        (u16::from(DATA_STEPS[self.ir as usize]) >> self.ir_step) & 1 != 0
+    }
+
     pub fn tick(&mut self, pins: CPUPins) -> CPUPins {
         let mut ps = pins;
         if ps.is_set(M6502_SYNC | M6502_IRQ | M6502_NMI | M6502_RDY | M6502_RES) {
@@ -624,6 +636,53 @@ impl fmt::Debug for M6502 {
     }
 }
 
+/// Length in bytes of every opcode, operands included.
+const INSTRUCTION_LENGTHS: [u8; 0x100] = [
+/*  x0 x1 x2 x3 x4 x5 x6 x7 x8 x9 xA xB xC xD xE xF */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 2, 1, 1, 3, 3, 3, 3, /* 0x */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 3, 1, 1, 3, 3, 3, 3, /* 1x */
+    3, 2, 2, 1, 2, 2, 2, 2, 1, 2, 1, 1, 3, 3, 3, 3, /* 2x */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 3, 1, 1, 3, 3, 3, 3, /* 3x */
+    1, 2, 2, 1, 2, 2, 2, 2, 1, 2, 1, 1, 3, 3, 3, 3, /* 4x */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 3, 1, 1, 3, 3, 3, 3, /* 5x */
+    1, 2, 2, 1, 2, 2, 2, 2, 1, 2, 1, 1, 3, 3, 3, 3, /* 6x */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 3, 1, 1, 3, 3, 3, 3, /* 7x */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 2, 1, 1, 3, 3, 3, 3, /* 8x */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 3, 1, 1, 3, 3, 3, 3, /* 9x */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 2, 1, 1, 3, 3, 3, 3, /* Ax */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 3, 1, 1, 3, 3, 3, 3, /* Bx */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 2, 1, 1, 3, 3, 3, 3, /* Cx */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 3, 1, 1, 3, 3, 3, 3, /* Dx */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 2, 1, 1, 3, 3, 3, 3, /* Ex */
+    2, 2, 2, 1, 2, 2, 2, 2, 1, 3, 1, 1, 3, 3, 3, 3, /* Fx */
+];
+
+#[must_use]
+pub const fn instruction_length(opcode: u8) -> u8 {
+    INSTRUCTION_LENGTHS[opcode as usize]
+}
+
+/// Per opcode, bit n is set when step n of `INSTRUCTIONS` uses the data read by the step before.
+const DATA_STEPS: [u8; 0x100] = [
+/*  x0    x1    x2    x3    x4    x5    x6    x7    x8    x9    xA    xB    xC    xD    xE    xF */
+    0x60, 0x3A, 0x00, 0x00, 0x06, 0x06, 0x06, 0x06, 0x00, 0x02, 0x00, 0x00, 0x0E, 0x0E, 0x0E, 0x16, /* 0x */
+    0x02, 0x2E, 0x1E, 0x00, 0x06, 0x0A, 0x0A, 0x06, 0x00, 0x16, 0x00, 0x00, 0x0E, 0x16, 0x16, 0x16, /* 1x */
+    0x22, 0x3A, 0x00, 0x00, 0x06, 0x06, 0x06, 0x06, 0x08, 0x02, 0x00, 0x00, 0x0E, 0x0E, 0x0E, 0x16, /* 2x */
+    0x02, 0x2E, 0x1E, 0x00, 0x06, 0x0A, 0x0A, 0x06, 0x00, 0x16, 0x00, 0x00, 0x0E, 0x16, 0x16, 0x16, /* 3x */
+    0x38, 0x3A, 0x00, 0x00, 0x00, 0x06, 0x06, 0x06, 0x00, 0x02, 0x00, 0x00, 0x06, 0x0E, 0x0E, 0x16, /* 4x */
+    0x02, 0x2E, 0x1E, 0x00, 0x00, 0x0A, 0x0A, 0x06, 0x00, 0x16, 0x00, 0x00, 0x00, 0x16, 0x16, 0x16, /* 5x */
+    0x18, 0x3A, 0x00, 0x00, 0x02, 0x06, 0x06, 0x06, 0x08, 0x02, 0x00, 0x00, 0x1E, 0x0E, 0x0E, 0x16, /* 6x */
+    0x02, 0x2E, 0x1E, 0x00, 0x02, 0x0A, 0x0A, 0x06, 0x00, 0x16, 0x08, 0x00, 0x36, 0x16, 0x16, 0x16, /* 7x */
+    0x02, 0x1A, 0x00, 0x00, 0x02, 0x02, 0x02, 0x06, 0x00, 0x02, 0x00, 0x00, 0x06, 0x06, 0x06, 0x16, /* 8x */
+    0x02, 0x0E, 0x0E, 0x00, 0x02, 0x02, 0x02, 0x06, 0x00, 0x06, 0x00, 0x00, 0x06, 0x06, 0x06, 0x16, /* 9x */
+    0x02, 0x3A, 0x02, 0x00, 0x06, 0x06, 0x06, 0x06, 0x00, 0x02, 0x00, 0x00, 0x0E, 0x0E, 0x0E, 0x16, /* Ax */
+    0x02, 0x2E, 0x1E, 0x00, 0x0A, 0x0A, 0x0A, 0x06, 0x00, 0x16, 0x00, 0x00, 0x16, 0x16, 0x16, 0x16, /* Bx */
+    0x02, 0x3A, 0x00, 0x00, 0x06, 0x06, 0x06, 0x06, 0x00, 0x02, 0x00, 0x00, 0x0E, 0x0E, 0x0E, 0x16, /* Cx */
+    0x02, 0x2E, 0x1E, 0x00, 0x00, 0x0A, 0x0A, 0x06, 0x00, 0x16, 0x00, 0x00, 0x00, 0x16, 0x16, 0x16, /* Dx */
+    0x02, 0x3A, 0x00, 0x00, 0x06, 0x06, 0x06, 0x06, 0x00, 0x02, 0x00, 0x00, 0x0E, 0x0E, 0x0E, 0x16, /* Ex */
+    0x02, 0x2E, 0x1E, 0x00, 0x00, 0x0A, 0x0A, 0x06, 0x00, 0x16, 0x08, 0x00, 0x00, 0x16, 0x16, 0x16, /* Fx */
+];
+
 const INSTRUCTIONS: [InstructionSteps; 0x100] =
     [
         /* 0x00 BRK */ 
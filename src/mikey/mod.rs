@@ -77,6 +77,18 @@ pub struct Mikey {
     flipped: i8,
     bus_grant_bkup: Option<bool>,
     comlynx_cable_present: bool,
+    #[serde(skip)]
+    cpu_asleep: bool,
+    #[serde(skip)]
+    cpu_stalled: bool,
+    #[serde(skip)]
+    last_video_dma_tick: u64,
+    #[serde(skip)]
+    video_dma_end_tick: u64,
+    #[serde(skip)]
+    last_instruction_tick: u64,
+    #[serde(skip)]
+    raised_interrupts: u8,
 }
 
 impl Mikey {
@@ -96,6 +108,12 @@ impl Mikey {
             bus_owner: MikeyBusOwner::Cpu,
             bus_grant_bkup: None,
             comlynx_cable_present: false,
+            cpu_asleep: false,
+            cpu_stalled: false,
+            last_video_dma_tick: 0,
+            video_dma_end_tick: 0,
+            last_instruction_tick: 0,
+            raised_interrupts: 0,
         }
     }
 
@@ -108,9 +126,17 @@ impl Mikey {
         self.video = Video::new();
         self.video_buffer_curr_addr = 0;
         self.bus_owner = MikeyBusOwner::Cpu;
+        self.cpu_asleep = false;
+        self.video_dma_end_tick = 0;
         self.uart.reset();
     }
 
+    /// Rebuilds the state left out of the save states.
+    pub(crate) fn restore(&mut self, bus: &Bus) {
+        // off the bus from `CPUSLEEP` until woken up, the video DMA keeps the grant aside
+        self.cpu_asleep = !self.bus_grant_bkup.unwrap_or(bus.grant());
+    }
+
     pub fn cpu_prefetch(&mut self, pc: u16, rom: &mut Rom) {
         trace!("- CPU prefetch 0x{pc:04x}");
         self.cpu_pins.set(M6502_SYNC);
@@ -121,6 +147,11 @@ impl Mikey {
     }
 
     pub fn cpu_tick(&mut self, bus: &mut Bus) {
+        // "RDY pin is only checked during read cycles", the core will repeat the same read.
+        self.cpu_stalled = self.cpu_pins.is_set(M6502_RDY) && self.cpu_pins.is_set(M6502_RW);
+        if !self.cpu_stalled && self.cpu_pins.is_set(M6502_SYNC) {
+            self.last_instruction_tick = self.ticks;
+        }
         self.cpu_pins = self.cpu.tick(self.cpu_pins);
         let addr = self.cpu_pins.ga();
 
@@ -140,14 +171,18 @@ impl Mikey {
         let (mut int, int4_done) = self.timers.tick_all();
 
         if int4_done {
-            // "The interrupt bit for timer 4 (UART baud rate) is driven by receiver or transmitter ready bit of the UART."
-            if self.uart.tick(&mut self.registers) {
-                int |= INT_TIMER4;
-            }
+            self.uart.tick(&mut self.registers, self.ticks);
         }
 
-        if self.registers.serctl_w_is_flag_set(SerCtlW::tx_int_en)
-            && self.registers.serctl_r_is_flag_set(SerCtlR::tx_rdy)
+        /* "
+        The interrupt bit for timer 4 (UART baud rate) is driven by receiver or transmitter ready bit of the UART.
+        Both the transmit and receive interrupts are 'level' sensitive, rather than 'edge' sensitive.
+        This means that an interrupt will be continuously generated as long as it is enabled and its UART buffer is ready.
+        " */
+        if (self.registers.serctl_w_is_flag_set(SerCtlW::tx_int_en)
+            && self.registers.serctl_r_is_flag_set(SerCtlR::tx_rdy))
+            || (self.registers.serctl_w_is_flag_set(SerCtlW::rx_int_en)
+                && self.registers.serctl_r_is_flag_set(SerCtlR::rx_rdy))
         {
             int |= INT_TIMER4;
         }
@@ -159,6 +194,7 @@ impl Mikey {
 
         self.video.tick();
 
+        self.raised_interrupts = int;
         if int != 0 {
             int |= self.registers.data(INTSET);
             self.registers.set_data(INTSET, int);
@@ -186,7 +222,12 @@ impl Mikey {
                     self.cpu_pins.pin_on(M6502_IRQ);
                 }
 
-                if bus.grant() { self.cpu_pins.pin_off(M6502_RDY) } else { self.cpu_pins.pin_on(M6502_RDY) }
+                if bus.grant() {
+                    self.cpu_pins.pin_off(M6502_RDY);
+                    self.cpu_asleep = false;
+                } else {
+                    self.cpu_pins.pin_on(M6502_RDY);
+                }
 
                 if self.registers.ir() != MikeyInstruction::None {
                     self.process_ir_step(bus, cart);
@@ -198,9 +239,9 @@ impl Mikey {
             }
             MikeyBusOwner::RefreshAndVideo => {
                 
-                let mut pixs: Vec<u8> = 
-                (0..VIDEO_DMA_BUFFER_LENGTH as i32)
-                    .map(|i| dma_ram.get((i * i32::from(self.flipped) + self.video_buffer_curr_addr as i32) as u16))
+                let mut pixs: Vec<u8> = self
+                    .video_dma_addresses()
+                    .map(|addr| dma_ram.get(addr))
                     .collect();
 
                 if self.flipped < 0 {
@@ -208,6 +249,7 @@ impl Mikey {
                 }
 
                 self.video.push_pix_buffer(&pixs);
+                self.last_video_dma_tick = self.ticks;
 
                 self.bus_owner = MikeyBusOwner::Cpu;
                 bus.set_status(BusStatus::None);
@@ -261,6 +303,7 @@ impl Mikey {
                 }
                 self.bus_owner = MikeyBusOwner::RefreshAndVideo;
                 self.registers.set_ticks_delay(REFRESH_AND_VIDEO_DMA_TICKS);
+                self.video_dma_end_tick = self.ticks + u64::from(REFRESH_AND_VIDEO_DMA_TICKS);
                 self.video_buffer_curr_addr = (self.disp_addr as isize + isize::from(self.flipped) * screen_pixel_base as isize) as usize;
                 trace!(
                     "[{}] Need pixels @ 0x{:04X} (0x{:04X}+0x{:04X})",
@@ -515,6 +558,7 @@ impl Mikey {
             MikeyInstruction::CpuSleep => {
                 self.registers.reset_ir();
                 bus.set_grant(false);
+                self.cpu_asleep = true;
                 bus.set_status(BusStatus::PokeDone);
             }
             MikeyInstruction::PeekDispCtl => {
@@ -623,6 +667,50 @@ impl Mikey {
         self.cpu_pins
     }
 
+    /// `true` from a `CPUSLEEP` write until the CPU runs again, once Suzy is done or an
+    /// interrupt woke it up. Video DMA fetches in between do not wake it.
+    #[must_use]
+    pub fn cpu_asleep(&self) -> bool {
+        self.cpu_asleep
+    }
+
+    /// `true` when the last CPU bus cycle was a repeated read while RDY was held.
+    #[must_use]
+    pub fn cpu_stalled(&self) -> bool {
+        self.cpu_stalled
+    }
+
+    #[must_use]
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Tick of the last video DMA fetch, see `video_dma_addresses`.
+    #[must_use]
+    pub fn last_video_dma_tick(&self) -> u64 {
+        self.last_video_dma_tick
+    }
+
+    /// `true` while the CPU is held off the bus by a video DMA fetch: from the tick Mikey
+    /// takes the bus to the end of the DMA delay.
+    #[must_use]
+    pub fn video_dma_active(&self) -> bool {
+        self.video_dma_end_tick != 0 && self.ticks <= self.video_dma_end_tick
+    }
+
+    /// Tick at which the CPU last started a new instruction.
+    #[must_use]
+    pub fn last_instruction_tick(&self) -> u64 {
+        self.last_instruction_tick
+    }
+
+    /// Addresses of the last video DMA fetch.
+    pub fn video_dma_addresses(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..VIDEO_DMA_BUFFER_LENGTH as i32).map(|i| {
+            (i * i32::from(self.flipped) + self.video_buffer_curr_addr as i32) as u16
+        })
+    }
+
     #[must_use]
     pub fn cpu(&self) -> &M6502 {
         &self.cpu
@@ -642,6 +730,12 @@ impl Mikey {
         &self.timers
     }
 
+    /// Interrupt bits raised on the last tick.
+    #[must_use]
+    pub fn raised_interrupts(&self) -> u8 {
+        self.raised_interrupts
+    }
+
     #[must_use]
     pub fn audio_sample(&self) -> (i16, i16) {
         let audio0 = f32::from(self.timers.audio_out(0));
@@ -678,6 +772,16 @@ impl Mikey {
         self.uart.set_cable(cable);
     }
 
+    #[cfg(feature = "comlynx_shared_memory")]
+    pub fn set_comlynx_cable(&mut self, cable: ComlynxCable) {
+        self.uart.set_cable(cable);
+    }
+
+    #[must_use]
+    pub fn uart(&self) -> &Uart {
+        &self.uart
+    }
+
     pub(crate) fn uart_mut(&mut self) -> &mut Uart {
         &mut self.uart
     }
@@ -277,6 +277,12 @@ impl MikeyRegisters {
         self.serctl_r.bits()
     }
 
+    /// Last value written to `SERCTL`.
+    #[must_use]
+    pub fn serctl_w(&self) -> u8 {
+        self.serctl_w.bits()
+    }
+
     #[must_use]
     pub fn dispctl(&self) -> u8 {
         self.dispctl.bits()
@@ -299,6 +305,8 @@ impl MikeyRegisters {
             None => SerCtlW::empty(),
         };
 
+        uart.set_tx_open(self.serctl_w_is_flag_set(SerCtlW::tx_open));
+
         if brk && !self.serctl_w_is_flag_set(SerCtlW::tx_brk) {
             //Set redeye to high if break has been disabled
             uart.set_redeye_pin(uart::redeye_status::RedeyeStatus::High);
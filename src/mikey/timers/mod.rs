@@ -65,6 +65,8 @@ pub struct Timers {
     audio_reg: [AudioTimerRegisters; AUDIO_TIMER_COUNT],
     countdown: [u16; 16], //Round up to 256 bits for SIMD
     done: [bool; TIMER_COUNT + AUDIO_TIMER_COUNT],
+    #[serde(skip)]
+    underflows: u16,
 }
 
 impl Timers {
@@ -88,6 +90,7 @@ impl Timers {
             countdown: [0; 16],
             audio_reg: [AudioTimerRegisters::new(); AUDIO_TIMER_COUNT],
             done: [false; TIMER_COUNT + AUDIO_TIMER_COUNT],
+            underflows: 0,
         }
     }
 
@@ -165,6 +168,7 @@ impl Timers {
         let mut int: u8 = 0;
         let mut countdown_triggered: [u16; 16] = [0; 16];
         self.done[4] = false;
+        self.underflows = 0;
 
         self.check_if_triggered(&mut countdown_triggered);
 
@@ -177,6 +181,7 @@ impl Timers {
                     &mut self.timer,
                     &mut self.audio_reg,
                     &mut self.done,
+                    &mut self.underflows,
                     id,
                 );
                 self.update_timer_countdown(id);
@@ -189,6 +194,7 @@ impl Timers {
         timers: &mut [Timer],
         audio_regs: &mut [AudioTimerRegisters],
         dones: &mut [bool],
+        underflows: &mut u16,
         id: usize,
     ) -> u8 {
         let timer = &mut timers[id];
@@ -225,12 +231,13 @@ impl Timers {
         if !dones[id] {
             return 0;
         }
+        *underflows |= 1 << id;
 
         if let Some(lid) = timer.linked_timer() {
             let linked_id = lid.get() as usize;
             if timers[linked_id].is_linked() {
                 trace!("Timer #{id}, trigger linked timer #{linked_id}");
-                int |= Self::tick_timer(timers, audio_regs, dones, linked_id);
+                int |= Self::tick_timer(timers, audio_regs, dones, underflows, linked_id);
             }
         }
 
@@ -273,6 +280,13 @@ impl Timers {
         (false, 0)
     }
 
+    /// Timers (bit per timer id, audio timers from bit 8) that underflowed on the last tick.
+    #[inline]
+    #[must_use]
+    pub fn underflows(&self) -> u16 {
+        self.underflows
+    }
+
     #[inline]
     #[must_use]
     pub fn timer4_interrupt_enabled(&self) -> bool {
@@ -93,6 +93,24 @@ impl Timer {
         self.control_b
     }
 
+    #[inline]
+    #[must_use]
+    pub fn timer_done(&self) -> bool {
+        self.control_b & CTRLB_TIMER_DONE_BIT != 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn borrow_in(&self) -> bool {
+        self.control_b & CTRLB_BORROW_IN_BIT != 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn borrow_out(&self) -> bool {
+        self.control_b & CTRLB_BORROW_OUT_BIT != 0
+    }
+
     pub fn reset_last_clock(&mut self) {
         self.control_b &= !CTRLB_LAST_CLOCK_BIT;
     }
@@ -8,29 +8,85 @@ use serde::{
 
 use super::{alloc, redeye_status, Deserialize, Serialize};
 
+/// Nodes driving a shared line `Low`, a bit per node from bit 0, and nodes using a push-pull
+/// driver, a bit per node from bit 8.
+pub type ComlynxLine = u16;
+
+/// Level of a shared line: a push-pull node driving `High` wins, else any node driving `Low`.
+#[must_use]
+pub fn line_status(line: ComlynxLine) -> RedeyeStatus {
+    let low = line & 0xFF;
+    let push_pull_high = (line >> 8) & !low;
+    RedeyeStatus::from(low == 0 || push_pull_high != 0)
+}
+
 pub struct ComlynxCable {
     redeye_pin: Arc<Mutex<RedeyeStatus>>,
+    open_collector: Option<(Arc<Mutex<ComlynxLine>>, u8)>,
 }
 
 impl ComlynxCable {
     #[must_use]
     pub fn new(cable: Option<Arc<Mutex<RedeyeStatus>>>) -> Self {
         if let Some(redeye_pin) = cable {
-            Self { redeye_pin }
+            Self {
+                redeye_pin,
+                open_collector: None,
+            }
         } else {
             Self {
                 redeye_pin: Arc::new(Mutex::new(RedeyeStatus::High)),
+                open_collector: None,
             }
         }
     }
 
+    /// Connects to a line shared by up to 8 nodes, see `ComlynxLine`.
+    #[must_use]
+    pub fn open_collector(line: Arc<Mutex<ComlynxLine>>, node: u8) -> Self {
+        debug_assert!(node < 8);
+        *line.lock() &= !(0x0101 << node);
+        Self {
+            redeye_pin: Arc::new(Mutex::new(RedeyeStatus::High)),
+            open_collector: Some((line, 1 << node)),
+        }
+    }
+
     #[must_use]
     pub fn status(&self) -> RedeyeStatus {
-        *self.redeye_pin.lock()
+        match &self.open_collector {
+            None => *self.redeye_pin.lock(),
+            Some((line, _)) => line_status(*line.lock()),
+        }
     }
 
     pub fn set(&mut self, status: RedeyeStatus) {
         *self.redeye_pin.lock() = status;
+        if let Some((line, bit)) = &self.open_collector {
+            match status {
+                RedeyeStatus::Low => *line.lock() |= u16::from(*bit),
+                RedeyeStatus::High => *line.lock() &= !u16::from(*bit),
+            }
+        }
+    }
+
+    /// Selects the push-pull (TTL) driver, it holds a shared line `High` against the other
+    /// nodes, instead of the open collector one.
+    pub fn set_push_pull(&mut self, push_pull: bool) {
+        if let Some((line, bit)) = &self.open_collector {
+            let bit = u16::from(*bit) << 8;
+            if push_pull {
+                *line.lock() |= bit;
+            } else {
+                *line.lock() &= !bit;
+            }
+        }
+    }
+
+    /// Level driven by this end of the cable.
+    #[must_use]
+    pub fn driven(&self) -> RedeyeStatus {
+        *self.redeye_pin.lock()
     }
 }
 
@@ -44,6 +100,7 @@ impl Clone for ComlynxCable {
     fn clone(&self) -> Self {
         Self {
             redeye_pin: self.redeye_pin.clone(),
+            open_collector: self.open_collector.clone(),
         }
     }
 }
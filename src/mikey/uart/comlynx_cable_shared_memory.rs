@@ -1,5 +1,6 @@
 use ::shared_memory::{Shmem, ShmemConf, ShmemError};
-use alloc::{fmt, string::String};
+use alloc::{fmt, string::String, vec::Vec};
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
 use redeye_status::RedeyeStatus;
 use serde::{
     de::{self, Visitor},
@@ -8,63 +9,302 @@ use serde::{
 
 use super::{alloc, redeye_status, Deserialize, Serialize};
 
+/// Link name used by `ComlynxCable::default()`.
+pub const COMLYNX_DEFAULT_LINK: &str = "redeye";
+/// Nodes a shared memory link can hold.
+pub const COMLYNX_LINK_MAX_NODES: usize = 8;
+
+/*
+Shared memory layout, all nodes of a link map it:
+  0  u32  magic "RDEY"
+  4  u8   layout version
+  8  node slots, 16 bytes each:
+       0  u8   state, free or attached
+       1  u8   level driven by the node
+       2  u8   driver, open collector or push-pull
+       4  u32  process id
+       8  u32  heartbeat, bumped at every level change
+*/
+const LINK_MAGIC: u32 = u32::from_le_bytes(*b"RDEY");
+const LINK_VERSION: u8 = 1;
+const LINK_MAGIC_OFFSET: usize = 0;
+const LINK_VERSION_OFFSET: usize = 4;
+const LINK_SLOTS_OFFSET: usize = 8;
+const LINK_SLOT_LEN: usize = 16;
+const LINK_LEN: usize = LINK_SLOTS_OFFSET + COMLYNX_LINK_MAX_NODES * LINK_SLOT_LEN;
+
+const SLOT_STATE: usize = 0;
+const SLOT_LEVEL: usize = 1;
+const SLOT_DRIVER: usize = 2;
+const SLOT_PID: usize = 4;
+const SLOT_HEARTBEAT: usize = 8;
+
+const SLOT_FREE: u8 = 0;
+const SLOT_ATTACHED: u8 = 1;
+
+const DRIVER_OPEN_COLLECTOR: u8 = 0;
+const DRIVER_PUSH_PULL: u8 = 1;
+
+#[derive(Debug)]
+pub enum ComlynxLinkError {
+    /// The link name is empty or not a valid file name.
+    InvalidName,
+    /// The shared memory couldn't be created.
+    Create(ShmemError),
+    /// The shared memory exists but couldn't be opened.
+    Open(ShmemError),
+    /// The shared memory isn't a link, or one of another layout version.
+    Incompatible,
+    /// All the node slots are taken.
+    Full,
+}
+
+impl fmt::Display for ComlynxLinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ComlynxLinkError::InvalidName => write!(f, "Invalid ComLynx link name"),
+            ComlynxLinkError::Create(e) => write!(f, "Unable to create the ComLynx link: {e}"),
+            ComlynxLinkError::Open(e) => write!(f, "Unable to open the ComLynx link: {e}"),
+            ComlynxLinkError::Incompatible => write!(f, "Incompatible ComLynx link"),
+            ComlynxLinkError::Full => write!(f, "ComLynx link is full"),
+        }
+    }
+}
+
+/// A node attached to a link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComlynxNode {
+    pub slot: usize,
+    pub pid: u32,
+    pub level: RedeyeStatus,
+    pub heartbeat: u32,
+}
+
+/// End of a `ComLynx` cable shared with other processes through a named shared memory link.
+///
+/// The redeye line is open collector, `Low` as long as any attached node drives it `Low`,
+/// unless a node using the push-pull driver holds it `High`.
 pub struct ComlynxCable {
     shmem: Shmem,
+    name: String,
+    slot: usize,
 }
 
 impl ComlynxCable {
-    /// Creates a new `ComlynxCable` instance.
+    /// Attaches to the link `name`, creating it if needed.
+    ///
+    /// A link file left behind by a crashed process is replaced, and on Linux the slots of
+    /// dead processes are freed.
     ///
     /// # Errors
     ///
-    /// Returns an error if unable to create or open the shared memory link 'redeye'.
-    pub fn new() -> Result<Self, String> {
-        let shmem = match ShmemConf::new().size(32).flink("redeye").create() {
-            Ok(m) => {
-                unsafe { *m.as_ptr() = RedeyeStatus::High.into() };
-                m
-            }
-            Err(ShmemError::LinkExists) => match ShmemConf::new().flink("redeye").open() {
-                Ok(s) => s,
-                Err(_) => match ShmemConf::new()
-                    .size(32)
-                    .flink("redeye")
-                    .force_create_flink()
-                    .create()
-                {
-                    Ok(m) => {
-                        unsafe { *m.as_ptr() = RedeyeStatus::High.into() };
-                        m
-                    }
-                    Err(e) => {
-                        return Err(format!(
-                            "Unable to create or open shmem flink 'redeye' : {e}"
-                        ))
+    /// Returns an error if the link can't be created or opened, isn't compatible or is full.
+    pub fn new(name: &str) -> Result<Self, ComlynxLinkError> {
+        if name.is_empty() || name.contains(['/', '\\']) {
+            return Err(ComlynxLinkError::InvalidName);
+        }
+        let shmem = match Self::create(name) {
+            Err(ComlynxLinkError::Create(ShmemError::LinkExists)) => {
+                match ShmemConf::new().flink(name).open() {
+                    Ok(shmem) => shmem,
+                    Err(ShmemError::MapOpenFailed(_)) => {
+                        // stale link file, its mapping is gone with its owner
+                        let _ = std::fs::remove_file(name);
+                        Self::create(name)?
                     }
-                },
-            },
-            Err(e) => {
-                return Err(format!(
-                    "Unable to create or open shmem flink 'redeye' : {e}"
-                ))
+                    Err(e) => return Err(ComlynxLinkError::Open(e)),
+                }
             }
+            shmem => shmem?,
+        };
+        if shmem.len() < LINK_LEN
+            || Self::atomic_u32(&shmem, LINK_MAGIC_OFFSET).load(Ordering::Acquire) != LINK_MAGIC
+            || Self::atomic_u8(&shmem, LINK_VERSION_OFFSET).load(Ordering::Acquire) != LINK_VERSION
+        {
+            return Err(ComlynxLinkError::Incompatible);
+        }
+
+        let mut cable = Self {
+            shmem,
+            name: name.into(),
+            // not attached yet
+            slot: COMLYNX_LINK_MAX_NODES,
         };
-        Ok(ComlynxCable { shmem })
+        cable.free_dead_nodes();
+        cable.slot = cable.claim_slot().ok_or(ComlynxLinkError::Full)?;
+        Ok(cable)
     }
 
+    fn create(name: &str) -> Result<Shmem, ComlynxLinkError> {
+        let shmem = ShmemConf::new()
+            .size(LINK_LEN)
+            .flink(name)
+            .create()
+            .map_err(ComlynxLinkError::Create)?;
+        unsafe { core::ptr::write_bytes(shmem.as_ptr(), 0, LINK_LEN) };
+        Self::atomic_u8(&shmem, LINK_VERSION_OFFSET).store(LINK_VERSION, Ordering::Release);
+        Self::atomic_u32(&shmem, LINK_MAGIC_OFFSET).store(LINK_MAGIC, Ordering::Release);
+        Ok(shmem)
+    }
+
+    fn atomic_u8(shmem: &Shmem, offset: usize) -> &AtomicU8 {
+        unsafe { &*shmem.as_ptr().add(offset).cast::<AtomicU8>() }
+    }
+
+    // the mapping is page aligned and the u32 offsets are multiples of 4
+    #[allow(clippy::cast_ptr_alignment)]
+    fn atomic_u32(shmem: &Shmem, offset: usize) -> &AtomicU32 {
+        unsafe { &*shmem.as_ptr().add(offset).cast::<AtomicU32>() }
+    }
+
+    fn slot_u8(&self, slot: usize, field: usize) -> &AtomicU8 {
+        Self::atomic_u8(
+            &self.shmem,
+            LINK_SLOTS_OFFSET + slot * LINK_SLOT_LEN + field,
+        )
+    }
+
+    fn slot_u32(&self, slot: usize, field: usize) -> &AtomicU32 {
+        Self::atomic_u32(
+            &self.shmem,
+            LINK_SLOTS_OFFSET + slot * LINK_SLOT_LEN + field,
+        )
+    }
+
+    fn claim_slot(&self) -> Option<usize> {
+        let slot = (0..COMLYNX_LINK_MAX_NODES).find(|&slot| {
+            self.slot_u8(slot, SLOT_STATE)
+                .compare_exchange(
+                    SLOT_FREE,
+                    SLOT_ATTACHED,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+        })?;
+        self.slot_u8(slot, SLOT_LEVEL)
+            .store(RedeyeStatus::High.into(), Ordering::Release);
+        self.slot_u8(slot, SLOT_DRIVER)
+            .store(DRIVER_OPEN_COLLECTOR, Ordering::Release);
+        self.slot_u32(slot, SLOT_PID)
+            .store(std::process::id(), Ordering::Release);
+        self.slot_u32(slot, SLOT_HEARTBEAT)
+            .store(0, Ordering::Release);
+        Some(slot)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn free_dead_nodes(&self) {
+        for node in self.nodes() {
+            if !std::path::Path::new(&format!("/proc/{}", node.pid)).exists() {
+                self.detach_node(node.slot);
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn free_dead_nodes(&self) {}
+
     #[must_use]
     pub fn status(&self) -> RedeyeStatus {
-        unsafe { (*self.shmem.as_ptr()).into() }
+        let mut low = false;
+        for slot in 0..COMLYNX_LINK_MAX_NODES {
+            if self.slot_u8(slot, SLOT_STATE).load(Ordering::Acquire) != SLOT_ATTACHED {
+                continue;
+            }
+            let high = self.slot_u8(slot, SLOT_LEVEL).load(Ordering::Acquire) != 0;
+            if high && self.slot_u8(slot, SLOT_DRIVER).load(Ordering::Acquire) == DRIVER_PUSH_PULL {
+                return RedeyeStatus::High;
+            }
+            low |= !high;
+        }
+        RedeyeStatus::from(!low)
     }
 
     pub fn set(&mut self, status: RedeyeStatus) {
-        unsafe { *self.shmem.as_ptr() = status.into() };
+        let previous = self
+            .slot_u8(self.slot, SLOT_LEVEL)
+            .swap(status.into(), Ordering::AcqRel);
+        if previous != u8::from(status) {
+            self.slot_u32(self.slot, SLOT_HEARTBEAT)
+                .fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Selects the push-pull (TTL) driver, it holds the line `High` against the other nodes,
+    /// instead of the open collector one.
+    pub fn set_push_pull(&mut self, push_pull: bool) {
+        let driver = if push_pull {
+            DRIVER_PUSH_PULL
+        } else {
+            DRIVER_OPEN_COLLECTOR
+        };
+        self.slot_u8(self.slot, SLOT_DRIVER)
+            .store(driver, Ordering::Release);
+    }
+
+    /// Level driven by this end of the cable.
+    #[must_use]
+    pub fn driven(&self) -> RedeyeStatus {
+        self.slot_u8(self.slot, SLOT_LEVEL)
+            .load(Ordering::Acquire)
+            .into()
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Slot of this node in the link.
+    #[must_use]
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    /// Nodes attached to the link, this one included.
+    #[must_use]
+    pub fn nodes(&self) -> Vec<ComlynxNode> {
+        (0..COMLYNX_LINK_MAX_NODES)
+            .filter(|&slot| self.slot_u8(slot, SLOT_STATE).load(Ordering::Acquire) == SLOT_ATTACHED)
+            .map(|slot| ComlynxNode {
+                slot,
+                pid: self.slot_u32(slot, SLOT_PID).load(Ordering::Acquire),
+                level: self
+                    .slot_u8(slot, SLOT_LEVEL)
+                    .load(Ordering::Acquire)
+                    .into(),
+                heartbeat: self.slot_u32(slot, SLOT_HEARTBEAT).load(Ordering::Acquire),
+            })
+            .collect()
+    }
+
+    /// Frees the slot of a node that went away without detaching, e.g. after a crash.
+    pub fn detach_node(&self, slot: usize) {
+        self.slot_u8(slot, SLOT_LEVEL)
+            .store(RedeyeStatus::High.into(), Ordering::Release);
+        self.slot_u8(slot, SLOT_DRIVER)
+            .store(DRIVER_OPEN_COLLECTOR, Ordering::Release);
+        self.slot_u8(slot, SLOT_STATE)
+            .store(SLOT_FREE, Ordering::Release);
+    }
+}
+
+impl Drop for ComlynxCable {
+    fn drop(&mut self) {
+        if self.slot >= COMLYNX_LINK_MAX_NODES {
+            return;
+        }
+        self.detach_node(self.slot);
+        // the last node out removes the link, the others leave it to the remaining nodes
+        let last = self.nodes().is_empty();
+        self.shmem.set_owner(last);
     }
 }
 
 impl Default for ComlynxCable {
     fn default() -> Self {
-        ComlynxCable::new().unwrap()
+        ComlynxCable::new(COMLYNX_DEFAULT_LINK).unwrap()
     }
 }
 
@@ -73,8 +313,7 @@ impl Serialize for ComlynxCable {
     where
         S: Serializer,
     {
-        let v = unsafe { *self.shmem.as_ptr() };
-        serializer.serialize_u8(v)
+        serializer.serialize_str(&self.name)
     }
 }
 
@@ -90,17 +329,14 @@ impl Visitor<'_> for ComlynxCableVisitor {
     type Value = ComlynxCable;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("an u8")
+        formatter.write_str("a link name")
     }
 
-    fn visit_u8<E>(self, _value: u8) -> Result<Self::Value, E>
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        match ComlynxCable::new() {
-            Ok(cable) => Ok(cable),
-            Err(e) => Err(de::Error::custom(e)),
-        }
+        ComlynxCable::new(value).map_err(de::Error::custom)
     }
 }
 
@@ -109,6 +345,109 @@ impl<'de> Deserialize<'de> for ComlynxCable {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_u8(ComlynxCableVisitor::new())
+        deserializer.deserialize_str(ComlynxCableVisitor::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn names() {
+        for name in ["", "a/b", "a\\b"] {
+            assert!(matches!(
+                ComlynxCable::new(name),
+                Err(ComlynxLinkError::InvalidName)
+            ));
+        }
+        let name = "holani_test_names";
+        let cable = ComlynxCable::new(name).unwrap();
+        assert_eq!(cable.name(), name);
+        assert!(Path::new(name).exists());
+        // the last node out removes the link
+        let other = ComlynxCable::new(name).unwrap();
+        drop(cable);
+        assert!(Path::new(name).exists());
+        drop(other);
+        assert!(!Path::new(name).exists());
+    }
+
+    #[test]
+    fn participation() {
+        let mut a = ComlynxCable::new("holani_test_participation").unwrap();
+        let mut b = ComlynxCable::new("holani_test_participation").unwrap();
+        assert_ne!(a.slot(), b.slot());
+        assert_eq!(a.nodes().len(), 2);
+        assert_eq!(a.status(), RedeyeStatus::High);
+
+        // open collector, any node pulls the line Low
+        a.set(RedeyeStatus::Low);
+        assert_eq!(a.driven(), RedeyeStatus::Low);
+        assert_eq!(b.driven(), RedeyeStatus::High);
+        assert_eq!(b.status(), RedeyeStatus::Low);
+        let node = b.nodes()[a.slot()];
+        assert_eq!(node.pid, std::process::id());
+        assert_eq!(node.level, RedeyeStatus::Low);
+        assert_eq!(node.heartbeat, 1);
+        a.set(RedeyeStatus::Low);
+        assert_eq!(b.nodes()[a.slot()].heartbeat, 1);
+
+        // a push-pull node holds it High
+        b.set_push_pull(true);
+        assert_eq!(a.status(), RedeyeStatus::High);
+        b.set(RedeyeStatus::Low);
+        assert_eq!(a.status(), RedeyeStatus::Low);
+        b.set(RedeyeStatus::High);
+        b.set_push_pull(false);
+        assert_eq!(a.status(), RedeyeStatus::Low);
+        a.set(RedeyeStatus::High);
+        assert_eq!(b.status(), RedeyeStatus::High);
+    }
+
+    #[test]
+    fn full() {
+        let name = "holani_test_full";
+        let mut cables: Vec<_> = (0..COMLYNX_LINK_MAX_NODES)
+            .map(|_| ComlynxCable::new(name).unwrap())
+            .collect();
+        assert!(matches!(
+            ComlynxCable::new(name),
+            Err(ComlynxLinkError::Full)
+        ));
+        let slot = cables.remove(3).slot;
+        assert_eq!(ComlynxCable::new(name).unwrap().slot(), slot);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn crash_cleanup() {
+        let name = "holani_test_crash";
+        let a = ComlynxCable::new(name).unwrap();
+        // a node left attached by a dead process, holding the line Low
+        let mut crashed = ComlynxCable::new(name).unwrap();
+        crashed.set(RedeyeStatus::Low);
+        let slot = crashed.slot();
+        a.slot_u32(slot, SLOT_PID)
+            .store(u32::MAX, Ordering::Release);
+        core::mem::forget(crashed);
+        assert_eq!(a.status(), RedeyeStatus::Low);
+
+        let b = ComlynxCable::new(name).unwrap();
+        assert_eq!(b.slot(), slot);
+        assert_eq!(b.status(), RedeyeStatus::High);
+        assert_eq!(a.nodes().len(), 2);
+    }
+
+    #[test]
+    fn stale_link_file() {
+        // link file of a process gone with its mapping
+        let name = "holani_test_stale";
+        std::fs::write(name, "/holani_test_stale_mapping").unwrap();
+        let cable = ComlynxCable::new(name).unwrap();
+        assert_eq!(cable.nodes().len(), 1);
+        drop(cable);
+        assert!(!Path::new(name).exists());
     }
 }
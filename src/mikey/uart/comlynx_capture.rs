@@ -0,0 +1,331 @@
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+
+/// Capture file magic, `HCLX`.
+pub const COMLYNX_CAPTURE_MAGIC: [u8; 4] = *b"HCLX";
+pub const COMLYNX_CAPTURE_VERSION: u8 = 1;
+const HEADER_LEN: usize = 8;
+const RECORD_LEN: usize = 12;
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct ComlynxFlags: u8 {
+        /// Parity bit sent or received.
+        const parity_bit = 0b0000_0001;
+        const parity_error = 0b0000_0010;
+        const frame_error = 0b0000_0100;
+        /// The byte replaced one not read yet.
+        const overrun = 0b0000_1000;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum ComlynxEventKind {
+    Transmit = 0,
+    Receive = 1,
+    /// The line has been `Low` for 24 bit periods.
+    BreakStart = 2,
+    BreakEnd = 3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum ComlynxSender {
+    /// This console, its transmissions and their echoes.
+    Local = 0,
+    /// Another console on the cable.
+    Remote = 1,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComlynxRecord {
+    /// Console tick of the event.
+    pub tick: u64,
+    pub kind: ComlynxEventKind,
+    pub sender: ComlynxSender,
+    pub data: u8,
+    pub flags: ComlynxFlags,
+}
+
+/// `ComLynx` traffic seen by a console.
+///
+/// Received frames starting with a start bit sent by this console are its own echo, marked `Local`.
+///
+/// Capture file, little endian:
+/// ```text
+/// header, 8 bytes
+///   0  [u8; 4]  "HCLX"
+///   4  u8       version, 1
+///   5  u8       node, the capturing console
+///   6  u16      0
+/// records, 12 bytes each
+///   0  u64      tick
+///   8  u8       kind: 0 transmit, 1 receive, 2 break start, 3 break end
+///   9  u8       sender: 0 local, 1 remote
+///  10  u8       data, 0 for breaks
+///  11  u8       flags: 0x01 parity bit, 0x02 parity error, 0x04 frame error, 0x08 overrun
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ComlynxCapture {
+    node: u8,
+    records: Vec<ComlynxRecord>,
+}
+
+impl ComlynxCapture {
+    #[must_use]
+    pub fn new(node: u8) -> Self {
+        Self {
+            node,
+            records: vec![],
+        }
+    }
+
+    pub(crate) fn transmit(&mut self, tick: u64, data: u8, flags: ComlynxFlags) {
+        self.push(
+            tick,
+            ComlynxEventKind::Transmit,
+            ComlynxSender::Local,
+            data,
+            flags,
+        );
+    }
+
+    pub(crate) fn receive(
+        &mut self,
+        tick: u64,
+        data: u8,
+        flags: ComlynxFlags,
+        sender: ComlynxSender,
+    ) {
+        self.push(tick, ComlynxEventKind::Receive, sender, data, flags);
+    }
+
+    pub(crate) fn replayed(&mut self, record: &ComlynxRecord) {
+        self.records.push(*record);
+    }
+
+    pub(crate) fn line_break(&mut self, tick: u64, start: bool, sender: ComlynxSender) {
+        let kind = if start {
+            ComlynxEventKind::BreakStart
+        } else {
+            ComlynxEventKind::BreakEnd
+        };
+        self.push(tick, kind, sender, 0, ComlynxFlags::empty());
+    }
+
+    fn push(
+        &mut self,
+        tick: u64,
+        kind: ComlynxEventKind,
+        sender: ComlynxSender,
+        data: u8,
+        flags: ComlynxFlags,
+    ) {
+        self.records.push(ComlynxRecord {
+            tick,
+            kind,
+            sender,
+            data,
+            flags,
+        });
+    }
+
+    #[must_use]
+    pub fn node(&self) -> u8 {
+        self.node
+    }
+
+    #[must_use]
+    pub fn records(&self) -> &[ComlynxRecord] {
+        &self.records
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Encodes the capture file.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.records.len() * RECORD_LEN);
+        bytes.extend_from_slice(&COMLYNX_CAPTURE_MAGIC);
+        bytes.extend_from_slice(&[COMLYNX_CAPTURE_VERSION, self.node, 0, 0]);
+        for record in &self.records {
+            bytes.extend_from_slice(&record.tick.to_le_bytes());
+            bytes.extend_from_slice(&[
+                record.kind as u8,
+                record.sender as u8,
+                record.data,
+                record.flags.bits(),
+            ]);
+        }
+        bytes
+    }
+
+    /// Decodes a capture file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` is not a capture file of a known version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < HEADER_LEN || bytes[..4] != COMLYNX_CAPTURE_MAGIC {
+            return Err("Not a ComLynx capture");
+        }
+        if bytes[4] != COMLYNX_CAPTURE_VERSION {
+            return Err("Unsupported ComLynx capture version");
+        }
+        if (bytes.len() - HEADER_LEN) % RECORD_LEN != 0 {
+            return Err("Truncated ComLynx capture");
+        }
+        let mut capture = Self::new(bytes[5]);
+        for record in bytes[HEADER_LEN..].chunks_exact(RECORD_LEN) {
+            let kind = match record[8] {
+                0 => ComlynxEventKind::Transmit,
+                1 => ComlynxEventKind::Receive,
+                2 => ComlynxEventKind::BreakStart,
+                3 => ComlynxEventKind::BreakEnd,
+                _ => return Err("Invalid ComLynx capture record"),
+            };
+            let sender = match record[9] {
+                0 => ComlynxSender::Local,
+                1 => ComlynxSender::Remote,
+                _ => return Err("Invalid ComLynx capture record"),
+            };
+            capture.push(
+                u64::from_le_bytes(record[..8].try_into().unwrap()),
+                kind,
+                sender,
+                record[10],
+                ComlynxFlags::from_bits_truncate(record[11]),
+            );
+        }
+        Ok(capture)
+    }
+}
+
+/// Plays the remote traffic of a capture back into a console, at the recorded ticks.
+///
+/// The console local transmissions and echoes are left to the console itself.
+#[derive(Clone, Debug)]
+pub struct ComlynxReplay {
+    records: Vec<ComlynxRecord>,
+    next: usize,
+    line_break: bool,
+}
+
+impl ComlynxReplay {
+    #[must_use]
+    pub fn new(capture: &ComlynxCapture) -> Self {
+        Self {
+            records: capture
+                .records()
+                .iter()
+                .filter(|r| {
+                    r.sender == ComlynxSender::Remote && r.kind != ComlynxEventKind::Transmit
+                })
+                .copied()
+                .collect(),
+            next: 0,
+            line_break: false,
+        }
+    }
+
+    /// Next record due at `tick`.
+    pub(crate) fn next_due(&mut self, tick: u64) -> Option<ComlynxRecord> {
+        let record = *self.records.get(self.next).filter(|r| r.tick <= tick)?;
+        self.next += 1;
+        match record.kind {
+            ComlynxEventKind::BreakStart => self.line_break = true,
+            ComlynxEventKind::BreakEnd => self.line_break = false,
+            _ => (),
+        }
+        Some(record)
+    }
+
+    /// A replayed break is in progress.
+    #[must_use]
+    pub fn line_break(&self) -> bool {
+        self.line_break
+    }
+
+    #[must_use]
+    pub fn done(&self) -> bool {
+        self.next >= self.records.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture() -> ComlynxCapture {
+        let mut capture = ComlynxCapture::new(2);
+        capture.transmit(100, 0x5A, ComlynxFlags::parity_bit);
+        capture.receive(1_000, 0x5A, ComlynxFlags::parity_bit, ComlynxSender::Local);
+        capture.line_break(u64::MAX, true, ComlynxSender::Remote);
+        capture
+    }
+
+    #[test]
+    fn round_trip() {
+        let capture = capture();
+        let bytes = capture.to_bytes();
+        assert_eq!(bytes.len(), HEADER_LEN + 3 * RECORD_LEN);
+        assert_eq!(bytes[..8], *b"HCLX\x01\x02\x00\x00");
+        assert_eq!(bytes[8..20], [100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x5A, 0x01]);
+        let decoded = ComlynxCapture::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.node(), 2);
+        assert_eq!(decoded.records(), capture.records());
+        assert_eq!(ComlynxCapture::new(7).to_bytes(), b"HCLX\x01\x07\x00\x00");
+    }
+
+    #[test]
+    fn malformed() {
+        let bytes = capture().to_bytes();
+        let not_capture = Err("Not a ComLynx capture");
+        assert_eq!(ComlynxCapture::from_bytes(&[]).map(|_| ()), not_capture);
+        assert_eq!(ComlynxCapture::from_bytes(b"HCLX").map(|_| ()), not_capture);
+        assert_eq!(
+            ComlynxCapture::from_bytes(b"HCLY\x01\x00\x00\x00").map(|_| ()),
+            not_capture
+        );
+
+        let mut version = bytes.clone();
+        version[4] = 2;
+        assert_eq!(
+            ComlynxCapture::from_bytes(&version).map(|_| ()),
+            Err("Unsupported ComLynx capture version")
+        );
+
+        let invalid = Err("Invalid ComLynx capture record");
+        let mut kind = bytes.clone();
+        kind[HEADER_LEN + 8] = 4;
+        assert_eq!(ComlynxCapture::from_bytes(&kind).map(|_| ()), invalid);
+        let mut sender = bytes.clone();
+        sender[HEADER_LEN + RECORD_LEN + 9] = 2;
+        assert_eq!(ComlynxCapture::from_bytes(&sender).map(|_| ()), invalid);
+
+        // unknown flags are dropped
+        let mut flags = bytes;
+        flags[HEADER_LEN + 11] = 0xF1;
+        let decoded = ComlynxCapture::from_bytes(&flags).unwrap();
+        assert_eq!(decoded.records()[0].flags, ComlynxFlags::parity_bit);
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = capture().to_bytes();
+        for len in [HEADER_LEN + 1, HEADER_LEN + RECORD_LEN - 1, bytes.len() - 1] {
+            assert_eq!(
+                ComlynxCapture::from_bytes(&bytes[..len]).map(|_| ()),
+                Err("Truncated ComLynx capture")
+            );
+        }
+        let header = ComlynxCapture::from_bytes(&bytes[..HEADER_LEN]).unwrap();
+        assert!(header.records().is_empty());
+        let first = ComlynxCapture::from_bytes(&bytes[..HEADER_LEN + RECORD_LEN]).unwrap();
+        assert_eq!(first.records(), &capture().records()[..1]);
+    }
+}
@@ -0,0 +1,78 @@
+use super::redeye_status::RedeyeStatus;
+
+/// An emulated device plugged in the redeye port, e.g. a PC host, a test harness or a modem.
+///
+/// Devices are ticked with the UART bit clock. They can work with the line level, the console
+/// senses `Low` when any device drives it `Low`, or with whole bytes. A device only sees the
+/// console it is attached to, not the other consoles on its cable.
+pub trait ComlynxDevice: Send {
+    /// Called every bit period with the line level, returns the level driven by the device.
+    fn tick(&mut self, _line: RedeyeStatus) -> RedeyeStatus {
+        RedeyeStatus::High
+    }
+
+    /// A byte sent by the console.
+    fn receive(&mut self, _data: u8) {}
+
+    /// A byte for the console, polled when its UART is idle with nothing left to read.
+    fn transmit(&mut self) -> Option<u8> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rom::lynx_sending;
+    use alloc::{boxed::Box, collections::vec_deque::VecDeque};
+
+    /// Answers every byte with the next value.
+    struct Increment(VecDeque<u8>);
+
+    impl ComlynxDevice for Increment {
+        fn receive(&mut self, data: u8) {
+            self.0.push_back(data.wrapping_add(1));
+        }
+
+        fn transmit(&mut self) -> Option<u8> {
+            self.0.pop_front()
+        }
+    }
+
+    /// Holds the line `Low` for some bit periods.
+    struct LineBreak(u32);
+
+    impl ComlynxDevice for LineBreak {
+        fn tick(&mut self, _line: RedeyeStatus) -> RedeyeStatus {
+            if self.0 == 0 {
+                return RedeyeStatus::High;
+            }
+            self.0 -= 1;
+            RedeyeStatus::Low
+        }
+    }
+
+    #[test]
+    fn bytes() {
+        let mut lynx = lynx_sending(&[0x41]);
+        lynx.add_comlynx_device(Box::new(Increment(VecDeque::new())));
+        for _ in 0..20_000 {
+            lynx.tick();
+        }
+        // its own echo, then the answer
+        assert_eq!(lynx.mikey().uart().received(), (2, 0x42));
+    }
+
+    #[test]
+    fn line() {
+        let mut lynx = lynx_sending(&[]);
+        lynx.add_comlynx_device(Box::new(LineBreak(30)));
+        let mut line_break = false;
+        for _ in 0..20_000 {
+            lynx.tick();
+            line_break |= lynx.mikey().registers().serctl() & 0x02 != 0;
+        }
+        assert!(line_break);
+        assert_eq!(lynx.mikey().registers().serctl() & 0x02, 0);
+    }
+}
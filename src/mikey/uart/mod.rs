@@ -1,8 +1,10 @@
+pub mod comlynx_capture;
+pub mod comlynx_device;
 pub mod redeye_status;
 
 #[cfg(not(feature = "comlynx_shared_memory"))]
 pub mod comlynx_cable_mutex;
-use alloc::{collections::vec_deque::VecDeque, vec::Vec};
+use alloc::{boxed::Box, vec::Vec};
 #[cfg(not(feature = "comlynx_shared_memory"))]
 use comlynx_cable_mutex::ComlynxCable;
 #[cfg(feature = "comlynx_shared_memory")]
@@ -10,24 +12,58 @@ pub mod comlynx_cable_shared_memory;
 use super::{alloc, trace, Deserialize, MikeyRegisters, SerCtlR, SerCtlW, Serialize};
 #[cfg(feature = "comlynx_shared_memory")]
 use comlynx_cable_shared_memory::ComlynxCable;
+use comlynx_capture::{
+    ComlynxCapture, ComlynxEventKind, ComlynxFlags, ComlynxReplay, ComlynxSender,
+};
+use comlynx_device::ComlynxDevice;
 use redeye_status::RedeyeStatus;
 
-const RX_BUFFER_LEN: usize = 2;
-const GENERATOR_DELAY: u8 = 8;
+/// UART clocks, timer 4 underflows, per bit.
+const UART_CLOCKS_PER_BIT: u8 = 8;
+/// "RXBRK: break received, 24 bit periods", in UART clocks.
+const BREAK_CLOCKS: u64 = 24 * 8;
 
 #[derive(Serialize, Deserialize)]
 pub struct Uart {
+    /// Clocks left in the bit being sent.
+    tx_clock: u8,
+    transmit_register: Vec<RedeyeStatus>,
+    transmit_holding_register: Option<u8>,
+    /// Clocks until the next receiver sample, 0 while waiting for a start bit.
+    rx_clock: u8,
+    /// Bits of the frame received so far.
     receive_register_len: u8,
     receive_register_buffer: u8,
-    receive_register: VecDeque<u8>,
+    receive_holding_register: u8,
+    /// Line level at the previous clock, a start bit begins on a falling edge.
+    rx_line: RedeyeStatus,
+    /// The frame being received began with the start bit of this transmitter, its own echo.
+    rx_echo: bool,
+    /// Clocks the line has been `Low`.
     break_count: u64,
-    transmit_register: Vec<RedeyeStatus>,
-    transmit_holding_register: Option<u8>,
+    /// `TXOPEN`, the transmitter uses the open collector driver instead of the push-pull one.
+    tx_open: bool,
     redeye_pin: ComlynxCable,
-    generator_delay: u8,
+    #[serde(skip)]
+    transmitted: (u64, u8),
+    #[serde(skip)]
+    received: (u64, u8),
+    #[serde(skip)]
+    capture: Option<ComlynxCapture>,
+    #[serde(skip)]
+    replay: Option<ComlynxReplay>,
+    #[serde(skip)]
+    rx_errors: ComlynxFlags,
+    #[serde(skip)]
+    ticks: u64,
+    #[serde(skip)]
+    devices: Vec<Box<dyn ComlynxDevice>>,
+    /// Level driven by the devices.
+    #[serde(skip)]
+    devices_level: Option<RedeyeStatus>,
     #[cfg(feature = "comlynx_external")]
     #[serde(skip)]
-    ext_tx: Option<kanal::Sender<u8>>,
+    ext_tx: Option<kanal::Sender<(u64, u8)>>,
     #[cfg(feature = "comlynx_external")]
     #[serde(skip)]
     ext_rx: Option<kanal::Receiver<u8>>,
@@ -36,15 +72,29 @@ pub struct Uart {
 impl Uart {
     #[must_use]
     pub fn new() -> Self {
+        let mut redeye_pin = ComlynxCable::default();
+        redeye_pin.set_push_pull(true);
         Self {
+            tx_clock: 0,
+            transmit_register: vec![],
+            transmit_holding_register: None,
+            rx_clock: 0,
             receive_register_len: 0,
-            receive_register: VecDeque::new(),
             receive_register_buffer: 0,
-            generator_delay: GENERATOR_DELAY,
+            receive_holding_register: 0,
+            rx_line: RedeyeStatus::High,
+            rx_echo: false,
             break_count: 0,
-            transmit_register: vec![],
-            transmit_holding_register: None,
-            redeye_pin: ComlynxCable::default(),
+            tx_open: false,
+            redeye_pin,
+            transmitted: (0, 0),
+            received: (0, 0),
+            capture: None,
+            replay: None,
+            rx_errors: ComlynxFlags::empty(),
+            ticks: 0,
+            devices: vec![],
+            devices_level: None,
             #[cfg(feature = "comlynx_external")]
             ext_tx: None,
             #[cfg(feature = "comlynx_external")]
@@ -53,57 +103,77 @@ impl Uart {
     }
 
     #[cfg(feature = "comlynx_external")]
-    pub fn set_external_comlynx(&mut self, ext_tx: kanal::Sender<u8>, ext_rx: kanal::Receiver<u8>) {
+    pub fn set_external_comlynx(
+        &mut self,
+        ext_tx: kanal::Sender<(u64, u8)>,
+        ext_rx: kanal::Receiver<u8>,
+    ) {
         self.ext_tx = Some(ext_tx);
         self.ext_rx = Some(ext_rx);
     }
 
     pub fn reset(&mut self) {
+        self.tx_clock = 0;
+        self.transmit_register.clear();
+        self.transmit_holding_register = None;
+        self.rx_clock = 0;
         self.receive_register_len = 0;
-        self.receive_register.clear();
         self.receive_register_buffer = 0;
+        self.receive_holding_register = 0;
+        self.rx_line = RedeyeStatus::High;
+        self.rx_echo = false;
         self.break_count = 0;
-        self.transmit_register.clear();
-        self.transmit_holding_register = None;
+        self.set_tx_open(false);
     }
 
-    pub fn tick(&mut self, regs: &mut MikeyRegisters) -> bool /* tx or rx ready interrupt */ {
+    /// Clocked by the timer 4 underflows.
+    pub fn tick(&mut self, regs: &mut MikeyRegisters, ticks: u64) {
+        self.ticks = ticks;
         /* "
         The baud rate is generated by TIMER4 according to the equation
         CLOCK4 / (TIMER4 + 1) / 8
         " */
-        if self.generator_delay == 0 {
-            self.generator_delay = GENERATOR_DELAY;
-        } else {
-            self.generator_delay -= 1;
-            return false;
+        if self.tx_clock == 0 {
+            self.tx_clock = UART_CLOCKS_PER_BIT;
+            self.inject(regs);
+            self.tx(regs);
+            self.tick_devices();
         }
+        self.tx_clock -= 1;
+        self.rx(regs);
+        self.replay(regs);
+    }
 
-        #[cfg(feature = "comlynx_external")]
-        if !regs.serctl_r_is_flag_set(SerCtlR::rx_rdy)
-            && regs.serctl_r_is_flag_set(SerCtlR::tx_rdy)
-            && regs.serctl_r_is_flag_set(SerCtlR::tx_empty)
+    /// Hands over whole the bytes of the external link or the devices, while the UART is idle.
+    fn inject(&mut self, regs: &mut MikeyRegisters) {
+        if regs.serctl_r_is_flag_set(SerCtlR::rx_rdy)
+            || !regs.serctl_r_is_flag_set(SerCtlR::tx_rdy)
+            || !regs.serctl_r_is_flag_set(SerCtlR::tx_empty)
+            || self.rx_clock != 0
         {
-            if let Ok(Some(rx_data)) = self.ext_rx.as_ref().unwrap().try_recv() {
-                self.receive_register = Some(rx_data);
-                regs.serctl_r_enable_flag(SerCtlR::rx_rdy);
-                regs.serctl_r_disable_flag(SerCtlR::par_err);
-                regs.serctl_r_disable_flag(SerCtlR::overrun);
-                regs.serctl_r_disable_flag(SerCtlR::frame_err);
-            }
+            return;
+        }
+        #[cfg(feature = "comlynx_external")]
+        let rx_data = self
+            .ext_rx
+            .as_ref()
+            .and_then(|ext_rx| ext_rx.try_recv().ok().flatten());
+        #[cfg(not(feature = "comlynx_external"))]
+        let rx_data = None;
+        let Some(rx_data) = rx_data.or_else(|| self.devices.iter_mut().find_map(|d| d.transmit()))
+        else {
+            return;
+        };
+        trace!("Received external 0x{rx_data:02X}");
+        let flags = if Self::parity(rx_data, regs) {
+            ComlynxFlags::parity_bit
+        } else {
+            ComlynxFlags::empty()
+        };
+        let flags = self.receive(regs, rx_data, flags);
+        if let Some(capture) = &mut self.capture {
+            capture.receive(self.ticks, rx_data, flags, ComlynxSender::Remote);
         }
-
-        /* "
-        Both the transmit and receive interrupts are 'level' sensitive, rather than 'edge' sensitive.
-        This means that an interrupt will be continuously generated as long as it is enabled and its UART buffer is ready.
-        " */
-        self.tx(regs);
-        self.rx(regs);
-
-        (regs.serctl_w_is_flag_set(SerCtlW::tx_int_en)
-            && regs.serctl_r_is_flag_set(SerCtlR::tx_rdy))
-            | (regs.serctl_w_is_flag_set(SerCtlW::rx_int_en)
-                && regs.serctl_r_is_flag_set(SerCtlR::rx_rdy))
     }
 
     fn tx(&mut self, regs: &mut MikeyRegisters) {
@@ -128,117 +198,268 @@ impl Uart {
         }
     }
 
-    fn load_transmit_data(&mut self, mut data: u8, regs: &mut MikeyRegisters) {
+    fn load_transmit_data(&mut self, data: u8, regs: &mut MikeyRegisters) {
         #[cfg(feature = "comlynx_external")]
-        let _ = self.ext_tx.as_ref().unwrap().send(data);
+        if let Some(ext_tx) = &self.ext_tx {
+            let _ = ext_tx.send((self.ticks, data));
+        }
 
+        self.transmitted = (self.transmitted.0 + 1, data);
+        for device in &mut self.devices {
+            device.receive(data);
+        }
+        let parity = Self::parity(data, regs);
+        if let Some(capture) = &mut self.capture {
+            let flags = if parity {
+                ComlynxFlags::parity_bit
+            } else {
+                ComlynxFlags::empty()
+            };
+            capture.transmit(self.ticks, data, flags);
+        }
+        // popped from the end: start bit, data from bit 0, parity, stop bit
         self.transmit_register.clear();
-        // stop bit
         self.transmit_register.push(RedeyeStatus::High);
-        // parity
-        let parity = RedeyeStatus::from(if regs.serctl_w_is_flag_set(SerCtlW::par_en) {
-            let is_odd_parity = data.count_ones() & 1 != 0;
-            if regs.serctl_w_is_flag_set(SerCtlW::par_even) { is_odd_parity } else { !is_odd_parity }
-        } else {
-            regs.serctl_w_is_flag_set(SerCtlW::par_even)
-        });
-        self.transmit_register.push(parity);
-        // data
-        for _i in 0..8 {
-            self.transmit_register.push(if data & 0x01 != 0 {
-                RedeyeStatus::High
-            } else {
-                RedeyeStatus::Low
-            });
-            data >>= 1;
+        self.transmit_register.push(RedeyeStatus::from(parity));
+        for bit in (0..8).rev() {
+            self.transmit_register
+                .push(RedeyeStatus::from(data & (1 << bit) != 0));
         }
-        // start bit
         self.transmit_register.push(RedeyeStatus::Low);
     }
 
-    fn rx(&mut self, regs: &mut MikeyRegisters) {
-        let redeye_status = self.redeye_pin.status();
+    fn tick_devices(&mut self) {
+        if self.devices.is_empty() {
+            self.devices_level = None;
+            return;
+        }
+        let line = self.redeye_pin.status();
+        let mut level = RedeyeStatus::High;
+        for device in &mut self.devices {
+            if device.tick(line) == RedeyeStatus::Low {
+                level = RedeyeStatus::Low;
+            }
+        }
+        self.devices_level = Some(level);
+    }
 
-        match redeye_status {
-            RedeyeStatus::Low => {
-                self.break_count += 1;
-                if self.break_count >= 24 {
-                    regs.serctl_r_enable_flag(SerCtlR::rx_brk);
-                    return;
-                }
+    /// Level seen by the receiver. The devices can't pull the line `Low` against the push-pull
+    /// driver of the transmitter.
+    fn line(&self) -> RedeyeStatus {
+        match self.devices_level {
+            Some(RedeyeStatus::Low)
+                if self.tx_open || self.redeye_pin.driven() == RedeyeStatus::Low =>
+            {
+                RedeyeStatus::Low
             }
-            RedeyeStatus::High => {
-                self.break_count = 0;
-                regs.serctl_r_disable_flag(SerCtlR::rx_brk);
+            _ => self.redeye_pin.status(),
+        }
+    }
+
+    fn rx(&mut self, regs: &mut MikeyRegisters) {
+        let line = self.line();
+        self.detect_break(line, regs);
+
+        let previous = core::mem::replace(&mut self.rx_line, line);
+        if self.rx_clock == 0 {
+            if line == RedeyeStatus::Low && previous == RedeyeStatus::High {
+                // the bits are sampled in their middle
+                self.rx_clock = UART_CLOCKS_PER_BIT / 2;
+                self.receive_register_len = 0;
+                // the start bit has just been popped, the data, parity and stop bits are left
+                self.rx_echo = self.transmit_register.len() == 10;
             }
+            return;
+        }
+        self.rx_clock -= 1;
+        if self.rx_clock == 0 {
+            self.sample(line, regs);
         }
+    }
 
+    fn sample(&mut self, line: RedeyeStatus, regs: &mut MikeyRegisters) {
         match self.receive_register_len {
             0 => {
-                if redeye_status == RedeyeStatus::Low {
-                    self.receive_register_buffer = 0;
-                    self.receive_register_len = 1;
+                if line == RedeyeStatus::High {
+                    trace!("False start bit");
+                    return;
                 }
+                self.receive_register_buffer = 0;
+                self.rx_errors = ComlynxFlags::empty();
             }
             1..=8 => {
-                self.receive_register_buffer <<= 1;
-                self.receive_register_buffer |= u8::from(redeye_status);
-                self.receive_register_len += 1;
+                self.receive_register_buffer >>= 1;
+                self.receive_register_buffer |= u8::from(line) << 7;
             }
             9 => {
-                let calc_par = if regs.serctl_w_is_flag_set(SerCtlW::par_en) {
-                    let odd_par = self.receive_register_buffer.count_ones() & 1 != 0;
-                    if regs.serctl_w_is_flag_set(SerCtlW::par_even) { odd_par } else { !odd_par }
-                } else {
-                    regs.serctl_w_is_flag_set(SerCtlW::par_even)
-                };
-                if calc_par {
-                    regs.serctl_r_enable_flag(SerCtlR::par_bit);
-                } else {
-                    regs.serctl_r_disable_flag(SerCtlR::par_bit);
+                // "PARBIT: 9th bit", the parity bit or the bit sent in its place
+                if line == RedeyeStatus::High {
+                    self.rx_errors |= ComlynxFlags::parity_bit;
                 }
-                if redeye_status != calc_par.into() {
+                if regs.serctl_w_is_flag_set(SerCtlW::par_en)
+                    && line != Self::parity(self.receive_register_buffer, regs).into()
+                {
                     trace!("Parity Error");
-                    regs.serctl_r_enable_flag(SerCtlR::par_err);
+                    self.rx_errors |= ComlynxFlags::parity_error;
                 }
-                self.receive_register_len += 1;
             }
-            10 => {
-                if self.receive_register_buffer != 0 && redeye_status == RedeyeStatus::Low {
+            _ => {
+                if line == RedeyeStatus::Low {
                     trace!("Frame Error");
-                    regs.serctl_r_enable_flag(SerCtlR::frame_err);
+                    self.rx_errors |= ComlynxFlags::frame_error;
                 }
-                trace!("Received 0x{:02X}", self.receive_register_buffer);
-                if self.receive_register.len() >= RX_BUFFER_LEN {
-                    trace!("Overrun");
-                    self.receive_register.pop_back();
-                    regs.serctl_r_enable_flag(SerCtlR::overrun);
+                let data = self.receive_register_buffer;
+                let flags = self.receive(regs, data, self.rx_errors);
+                if let Some(capture) = &mut self.capture {
+                    let sender = if self.rx_echo {
+                        ComlynxSender::Local
+                    } else {
+                        ComlynxSender::Remote
+                    };
+                    capture.receive(self.ticks, data, flags, sender);
                 }
+                return;
+            }
+        }
+        self.receive_register_len += 1;
+        self.rx_clock = UART_CLOCKS_PER_BIT;
+    }
 
-                self.receive_register
-                    .push_back(self.receive_register_buffer);
-                regs.serctl_r_enable_flag(SerCtlR::rx_rdy);
-
-                self.receive_register_len = 0;
+    /// Moves a received byte to the holding register. The error bits stay set until
+    /// `RESETERR` is written.
+    fn receive(
+        &mut self,
+        regs: &mut MikeyRegisters,
+        data: u8,
+        mut flags: ComlynxFlags,
+    ) -> ComlynxFlags {
+        trace!("Received 0x{data:02X}");
+        if regs.serctl_r_is_flag_set(SerCtlR::rx_rdy) {
+            trace!("Overrun");
+            flags |= ComlynxFlags::overrun;
+        }
+        for (flag, bit) in [
+            (ComlynxFlags::parity_error, SerCtlR::par_err),
+            (ComlynxFlags::frame_error, SerCtlR::frame_err),
+            (ComlynxFlags::overrun, SerCtlR::overrun),
+        ] {
+            if flags.contains(flag) {
+                regs.serctl_r_enable_flag(bit);
             }
-            _ => (),
         }
+        if flags.contains(ComlynxFlags::parity_bit) {
+            regs.serctl_r_enable_flag(SerCtlR::par_bit);
+        } else {
+            regs.serctl_r_disable_flag(SerCtlR::par_bit);
+        }
+        self.receive_holding_register = data;
+        self.received = (self.received.0 + 1, data);
+        regs.serctl_r_enable_flag(SerCtlR::rx_rdy);
+        flags
     }
 
-    pub fn get_data(&mut self, regs: &mut MikeyRegisters) -> u8 {
-        let data = match self.receive_register.pop_front() {
-            None => 0,
-            Some(data) => {
-                trace!("Get 0x{data:02X}");
-                data
+    fn detect_break(&mut self, line: RedeyeStatus, regs: &mut MikeyRegisters) {
+        match line {
+            RedeyeStatus::Low => {
+                self.break_count += 1;
+                if self.break_count == BREAK_CLOCKS {
+                    self.capture_break(regs, true);
+                    regs.serctl_r_enable_flag(SerCtlR::rx_brk);
+                }
             }
-        };
+            RedeyeStatus::High => {
+                if self.break_count >= BREAK_CLOCKS {
+                    self.capture_break(regs, false);
+                }
+                self.break_count = 0;
+                if !self.replay.as_ref().is_some_and(ComlynxReplay::line_break) {
+                    regs.serctl_r_disable_flag(SerCtlR::rx_brk);
+                }
+            }
+        }
+    }
+
+    /// Parity bit sent, or the 9th bit when parity is disabled.
+    fn parity(data: u8, regs: &MikeyRegisters) -> bool {
+        if regs.serctl_w_is_flag_set(SerCtlW::par_en) {
+            let is_odd_parity = data.count_ones() & 1 != 0;
+            if regs.serctl_w_is_flag_set(SerCtlW::par_even) { is_odd_parity } else { !is_odd_parity }
+        } else {
+            regs.serctl_w_is_flag_set(SerCtlW::par_even)
+        }
+    }
 
-        if self.receive_register.is_empty() {
-            regs.serctl_r_disable_flag(SerCtlR::rx_rdy);
+    fn capture_break(&mut self, regs: &MikeyRegisters, start: bool) {
+        if let Some(capture) = &mut self.capture {
+            let sender = if regs.serctl_w_is_flag_set(SerCtlW::tx_brk) {
+                ComlynxSender::Local
+            } else {
+                ComlynxSender::Remote
+            };
+            capture.line_break(self.ticks, start, sender);
         }
+    }
 
-        data
+    /// Injects the replayed bytes and breaks that are due.
+    fn replay(&mut self, regs: &mut MikeyRegisters) {
+        while let Some(record) = self.replay.as_mut().and_then(|r| r.next_due(self.ticks)) {
+            match record.kind {
+                ComlynxEventKind::Receive => {
+                    self.receive(regs, record.data, record.flags);
+                }
+                ComlynxEventKind::BreakStart => regs.serctl_r_enable_flag(SerCtlR::rx_brk),
+                ComlynxEventKind::BreakEnd => regs.serctl_r_disable_flag(SerCtlR::rx_brk),
+                ComlynxEventKind::Transmit => (),
+            }
+            if let Some(capture) = &mut self.capture {
+                capture.replayed(&record);
+            }
+        }
+    }
+
+    /// Plugs a device in the redeye port, returns its index.
+    pub fn add_device(&mut self, device: Box<dyn ComlynxDevice>) -> usize {
+        self.devices.push(device);
+        self.devices.len() - 1
+    }
+
+    pub fn remove_device(&mut self, index: usize) -> Box<dyn ComlynxDevice> {
+        self.devices.remove(index)
+    }
+
+    #[must_use]
+    pub fn device_count(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// Starts or stops recording the `ComLynx` traffic, `node` identifies the console.
+    pub fn set_capture_enabled(&mut self, enabled: bool, node: u8) {
+        self.capture = enabled.then(|| ComlynxCapture::new(node));
+    }
+
+    #[must_use]
+    pub fn capture(&self) -> Option<&ComlynxCapture> {
+        self.capture.as_ref()
+    }
+
+    pub fn capture_mut(&mut self) -> Option<&mut ComlynxCapture> {
+        self.capture.as_mut()
+    }
+
+    /// Plays a capture back, `None` stops the replay.
+    pub fn set_replay(&mut self, replay: Option<ComlynxReplay>) {
+        self.replay = replay;
+    }
+
+    #[must_use]
+    pub fn replay_source(&self) -> Option<&ComlynxReplay> {
+        self.replay.as_ref()
+    }
+
+    pub fn get_data(&mut self, regs: &mut MikeyRegisters) -> u8 {
+        trace!("Get 0x{:02X}", self.receive_holding_register);
+        regs.serctl_r_disable_flag(SerCtlR::rx_rdy);
+        self.receive_holding_register
     }
 
     pub fn set_transmit_holding_buffer(&mut self, regs: &mut MikeyRegisters, data: u8) {
@@ -252,9 +473,52 @@ impl Uart {
         self.redeye_pin.set(status);
     }
 
+    /// `TXOPEN` written.
+    pub fn set_tx_open(&mut self, tx_open: bool) {
+        self.tx_open = tx_open;
+        self.redeye_pin.set_push_pull(!tx_open);
+    }
+
     #[cfg(not(feature = "comlynx_shared_memory"))]
     pub fn set_cable(&mut self, cable: &ComlynxCable) {
         self.redeye_pin = cable.clone();
+        self.redeye_pin.set_push_pull(!self.tx_open);
+    }
+
+    #[cfg(feature = "comlynx_shared_memory")]
+    pub fn set_cable(&mut self, cable: ComlynxCable) {
+        self.redeye_pin = cable;
+        self.redeye_pin.set_push_pull(!self.tx_open);
+    }
+
+    /// Number of bytes sent since power on, and the last one.
+    #[must_use]
+    pub fn transmitted(&self) -> (u64, u8) {
+        self.transmitted
+    }
+
+    /// Number of bytes received since power on, and the last one.
+    #[must_use]
+    pub fn received(&self) -> (u64, u8) {
+        self.received
+    }
+
+    /// Byte waiting to be moved to the transmit shift register.
+    #[must_use]
+    pub fn transmit_holding_register(&self) -> Option<u8> {
+        self.transmit_holding_register
+    }
+
+    /// A byte is being shifted out.
+    #[must_use]
+    pub fn transmitting(&self) -> bool {
+        !self.transmit_register.is_empty()
+    }
+
+    /// Last byte received, not read yet from `SERDAT` while `RXRDY` is set.
+    #[must_use]
+    pub fn receive_holding_register(&self) -> u8 {
+        self.receive_holding_register
     }
 
     #[must_use]
@@ -268,3 +532,239 @@ impl Default for Uart {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cartridge::Cartridge;
+    use crate::consts::{INTSET, INT_TIMER4};
+    use crate::mikey::Mikey;
+    use crate::ram::Ram;
+    use alloc::collections::vec_deque::VecDeque;
+
+    /// `PAREN | TXOPEN | PAREVEN`.
+    const EVEN_PARITY: u8 = 0x15;
+
+    /// Drives the line a bit period per level.
+    struct Bits(VecDeque<RedeyeStatus>);
+
+    impl ComlynxDevice for Bits {
+        fn tick(&mut self, _line: RedeyeStatus) -> RedeyeStatus {
+            self.0.pop_front().unwrap_or(RedeyeStatus::High)
+        }
+    }
+
+    fn uart(serctl: u8) -> (Uart, MikeyRegisters) {
+        let mut uart = Uart::new();
+        let mut regs = MikeyRegisters::new();
+        regs.set_serctl(&mut uart, serctl);
+        (uart, regs)
+    }
+
+    fn run(uart: &mut Uart, regs: &mut MikeyRegisters, clocks: u64) {
+        for tick in 0..clocks {
+            uart.tick(regs, tick);
+        }
+    }
+
+    /// Start bit, data from bit 0, 9th bit, stop bit.
+    fn frame(data: u8, ninth: bool, stop: bool) -> Vec<RedeyeStatus> {
+        let mut bits = vec![RedeyeStatus::Low];
+        bits.extend((0..8).map(|bit| RedeyeStatus::from(data & (1 << bit) != 0)));
+        bits.push(ninth.into());
+        bits.push(stop.into());
+        bits
+    }
+
+    fn send_bits(uart: &mut Uart, regs: &mut MikeyRegisters, bits: &[RedeyeStatus]) {
+        uart.add_device(Box::new(Bits(bits.iter().copied().collect())));
+        run(uart, regs, (bits.len() as u64 + 2) * 8);
+        uart.remove_device(0);
+    }
+
+    #[test]
+    fn transmit_frame() {
+        let (mut uart, mut regs) = uart(EVEN_PARITY);
+        uart.set_transmit_holding_buffer(&mut regs, 0x01);
+        let mut line = vec![];
+        for tick in 0..88 {
+            uart.tick(&mut regs, tick);
+            line.push(uart.cable().driven());
+            assert!(regs.serctl_r_is_flag_set(SerCtlR::tx_rdy));
+            assert!(!regs.serctl_r_is_flag_set(SerCtlR::tx_empty));
+        }
+        // 8 clocks per bit, even parity of 0x01 is 1
+        let expected: Vec<RedeyeStatus> = frame(0x01, true, true)
+            .into_iter()
+            .flat_map(|bit| [bit; 8])
+            .collect();
+        assert_eq!(line, expected);
+        uart.tick(&mut regs, 88);
+        assert!(regs.serctl_r_is_flag_set(SerCtlR::tx_empty));
+    }
+
+    #[test]
+    fn echo() {
+        let (mut uart, mut regs) = uart(EVEN_PARITY);
+        uart.set_transmit_holding_buffer(&mut regs, 0xA5);
+        run(&mut uart, &mut regs, 84);
+        assert!(!regs.serctl_r_is_flag_set(SerCtlR::rx_rdy));
+        // the stop bit is sampled in its middle
+        uart.tick(&mut regs, 84);
+        assert!(regs.serctl_r_is_flag_set(SerCtlR::rx_rdy));
+        assert!(!regs.serctl_r_is_flag_set(SerCtlR::par_bit));
+        assert_eq!(regs.serctl() & 0x1C, 0);
+        assert_eq!(uart.get_data(&mut regs), 0xA5);
+        assert!(!regs.serctl_r_is_flag_set(SerCtlR::rx_rdy));
+    }
+
+    #[test]
+    fn capture_echo_by_frame() {
+        let (mut uart, mut regs) = uart(EVEN_PARITY);
+        uart.set_capture_enabled(true, 0);
+        // a remote frame colliding with the local one, then the same byte alone
+        uart.add_device(Box::new(Bits(frame(0x0F, true, true).into())));
+        uart.set_transmit_holding_buffer(&mut regs, 0xFF);
+        run(&mut uart, &mut regs, 100);
+        uart.remove_device(0);
+        send_bits(&mut uart, &mut regs, &frame(0xFF, false, true));
+
+        let records: Vec<_> = uart
+            .capture()
+            .unwrap()
+            .records()
+            .iter()
+            .map(|r| (r.kind, r.sender, r.data))
+            .collect();
+        assert_eq!(
+            records,
+            [
+                (ComlynxEventKind::Transmit, ComlynxSender::Local, 0xFF),
+                (ComlynxEventKind::Receive, ComlynxSender::Local, 0x0F),
+                (ComlynxEventKind::Receive, ComlynxSender::Remote, 0xFF),
+            ]
+        );
+    }
+
+    #[test]
+    fn overrun_until_reset_err() {
+        let (mut uart, mut regs) = uart(EVEN_PARITY);
+        let mut bits = frame(0x11, false, true);
+        bits.extend(frame(0x22, false, true));
+        send_bits(&mut uart, &mut regs, &bits);
+        assert!(regs.serctl_r_is_flag_set(SerCtlR::overrun));
+        assert_eq!(uart.get_data(&mut regs), 0x22);
+
+        send_bits(&mut uart, &mut regs, &frame(0x33, false, true));
+        assert!(regs.serctl_r_is_flag_set(SerCtlR::overrun));
+        regs.set_serctl(&mut uart, EVEN_PARITY | 0x08);
+        assert!(!regs.serctl_r_is_flag_set(SerCtlR::overrun));
+        assert!(regs.serctl_r_is_flag_set(SerCtlR::rx_rdy));
+        assert_eq!(regs.serctl_w(), EVEN_PARITY);
+    }
+
+    #[test]
+    fn parity_error() {
+        let (mut uart, mut regs) = uart(EVEN_PARITY);
+        send_bits(&mut uart, &mut regs, &frame(0x01, true, true));
+        assert_eq!(regs.serctl() & 0x1F, 0x01);
+        send_bits(&mut uart, &mut regs, &frame(0x01, false, true));
+        assert_eq!(regs.serctl() & 0x1F, 0x18);
+    }
+
+    #[test]
+    fn ninth_bit() {
+        // parity disabled, PAREVEN is sent as the 9th bit
+        let (mut uart, mut regs) = uart(0x05);
+        uart.set_transmit_holding_buffer(&mut regs, 0x01);
+        run(&mut uart, &mut regs, 85);
+        assert_eq!(regs.serctl() & 0x1F, 0x01);
+
+        regs.set_serctl(&mut uart, 0x04);
+        uart.get_data(&mut regs);
+        send_bits(&mut uart, &mut regs, &frame(0x01, false, true));
+        assert_eq!(regs.serctl() & 0x1F, 0x00);
+        send_bits(&mut uart, &mut regs, &frame(0x01, true, true));
+        assert_eq!(regs.serctl() & 0x1F, 0x09);
+    }
+
+    #[test]
+    fn frame_error() {
+        let (mut uart, mut regs) = uart(EVEN_PARITY);
+        send_bits(&mut uart, &mut regs, &frame(0x55, false, false));
+        assert!(regs.serctl_r_is_flag_set(SerCtlR::frame_err));
+        assert_eq!(uart.get_data(&mut regs), 0x55);
+    }
+
+    #[test]
+    fn break_length() {
+        let (mut uart, mut regs) = uart(EVEN_PARITY);
+        uart.add_device(Box::new(Bits([RedeyeStatus::Low; 23].into())));
+        for tick in 0..400 {
+            uart.tick(&mut regs, tick);
+            assert!(!regs.serctl_r_is_flag_set(SerCtlR::rx_brk));
+        }
+        uart.remove_device(0);
+
+        uart.add_device(Box::new(Bits([RedeyeStatus::Low; 24].into())));
+        let mut line_break = 0;
+        for tick in 0..400 {
+            uart.tick(&mut regs, tick);
+            line_break += u32::from(regs.serctl_r_is_flag_set(SerCtlR::rx_brk));
+        }
+        assert_eq!(line_break, 1);
+        // the break is also received as a 0 with a frame error
+        assert_eq!(uart.get_data(&mut regs), 0);
+        assert!(regs.serctl_r_is_flag_set(SerCtlR::frame_err));
+    }
+
+    #[test]
+    fn push_pull() {
+        // TXOPEN clear, the transmitter holds the line High
+        let (mut uart, mut regs) = uart(0x11);
+        send_bits(&mut uart, &mut regs, &frame(0x42, false, true));
+        assert!(!regs.serctl_r_is_flag_set(SerCtlR::rx_rdy));
+
+        regs.set_serctl(&mut uart, EVEN_PARITY);
+        send_bits(&mut uart, &mut regs, &frame(0x42, false, true));
+        assert_eq!(uart.get_data(&mut regs), 0x42);
+    }
+
+    #[test]
+    fn level_interrupt() {
+        let mut mikey = Mikey::new();
+        let mut bus = Bus::new();
+        let mut cart = Cartridge::default();
+        let ram = Ram::new();
+        let mut tick = |mikey: &mut Mikey| {
+            mikey.registers_mut().set_data(INTSET, 0);
+            mikey.tick(&mut bus, &mut cart, &ram);
+            mikey.registers().data(INTSET) & INT_TIMER4 != 0
+        };
+
+        assert!(!tick(&mut mikey));
+        // RXINTEN
+        let Mikey {
+            registers, uart, ..
+        } = &mut mikey;
+        registers.set_serctl(uart, 0x40);
+        assert!(!tick(&mut mikey));
+        mikey.registers_mut().serctl_r_enable_flag(SerCtlR::rx_rdy);
+        for _ in 0..3 {
+            assert!(tick(&mut mikey));
+        }
+        let Mikey {
+            registers, uart, ..
+        } = &mut mikey;
+        uart.get_data(registers);
+        assert!(!tick(&mut mikey));
+
+        // TXINTEN, the transmitter is ready
+        let Mikey {
+            registers, uart, ..
+        } = &mut mikey;
+        registers.set_serctl(uart, 0x80);
+        assert!(tick(&mut mikey));
+    }
+}
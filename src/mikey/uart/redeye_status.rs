@@ -1,7 +1,7 @@
 use core::ops::Not;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum RedeyeStatus {
     Low = 0,
@@ -19,9 +19,10 @@ impl From<u8> for RedeyeStatus {
 
 impl From<bool> for RedeyeStatus {
     fn from(value: bool) -> Self {
-        match value {
-            true => RedeyeStatus::High,
-            false => RedeyeStatus::Low,
+        if value {
+            RedeyeStatus::High
+        } else {
+            RedeyeStatus::Low
         }
     }
 }
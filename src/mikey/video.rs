@@ -25,6 +25,7 @@ fn create_rgba_buffer() -> Vec<u8> {
 }
 
 #[derive(Serialize, Deserialize)]
+#[allow(clippy::unsafe_derive_deserialize)]
 pub struct Video {
     #[serde(skip)]
     #[serde[default="create_video_buffers"]]
@@ -34,6 +35,8 @@ pub struct Video {
     pix_buffer_available: u8,
     redraw_requested: bool,
     #[serde(skip)]
+    frame_count: u64,
+    #[serde(skip)]
     #[serde[default="create_row_buffer"]]
     display_row_buffer: [u8; LYNX_SCREEN_WIDTH as usize],
     pub display_row_index: usize,
@@ -99,6 +102,7 @@ impl Video {
             pix_buffer: 0,
             pix_buffer_available: 0,
             redraw_requested: false,
+            frame_count: 0,
             display_row_buffer: [0; LYNX_SCREEN_WIDTH as usize],
             display_row_index: 0,
             vsync_count: 0,
@@ -132,14 +136,20 @@ impl Video {
 
         #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
         unsafe {
-            use core::arch::x86_64::*;
-
-            let v = _mm_loadl_epi64(pixs.as_ptr() as *const __m128i);
+            use core::arch::x86_64::{
+                __m128i, _mm_and_si128, _mm_loadl_epi64, _mm_or_si128, _mm_set1_epi8,
+                _mm_slli_epi16, _mm_srli_epi16, _mm_storel_epi64,
+            };
+
+            // loadl/storel only move 64 bits and have no alignment requirement.
+            #[allow(clippy::cast_ptr_alignment)]
+            let v = _mm_loadl_epi64(pixs.as_ptr().cast::<__m128i>());
             let low_shifted = _mm_and_si128(_mm_slli_epi16(v, 4), _mm_set1_epi8(0xF0u8 as i8));
-            let high_shifted = _mm_and_si128(_mm_srli_epi16(v, 4), _mm_set1_epi8(0x0F as i8));
+            let high_shifted = _mm_and_si128(_mm_srli_epi16(v, 4), _mm_set1_epi8(0x0F_i8));
             let swapped = _mm_or_si128(low_shifted, high_shifted);
             let mut out = [0u8; 8];
-            _mm_storel_epi64(out.as_mut_ptr() as *mut __m128i, swapped);
+            #[allow(clippy::cast_ptr_alignment)]
+            _mm_storel_epi64(out.as_mut_ptr().cast::<__m128i>(), swapped);
             self.pix_buffer = u64::from_le_bytes(out);
 
             return;
@@ -173,6 +183,7 @@ impl Video {
             self.draw_buffer().reset();
             self.pix_buffer_available = 0;
             self.redraw_requested = true;
+            self.frame_count += 1;
         }
     }
 
@@ -262,6 +273,13 @@ impl Video {
         }
     }
 
+    /// Number of frames completed since power on.
+    #[inline]
+    #[must_use]
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
     #[inline]
     #[must_use]
     pub fn rgba_screen(&self) -> &Vec<u8> {
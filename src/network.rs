@@ -0,0 +1,271 @@
+use crate::lynx::Lynx;
+use crate::mikey::uart::comlynx_cable_mutex::{line_status, ComlynxCable, ComlynxLine};
+use crate::mikey::uart::redeye_status::RedeyeStatus;
+use alloc::{sync::Arc, vec::Vec};
+use parking_lot::Mutex;
+
+/// Consoles a `ComLynx` line can link.
+pub const LYNX_NETWORK_MAX_NODES: usize = 8;
+
+/// Lynx consoles linked by a `ComLynx` cable, run in tick lockstep.
+///
+/// The redeye line is open collector: it is `Low` as long as any console drives it `Low`,
+/// unless a console left its transmitter in push-pull mode, `TXOPEN` clear, holds it `High`.
+/// Consoles are ticked in order, so a session plays back the same given the same inputs.
+pub struct LynxNetwork {
+    nodes: Vec<Lynx>,
+    line: Arc<Mutex<ComlynxLine>>,
+    ticks: u64,
+}
+
+impl LynxNetwork {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            line: Arc::new(Mutex::new(0)),
+            ticks: 0,
+        }
+    }
+
+    /// Plugs a console in the network, returns its index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the network already has `LYNX_NETWORK_MAX_NODES` consoles.
+    pub fn add(&mut self, mut lynx: Lynx) -> Result<usize, &'static str> {
+        if self.nodes.len() >= LYNX_NETWORK_MAX_NODES {
+            return Err("ComLynx network is full");
+        }
+        let index = self.nodes.len();
+        self.connect(&mut lynx, index);
+        self.nodes.push(lynx);
+        Ok(index)
+    }
+
+    /// Unplugs a console, it keeps its own end of the cable.
+    pub fn remove(&mut self, index: usize) -> Lynx {
+        let mut lynx = self.nodes.remove(index);
+        let mut cable = ComlynxCable::default();
+        cable.set(lynx.comlynx_cable().driven());
+        lynx.set_comlynx_cable(&cable);
+        lynx.set_comlynx_cable_present(false);
+
+        *self.line.lock() = 0;
+        let line = self.line.clone();
+        for (node, lynx) in self.nodes.iter_mut().enumerate() {
+            Self::connect_line(&line, lynx, node);
+        }
+        lynx
+    }
+
+    /// Swaps a console for another one, e.g. a restored save state, returns the replaced one.
+    pub fn replace(&mut self, index: usize, mut lynx: Lynx) -> Lynx {
+        self.connect(&mut lynx, index);
+        core::mem::replace(&mut self.nodes[index], lynx)
+    }
+
+    fn connect(&self, lynx: &mut Lynx, node: usize) {
+        Self::connect_line(&self.line, lynx, node);
+    }
+
+    fn connect_line(line: &Arc<Mutex<ComlynxLine>>, lynx: &mut Lynx, node: usize) {
+        let driven = lynx.comlynx_cable().driven();
+        let mut cable = ComlynxCable::open_collector(line.clone(), node as u8);
+        cable.set(driven);
+        lynx.set_comlynx_cable(&cable);
+    }
+
+    /// Ticks every console once.
+    pub fn tick(&mut self) {
+        for lynx in &mut self.nodes {
+            lynx.tick();
+        }
+        self.ticks += 1;
+    }
+
+    /// Ticks every console `ticks` times.
+    pub fn run(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    /// Ticks until the console at `index` has a new frame to display.
+    pub fn run_frame(&mut self, index: usize) {
+        loop {
+            self.tick();
+            if self.nodes[index].redraw_requested() {
+                break;
+            }
+        }
+    }
+
+    /// Ticks the consoles were run for.
+    #[must_use]
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Level of the shared redeye line.
+    #[must_use]
+    pub fn redeye(&self) -> RedeyeStatus {
+        line_status(*self.line.lock())
+    }
+
+    /// Consoles driving the line `Low`, a bit per console.
+    #[must_use]
+    pub fn redeye_drivers(&self) -> u8 {
+        self.line.lock().to_le_bytes()[0]
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    #[must_use]
+    pub fn node(&self, index: usize) -> &Lynx {
+        &self.nodes[index]
+    }
+
+    /// The console at `index`, its `ComLynx` cable must be left alone.
+    pub fn node_mut(&mut self, index: usize) -> &mut Lynx {
+        &mut self.nodes[index]
+    }
+
+    #[must_use]
+    pub fn nodes(&self) -> &[Lynx] {
+        &self.nodes
+    }
+}
+
+impl Default for LynxNetwork {
+    fn default() -> Self {
+        LynxNetwork::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mikey::uart::comlynx_capture::{
+        ComlynxCapture, ComlynxEventKind, ComlynxReplay, ComlynxSender,
+    };
+
+    fn store(code: &mut Vec<u8>, addr: u16, data: u8) {
+        code.extend_from_slice(&[0xA9, data, 0x8D, addr as u8, (addr >> 8) as u8]);
+    }
+
+    /// Sets the UART to 62500 bauds, sends `data` if any, then loops.
+    fn lynx_sending(data: Option<u8>) -> Lynx {
+        let mut code = vec![];
+        store(&mut code, 0xFD10, 1); // TIM4BKUP
+        store(&mut code, 0xFD11, 0x18); // TIM4CTLA, reload, count, 1us
+        store(&mut code, 0xFD8C, 0x1D); // SERCTL, PAREN | RESETERR | TXOPEN | PAREVEN
+        if let Some(data) = data {
+            store(&mut code, 0xFD8D, data); // SERDAT
+        }
+        let here = 0xFE00 + code.len() as u16;
+        code.extend_from_slice(&[0x4C, here as u8, (here >> 8) as u8]);
+        let mut rom = vec![0u8; 512];
+        rom[..code.len()].copy_from_slice(&code);
+        rom[0x1FC..].copy_from_slice(&[0x00, 0xFE, 0x00, 0xFE]);
+        let mut lynx = Lynx::new();
+        lynx.load_rom_from_slice(&rom).unwrap();
+        lynx
+    }
+
+    #[test]
+    fn push_pull() {
+        let line = Arc::new(Mutex::new(0));
+        let mut a = ComlynxCable::open_collector(line.clone(), 0);
+        let mut b = ComlynxCable::open_collector(line.clone(), 1);
+        b.set(RedeyeStatus::Low);
+        assert_eq!(a.status(), RedeyeStatus::Low);
+        // a push-pull driver holds the line High
+        a.set_push_pull(true);
+        assert_eq!(a.status(), RedeyeStatus::High);
+        a.set(RedeyeStatus::Low);
+        b.set(RedeyeStatus::High);
+        assert_eq!(line_status(*line.lock()), RedeyeStatus::Low);
+    }
+
+    #[test]
+    fn broadcast() {
+        let mut network = LynxNetwork::new();
+        network.add(lynx_sending(Some(0x5A))).unwrap();
+        network.add(lynx_sending(None)).unwrap();
+        network.add(lynx_sending(None)).unwrap();
+        network.run(20_000);
+
+        for lynx in network.nodes() {
+            assert_eq!(lynx.mikey().uart().received(), (1, 0x5A));
+        }
+        assert_eq!(network.redeye(), RedeyeStatus::High);
+    }
+
+    #[test]
+    fn deterministic() {
+        let run = || {
+            let mut network = LynxNetwork::new();
+            network.add(lynx_sending(Some(0x12))).unwrap();
+            network.add(lynx_sending(Some(0x34))).unwrap();
+            network.run(20_000);
+            network
+                .nodes()
+                .iter()
+                .map(|lynx| lynx.mikey().uart().received())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn capture_replay() {
+        let mut network = LynxNetwork::new();
+        network.add(lynx_sending(Some(0x5A))).unwrap();
+        network.add(lynx_sending(None)).unwrap();
+        network.node_mut(0).set_comlynx_capture_enabled(true, 0);
+        network.node_mut(1).set_comlynx_capture_enabled(true, 1);
+        network.run(20_000);
+
+        let sender = network.node(0).comlynx_capture().unwrap().records();
+        assert_eq!(sender[0].kind, ComlynxEventKind::Transmit);
+        assert_eq!(sender[1].kind, ComlynxEventKind::Receive);
+        assert_eq!(sender[1].sender, ComlynxSender::Local);
+
+        let capture = network.node(1).comlynx_capture().unwrap();
+        let received = capture.records()[0];
+        assert_eq!(received.kind, ComlynxEventKind::Receive);
+        assert_eq!(received.sender, ComlynxSender::Remote);
+        assert_eq!(received.data, 0x5A);
+
+        let capture = ComlynxCapture::from_bytes(&capture.to_bytes()).unwrap();
+        let mut lynx = lynx_sending(None);
+        lynx.set_comlynx_replay(Some(ComlynxReplay::new(&capture)));
+        lynx.set_comlynx_capture_enabled(true, 1);
+        for _ in 0..20_000 {
+            lynx.tick();
+        }
+        assert_eq!(lynx.mikey().uart().received(), (1, 0x5A));
+        assert_eq!(lynx.comlynx_capture().unwrap().records(), capture.records());
+    }
+
+    #[test]
+    fn full() {
+        let mut network = LynxNetwork::new();
+        for _ in 0..LYNX_NETWORK_MAX_NODES {
+            network.add(Lynx::new()).unwrap();
+        }
+        assert!(network.add(Lynx::new()).is_err());
+        let lynx = network.remove(0);
+        assert_eq!(lynx.comlynx_cable().status(), RedeyeStatus::High);
+        assert_eq!(network.len(), LYNX_NETWORK_MAX_NODES - 1);
+    }
+}
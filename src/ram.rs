@@ -104,11 +104,7 @@ impl Ram {
     pub fn set_mmapctl(&mut self, data: u8) {
         self.data[MMC_ADDR as usize] = data;
         // B7 = sequential disable. If set, the CPU will always use full cycles (5 ticks min), never a sequential cycle (4 ticks).
-        if data & 0b1000_0000 != 0 {
-            self.page_mode_enabled = false;
-        } else {
-            self.page_mode_enabled = true;
-        }
+        self.page_mode_enabled = data & 0b1000_0000 == 0;
     }
 
     #[inline]
@@ -0,0 +1,1104 @@
+use crate::alloc::string::String;
+use crate::alloc::vec::Vec;
+use crate::consts::{
+    ATTEN_A, ATTEN_B, ATTEN_C, ATTEN_D, AUD0COUNT, AUD0CTL, AUD0L8SHFT, AUD0MISC, AUD0OUTVAL,
+    AUD0SHFTFB, AUD0TBACK, AUD0VOL, AUD1COUNT, AUD1CTL, AUD1L8SHFT, AUD1MISC, AUD1OUTVAL,
+    AUD1SHFTFB, AUD1TBACK, AUD1VOL, AUD2COUNT, AUD2CTL, AUD2L8SHFT, AUD2MISC, AUD2OUTVAL,
+    AUD2SHFTFB, AUD2TBACK, AUD2VOL, AUD3COUNT, AUD3CTL, AUD3L8SHFT, AUD3MISC, AUD3OUTVAL,
+    AUD3SHFTFB, AUD3TBACK, AUD3VOL, AUDIN, BLUERED0, BLUERED1, BLUERED2, BLUERED3, BLUERED4,
+    BLUERED5, BLUERED6, BLUERED7, BLUERED8, BLUERED9, BLUEREDA, BLUEREDB, BLUEREDC, BLUEREDD,
+    BLUEREDE, BLUEREDF, COLLADRL, COLLBASL, COLLOFFL, CPUSLEEP, DISPADRL, DISPCTL, GREEN0, GREEN1,
+    GREEN2, GREEN3, GREEN4, GREEN5, GREEN6, GREEN7, GREEN8, GREEN9, GREENA, GREENB, GREENC, GREEND,
+    GREENE, GREENF, HOFFL, HPOSSTRTL, HSIZOFFL, INTRST, INTSET, IODAT, IODAT_AUDIN, IODAT_CAD,
+    IODAT_EXTPW, IODAT_NOEXP, IODAT_REST, IODIR, JOYSTICK, MAGRDY0, MAGRDY1, MAPCTL_MIK_BIT,
+    MAPCTL_ROM_BIT, MAPCTL_SUZ_BIT, MAPCTL_VEC_BIT, MATHA, MATHB, MATHC, MATHD, MATHE, MATHF,
+    MATHG, MATHH, MATHJ, MATHK, MATHL, MATHM, MATHN, MATHP, MIKEYHREV, MIKEYSREV, MMC_ADDR, MPAN,
+    MSTEREO, MTEST0, MTEST1, MTEST2, PBKUP, PROCADRL, RCART0, RCART1, SCBADRL, SCBNEXTL, SDONEACK,
+    SERCTL, SERDAT, SPRCOLL, SPRCOLL_DONT_COLLIDE, SPRCOLL_NUMBER, SPRCTL0, SPRCTL0_BPP,
+    SPRCTL0_HFLIP, SPRCTL0_SPR_TYPE, SPRCTL0_VFLIP, SPRCTL1, SPRCTL1_ALGO_3, SPRCTL1_DRAW_LEFT,
+    SPRCTL1_DRAW_UP, SPRCTL1_LITERAL, SPRCTL1_RELOAD_HVST, SPRCTL1_REUSE_PALETTE,
+    SPRCTL1_SKIP_SPRITE, SPRDLINEL, SPRDOFFL, SPRGO, SPRGO_EVERON, SPRGO_GO, SPRHSIZL, SPRINIT,
+    SPRSYS, SPRSYS_ACCUMULATE, SPRSYS_CLEAR_UNSAFE, SPRSYS_DONT_COLLIDE, SPRSYS_LAST_CARRY,
+    SPRSYS_LEFTHAND, SPRSYS_MATHBIT, SPRSYS_MATH_IN_PROGRESS, SPRSYS_SIGN_MATH,
+    SPRSYS_SPRITE_IN_PROGRESS, SPRSYS_STOP_CURRENT_SPRITE, SPRSYS_UNSAFE_ACCESS, SPRSYS_VSTRETCH,
+    SPRVPOSL, SPRVSIZL, STRETCHL, SUZYBUSEN, SUZYHREV, SUZ_ADDR, SWITCHES, SYSCTL1, SYSCTL1_CAS,
+    SYSCTL1_POWER, TILTACUML, TILTL, TIM0BKUP, TIM0CNT, TIM0CTLA, TIM0CTLB, TIM1BKUP, TIM1CNT,
+    TIM1CTLA, TIM1CTLB, TIM2BKUP, TIM2CNT, TIM2CTLA, TIM2CTLB, TIM3BKUP, TIM3CNT, TIM3CTLA,
+    TIM3CTLB, TIM4BKUP, TIM4CNT, TIM4CTLA, TIM4CTLB, TIM5BKUP, TIM5CNT, TIM5CTLA, TIM5CTLB,
+    TIM6BKUP, TIM6CNT, TIM6CTLA, TIM6CTLB, TIM7BKUP, TIM7CNT, TIM7CTLA, TIM7CTLB, TMPADRL, VIDADRL,
+    VIDBASL, VOFFL, VPOSSTRTL, VSIZACUML, VSIZOFFL,
+};
+use RegisterAccess::{ReadOnly, ReadWrite, WriteOnly};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegisterAccess {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+/// Named bits of a register. A single bit field without values is a flag,
+/// named when set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitField {
+    pub name: &'static str,
+    pub mask: u8,
+    /// Names of the field values, in place (not shifted).
+    pub values: &'static [(u8, &'static str)],
+}
+
+const fn flag(name: &'static str, mask: u8) -> BitField {
+    BitField {
+        name,
+        mask,
+        values: &[],
+    }
+}
+
+const fn field(name: &'static str, mask: u8, values: &'static [(u8, &'static str)]) -> BitField {
+    BitField { name, mask, values }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterDescriptor {
+    pub name: &'static str,
+    pub address: u16,
+    /// Size in bytes, the 16 bits registers are a low byte then a high byte.
+    pub size: u8,
+    pub access: RegisterAccess,
+    /// Bits as read.
+    pub read_fields: &'static [BitField],
+    /// Bits as written.
+    pub write_fields: &'static [BitField],
+    pub description: &'static str,
+}
+
+const fn reg(
+    name: &'static str,
+    address: u16,
+    access: RegisterAccess,
+    description: &'static str,
+) -> RegisterDescriptor {
+    RegisterDescriptor {
+        name,
+        address,
+        size: 1,
+        access,
+        read_fields: &[],
+        write_fields: &[],
+        description,
+    }
+}
+
+impl RegisterDescriptor {
+    const fn word(mut self) -> Self {
+        self.size = 2;
+        self
+    }
+
+    const fn fields(self, fields: &'static [BitField]) -> Self {
+        self.read_fields(fields).write_fields(fields)
+    }
+
+    const fn read_fields(mut self, fields: &'static [BitField]) -> Self {
+        self.read_fields = fields;
+        self
+    }
+
+    const fn write_fields(mut self, fields: &'static [BitField]) -> Self {
+        self.write_fields = fields;
+        self
+    }
+
+    #[must_use]
+    pub fn contains(&self, addr: u16) -> bool {
+        addr.wrapping_sub(self.address) < u16::from(self.size)
+    }
+
+    #[must_use]
+    pub fn readable(&self) -> bool {
+        self.access != WriteOnly
+    }
+
+    #[must_use]
+    pub fn writable(&self) -> bool {
+        self.access != ReadOnly
+    }
+
+    /// Names the bits of a value read, e.g. `TXRDY | TXEMPTY`.
+    #[must_use]
+    pub fn format_read(&self, value: u8) -> String {
+        format_fields(self.read_fields, value)
+    }
+
+    /// Names the bits of a value written, e.g. `TYPE_NORMAL | BPP4 | HFLIP`.
+    #[must_use]
+    pub fn format_write(&self, value: u8) -> String {
+        format_fields(self.write_fields, value)
+    }
+
+    /// Checks a write to the register.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the register can't be written or if `value` sets
+    /// bits that have no meaning.
+    pub fn check_write(&self, value: u8) -> Result<(), &'static str> {
+        if !self.writable() {
+            return Err("Register is read only");
+        }
+        if !self.write_fields.is_empty() && value & !fields_mask(self.write_fields) != 0 {
+            return Err("Value sets undefined bits");
+        }
+        Ok(())
+    }
+}
+
+fn fields_mask(fields: &[BitField]) -> u8 {
+    fields.iter().fold(0, |mask, field| mask | field.mask)
+}
+
+fn format_fields(fields: &[BitField], value: u8) -> String {
+    if fields.is_empty() {
+        return format!("0x{value:02X}");
+    }
+    let mut names: Vec<String> = vec![];
+    for field in fields {
+        let bits = value & field.mask;
+        if let Some((_, name)) = field.values.iter().find(|(v, _)| *v == bits) {
+            names.push((*name).into());
+        } else if field.mask.count_ones() > 1 {
+            names.push(format!(
+                "{}={}",
+                field.name,
+                bits >> field.mask.trailing_zeros()
+            ));
+        } else if bits != 0 {
+            names.push(field.name.into());
+        }
+    }
+    let undefined = value & !fields_mask(fields);
+    if undefined != 0 {
+        names.push(format!("0x{undefined:02X}"));
+    }
+    if names.is_empty() {
+        return "0".into();
+    }
+    names.join(" | ")
+}
+
+const SPRCTL0_FIELDS: &[BitField] = &[
+    field(
+        "TYPE",
+        SPRCTL0_SPR_TYPE,
+        &[
+            (0, "TYPE_BACKGROUND_SHADOW"),
+            (1, "TYPE_BACKGROUND_NO_COLLIDE"),
+            (2, "TYPE_BOUNDARY_SHADOW"),
+            (3, "TYPE_BOUNDARY"),
+            (4, "TYPE_NORMAL"),
+            (5, "TYPE_NO_COLLIDE"),
+            (6, "TYPE_XOR_SHADOW"),
+            (7, "TYPE_SHADOW"),
+        ],
+    ),
+    field(
+        "BPP",
+        SPRCTL0_BPP,
+        &[
+            (0x00, "BPP1"),
+            (0x40, "BPP2"),
+            (0x80, "BPP3"),
+            (0xC0, "BPP4"),
+        ],
+    ),
+    flag("HFLIP", SPRCTL0_HFLIP),
+    flag("VFLIP", SPRCTL0_VFLIP),
+];
+
+const SPRCTL1_FIELDS: &[BitField] = &[
+    flag("LITERAL", SPRCTL1_LITERAL),
+    flag("ALGO_3", SPRCTL1_ALGO_3),
+    field(
+        "RELOAD",
+        SPRCTL1_RELOAD_HVST,
+        &[
+            (0x00, "RELOAD_NONE"),
+            (0x10, "RELOAD_HV"),
+            (0x20, "RELOAD_HVS"),
+            (0x30, "RELOAD_HVST"),
+        ],
+    ),
+    flag("REUSE_PALETTE", SPRCTL1_REUSE_PALETTE),
+    flag("SKIP_SPRITE", SPRCTL1_SKIP_SPRITE),
+    flag("DRAW_UP", SPRCTL1_DRAW_UP),
+    flag("DRAW_LEFT", SPRCTL1_DRAW_LEFT),
+];
+
+const SPRCOLL_FIELDS: &[BitField] = &[
+    flag("DONT_COLLIDE", SPRCOLL_DONT_COLLIDE),
+    field("NUMBER", SPRCOLL_NUMBER, &[]),
+];
+
+const SUZYBUSEN_FIELDS: &[BitField] = &[flag("ENABLE", 0x01)];
+
+const SPRGO_FIELDS: &[BitField] = &[flag("EVERON", SPRGO_EVERON), flag("GO", SPRGO_GO)];
+
+const SPRSYS_READ_FIELDS: &[BitField] = &[
+    flag("MATH_IN_PROGRESS", SPRSYS_MATH_IN_PROGRESS),
+    flag("MATHBIT", SPRSYS_MATHBIT),
+    flag("LAST_CARRY", SPRSYS_LAST_CARRY),
+    flag("VSTRETCH", SPRSYS_VSTRETCH),
+    flag("LEFTHAND", SPRSYS_LEFTHAND),
+    flag("UNSAFE_ACCESS", SPRSYS_UNSAFE_ACCESS),
+    flag("STOP_CURRENT_SPRITE", SPRSYS_STOP_CURRENT_SPRITE),
+    flag("SPRITE_IN_PROGRESS", SPRSYS_SPRITE_IN_PROGRESS),
+];
+
+const SPRSYS_WRITE_FIELDS: &[BitField] = &[
+    flag("SIGN_MATH", SPRSYS_SIGN_MATH),
+    flag("ACCUMULATE", SPRSYS_ACCUMULATE),
+    flag("DONT_COLLIDE", SPRSYS_DONT_COLLIDE),
+    flag("VSTRETCH", SPRSYS_VSTRETCH),
+    flag("LEFTHAND", SPRSYS_LEFTHAND),
+    flag("CLEAR_UNSAFE", SPRSYS_CLEAR_UNSAFE),
+    flag("STOP_CURRENT_SPRITE", SPRSYS_STOP_CURRENT_SPRITE),
+];
+
+const JOYSTICK_FIELDS: &[BitField] = &[
+    flag("DOWN", 0x80),
+    flag("UP", 0x40),
+    flag("RIGHT", 0x20),
+    flag("LEFT", 0x10),
+    flag("OPTION1", 0x08),
+    flag("OPTION2", 0x04),
+    flag("INSIDE", 0x02),
+    flag("OUTSIDE", 0x01),
+];
+
+const SWITCHES_FIELDS: &[BitField] = &[
+    flag("CART1_INACTIVE", 0x04),
+    flag("CART0_INACTIVE", 0x02),
+    flag("PAUSE", 0x01),
+];
+
+const PERIODS: &[(u8, &str)] = &[
+    (0, "PERIOD_1US"),
+    (1, "PERIOD_2US"),
+    (2, "PERIOD_4US"),
+    (3, "PERIOD_8US"),
+    (4, "PERIOD_16US"),
+    (5, "PERIOD_32US"),
+    (6, "PERIOD_64US"),
+    (7, "LINKED"),
+];
+
+const TIMER_CTLA_FIELDS: &[BitField] = &[
+    flag("INT_EN", 0x80),
+    flag("RESET_DONE", 0x40),
+    flag("MAGMODE", 0x20),
+    flag("RELOAD", 0x10),
+    flag("COUNT", 0x08),
+    field("PERIOD", 0x07, PERIODS),
+];
+
+const TIMER_CTLB_FIELDS: &[BitField] = &[
+    flag("TIMER_DONE", 0x08),
+    flag("LAST_CLOCK", 0x04),
+    flag("BORROW_IN", 0x02),
+    flag("BORROW_OUT", 0x01),
+];
+
+const AUDIO_CTL_FIELDS: &[BitField] = &[
+    flag("FEEDBACK7", 0x80),
+    flag("RESET_DONE", 0x40),
+    flag("INTEGRATE", 0x20),
+    flag("RELOAD", 0x10),
+    flag("COUNT", 0x08),
+    field("PERIOD", 0x07, PERIODS),
+];
+
+const AUDIO_MISC_FIELDS: &[BitField] = &[
+    field("SHIFT_HIGH", 0xF0, &[]),
+    flag("TIMER_DONE", 0x08),
+    flag("LAST_CLOCK", 0x04),
+    flag("BORROW_IN", 0x02),
+    flag("BORROW_OUT", 0x01),
+];
+
+const ATTEN_FIELDS: &[BitField] = &[field("LEFT", 0xF0, &[]), field("RIGHT", 0x0F, &[])];
+
+const STEREO_FIELDS: &[BitField] = &[
+    flag("LEFT3", 0x80),
+    flag("LEFT2", 0x40),
+    flag("LEFT1", 0x20),
+    flag("LEFT0", 0x10),
+    flag("RIGHT3", 0x08),
+    flag("RIGHT2", 0x04),
+    flag("RIGHT1", 0x02),
+    flag("RIGHT0", 0x01),
+];
+
+const INT_FIELDS: &[BitField] = &[
+    flag("TIMER7", 0x80),
+    flag("TIMER6", 0x40),
+    flag("TIMER5", 0x20),
+    flag("TIMER4", 0x10),
+    flag("TIMER3", 0x08),
+    flag("TIMER2", 0x04),
+    flag("TIMER1", 0x02),
+    flag("TIMER0", 0x01),
+];
+
+const SYSCTL1_FIELDS: &[BitField] = &[flag("POWER", SYSCTL1_POWER), flag("CAS", SYSCTL1_CAS)];
+
+const IO_FIELDS: &[BitField] = &[
+    flag("AUDIN", IODAT_AUDIN),
+    flag("REST", IODAT_REST),
+    flag("NOEXP", IODAT_NOEXP),
+    flag("CAD", IODAT_CAD),
+    flag("EXTPW", IODAT_EXTPW),
+];
+
+const SERCTL_READ_FIELDS: &[BitField] = &[
+    flag("TXRDY", 0x80),
+    flag("RXRDY", 0x40),
+    flag("TXEMPTY", 0x20),
+    flag("PARERR", 0x10),
+    flag("OVERRUN", 0x08),
+    flag("FRAMERR", 0x04),
+    flag("RXBRK", 0x02),
+    flag("PARBIT", 0x01),
+];
+
+const SERCTL_WRITE_FIELDS: &[BitField] = &[
+    flag("TXINTEN", 0x80),
+    flag("RXINTEN", 0x40),
+    flag("PAREN", 0x10),
+    flag("RESETERR", 0x08),
+    flag("TXOPEN", 0x04),
+    flag("TXBRK", 0x02),
+    flag("PAREVEN", 0x01),
+];
+
+const DISPCTL_FIELDS: &[BitField] = &[
+    flag("COLOR", 0x08),
+    flag("FOURBIT", 0x04),
+    flag("FLIP", 0x02),
+    flag("DMA_ENABLE", 0x01),
+];
+
+const GREEN_FIELDS: &[BitField] = &[field("GREEN", 0x0F, &[])];
+
+const BLUERED_FIELDS: &[BitField] = &[field("BLUE", 0xF0, &[]), field("RED", 0x0F, &[])];
+
+const MAPCTL_FIELDS: &[BitField] = &[
+    flag("VECTORS", MAPCTL_VEC_BIT),
+    flag("ROM", MAPCTL_ROM_BIT),
+    flag("MIKEY", MAPCTL_MIK_BIT),
+    flag("SUZY", MAPCTL_SUZ_BIT),
+];
+
+/// Every Suzy and Mikey register, and `MAPCTL`.
+pub const REGISTERS: &[RegisterDescriptor] = &[
+    reg("TMPADR", TMPADRL, ReadWrite, "Temporary address").word(),
+    reg(
+        "TILTACUM",
+        TILTACUML,
+        ReadWrite,
+        "Accumulator for tilt value",
+    )
+    .word(),
+    reg("HOFF", HOFFL, ReadWrite, "Offset to H edge of screen").word(),
+    reg("VOFF", VOFFL, ReadWrite, "Offset to V edge of screen").word(),
+    reg(
+        "VIDBAS",
+        VIDBASL,
+        ReadWrite,
+        "Base address of the video build buffer",
+    )
+    .word(),
+    reg(
+        "COLLBAS",
+        COLLBASL,
+        ReadWrite,
+        "Base address of the collision build buffer",
+    )
+    .word(),
+    reg("VIDADR", VIDADRL, ReadWrite, "Current video build address").word(),
+    reg(
+        "COLLADR",
+        COLLADRL,
+        ReadWrite,
+        "Current collision build address",
+    )
+    .word(),
+    reg("SCBNEXT", SCBNEXTL, ReadWrite, "Address of the next SCB").word(),
+    reg(
+        "SPRDLINE",
+        SPRDLINEL,
+        ReadWrite,
+        "Start of the sprite data line address",
+    )
+    .word(),
+    reg("HPOSSTRT", HPOSSTRTL, ReadWrite, "Starting H position").word(),
+    reg("VPOSSTRT", VPOSSTRTL, ReadWrite, "Starting V position").word(),
+    reg("SPRHSIZ", SPRHSIZL, ReadWrite, "H size, 8.8 fixed point").word(),
+    reg("SPRVSIZ", SPRVSIZL, ReadWrite, "V size, 8.8 fixed point").word(),
+    reg("STRETCH", STRETCHL, ReadWrite, "H size adder").word(),
+    reg("TILT", TILTL, ReadWrite, "H position adder").word(),
+    reg(
+        "SPRDOFF",
+        SPRDOFFL,
+        ReadWrite,
+        "Offset to the next sprite data line",
+    )
+    .word(),
+    reg("SPRVPOS", SPRVPOSL, ReadWrite, "Current V position").word(),
+    reg(
+        "COLLOFF",
+        COLLOFFL,
+        ReadWrite,
+        "Offset to the collision depository",
+    )
+    .word(),
+    reg(
+        "VSIZACUM",
+        VSIZACUML,
+        ReadWrite,
+        "Vertical size accumulator",
+    )
+    .word(),
+    reg("HSIZOFF", HSIZOFFL, ReadWrite, "Horizontal size offset").word(),
+    reg("VSIZOFF", VSIZOFFL, ReadWrite, "Vertical size offset").word(),
+    reg("SCBADR", SCBADRL, ReadWrite, "Address of the current SCB").word(),
+    reg(
+        "PROCADR",
+        PROCADRL,
+        ReadWrite,
+        "Current sprite data processing address",
+    )
+    .word(),
+    reg(
+        "MATHD",
+        MATHD,
+        ReadWrite,
+        "Multiplicand low byte, quotient byte 0",
+    ),
+    reg(
+        "MATHC",
+        MATHC,
+        ReadWrite,
+        "Multiplicand high byte, quotient byte 1",
+    ),
+    reg(
+        "MATHB",
+        MATHB,
+        ReadWrite,
+        "Multiplier low byte, quotient byte 2",
+    ),
+    reg(
+        "MATHA",
+        MATHA,
+        ReadWrite,
+        "Multiplier high byte, quotient byte 3, starts a multiply",
+    ),
+    reg("MATHP", MATHP, ReadWrite, "Divisor low byte"),
+    reg("MATHN", MATHN, ReadWrite, "Divisor high byte"),
+    reg("MATHH", MATHH, ReadWrite, "Product and dividend byte 0"),
+    reg("MATHG", MATHG, ReadWrite, "Product and dividend byte 1"),
+    reg("MATHF", MATHF, ReadWrite, "Product and dividend byte 2"),
+    reg(
+        "MATHE",
+        MATHE,
+        ReadWrite,
+        "Product and dividend byte 3, writing it starts a divide",
+    ),
+    reg(
+        "MATHM",
+        MATHM,
+        ReadWrite,
+        "Accumulator and remainder byte 0, clears the overflow",
+    ),
+    reg(
+        "MATHL",
+        MATHL,
+        ReadWrite,
+        "Accumulator and remainder byte 1",
+    ),
+    reg(
+        "MATHK",
+        MATHK,
+        ReadWrite,
+        "Accumulator and remainder byte 2",
+    ),
+    reg(
+        "MATHJ",
+        MATHJ,
+        ReadWrite,
+        "Accumulator and remainder byte 3",
+    ),
+    reg("SPRCTL0", SPRCTL0, WriteOnly, "Sprite control bits 0").fields(SPRCTL0_FIELDS),
+    reg("SPRCTL1", SPRCTL1, WriteOnly, "Sprite control bits 1").fields(SPRCTL1_FIELDS),
+    reg("SPRCOLL", SPRCOLL, WriteOnly, "Sprite collision number").fields(SPRCOLL_FIELDS),
+    reg("SPRINIT", SPRINIT, WriteOnly, "Sprite initialization bits"),
+    reg("SUZYHREV", SUZYHREV, ReadOnly, "Suzy hardware revision"),
+    reg("SUZYBUSEN", SUZYBUSEN, WriteOnly, "Suzy bus enable").fields(SUZYBUSEN_FIELDS),
+    reg("SPRGO", SPRGO, WriteOnly, "Sprite process start bit").fields(SPRGO_FIELDS),
+    reg("SPRSYS", SPRSYS, ReadWrite, "System control bits")
+        .read_fields(SPRSYS_READ_FIELDS)
+        .write_fields(SPRSYS_WRITE_FIELDS),
+    reg("JOYSTICK", JOYSTICK, ReadOnly, "Joystick and buttons").fields(JOYSTICK_FIELDS),
+    reg("SWITCHES", SWITCHES, ReadOnly, "Other switches").fields(SWITCHES_FIELDS),
+    reg(
+        "RCART0",
+        RCART0,
+        ReadWrite,
+        "Cartridge bank 0 data, increments the position",
+    ),
+    reg(
+        "RCART1",
+        RCART1,
+        ReadWrite,
+        "Cartridge bank 1 data, increments the position",
+    ),
+    reg("TIM0BKUP", TIM0BKUP, ReadWrite, "Timer 0 backup value"),
+    reg("TIM0CTLA", TIM0CTLA, ReadWrite, "Timer 0 static control").fields(TIMER_CTLA_FIELDS),
+    reg("TIM0CNT", TIM0CNT, ReadWrite, "Timer 0 current count"),
+    reg("TIM0CTLB", TIM0CTLB, ReadWrite, "Timer 0 dynamic control").fields(TIMER_CTLB_FIELDS),
+    reg("TIM1BKUP", TIM1BKUP, ReadWrite, "Timer 1 backup value"),
+    reg("TIM1CTLA", TIM1CTLA, ReadWrite, "Timer 1 static control").fields(TIMER_CTLA_FIELDS),
+    reg("TIM1CNT", TIM1CNT, ReadWrite, "Timer 1 current count"),
+    reg("TIM1CTLB", TIM1CTLB, ReadWrite, "Timer 1 dynamic control").fields(TIMER_CTLB_FIELDS),
+    reg("TIM2BKUP", TIM2BKUP, ReadWrite, "Timer 2 backup value"),
+    reg("TIM2CTLA", TIM2CTLA, ReadWrite, "Timer 2 static control").fields(TIMER_CTLA_FIELDS),
+    reg("TIM2CNT", TIM2CNT, ReadWrite, "Timer 2 current count"),
+    reg("TIM2CTLB", TIM2CTLB, ReadWrite, "Timer 2 dynamic control").fields(TIMER_CTLB_FIELDS),
+    reg("TIM3BKUP", TIM3BKUP, ReadWrite, "Timer 3 backup value"),
+    reg("TIM3CTLA", TIM3CTLA, ReadWrite, "Timer 3 static control").fields(TIMER_CTLA_FIELDS),
+    reg("TIM3CNT", TIM3CNT, ReadWrite, "Timer 3 current count"),
+    reg("TIM3CTLB", TIM3CTLB, ReadWrite, "Timer 3 dynamic control").fields(TIMER_CTLB_FIELDS),
+    reg("TIM4BKUP", TIM4BKUP, ReadWrite, "Timer 4 backup value"),
+    reg("TIM4CTLA", TIM4CTLA, ReadWrite, "Timer 4 static control").fields(TIMER_CTLA_FIELDS),
+    reg("TIM4CNT", TIM4CNT, ReadWrite, "Timer 4 current count"),
+    reg("TIM4CTLB", TIM4CTLB, ReadWrite, "Timer 4 dynamic control").fields(TIMER_CTLB_FIELDS),
+    reg("TIM5BKUP", TIM5BKUP, ReadWrite, "Timer 5 backup value"),
+    reg("TIM5CTLA", TIM5CTLA, ReadWrite, "Timer 5 static control").fields(TIMER_CTLA_FIELDS),
+    reg("TIM5CNT", TIM5CNT, ReadWrite, "Timer 5 current count"),
+    reg("TIM5CTLB", TIM5CTLB, ReadWrite, "Timer 5 dynamic control").fields(TIMER_CTLB_FIELDS),
+    reg("TIM6BKUP", TIM6BKUP, ReadWrite, "Timer 6 backup value"),
+    reg("TIM6CTLA", TIM6CTLA, ReadWrite, "Timer 6 static control").fields(TIMER_CTLA_FIELDS),
+    reg("TIM6CNT", TIM6CNT, ReadWrite, "Timer 6 current count"),
+    reg("TIM6CTLB", TIM6CTLB, ReadWrite, "Timer 6 dynamic control").fields(TIMER_CTLB_FIELDS),
+    reg("TIM7BKUP", TIM7BKUP, ReadWrite, "Timer 7 backup value"),
+    reg("TIM7CTLA", TIM7CTLA, ReadWrite, "Timer 7 static control").fields(TIMER_CTLA_FIELDS),
+    reg("TIM7CNT", TIM7CNT, ReadWrite, "Timer 7 current count"),
+    reg("TIM7CTLB", TIM7CTLB, ReadWrite, "Timer 7 dynamic control").fields(TIMER_CTLB_FIELDS),
+    reg(
+        "AUD0VOL",
+        AUD0VOL,
+        ReadWrite,
+        "Audio channel 0 2's complement volume",
+    ),
+    reg(
+        "AUD0SHFTFB",
+        AUD0SHFTFB,
+        ReadWrite,
+        "Audio channel 0 shift register feedback enables",
+    ),
+    reg(
+        "AUD0OUTVAL",
+        AUD0OUTVAL,
+        ReadWrite,
+        "Audio channel 0 output value",
+    ),
+    reg(
+        "AUD0L8SHFT",
+        AUD0L8SHFT,
+        ReadWrite,
+        "Audio channel 0 lower 8 bits of the shift register",
+    ),
+    reg(
+        "AUD0TBACK",
+        AUD0TBACK,
+        ReadWrite,
+        "Audio channel 0 timer backup value",
+    ),
+    reg("AUD0CTL", AUD0CTL, ReadWrite, "Audio channel 0 control").fields(AUDIO_CTL_FIELDS),
+    reg(
+        "AUD0COUNT",
+        AUD0COUNT,
+        ReadWrite,
+        "Audio channel 0 timer count",
+    ),
+    reg(
+        "AUD0MISC",
+        AUD0MISC,
+        ReadWrite,
+        "Audio channel 0 other bits",
+    )
+    .fields(AUDIO_MISC_FIELDS),
+    reg(
+        "AUD1VOL",
+        AUD1VOL,
+        ReadWrite,
+        "Audio channel 1 2's complement volume",
+    ),
+    reg(
+        "AUD1SHFTFB",
+        AUD1SHFTFB,
+        ReadWrite,
+        "Audio channel 1 shift register feedback enables",
+    ),
+    reg(
+        "AUD1OUTVAL",
+        AUD1OUTVAL,
+        ReadWrite,
+        "Audio channel 1 output value",
+    ),
+    reg(
+        "AUD1L8SHFT",
+        AUD1L8SHFT,
+        ReadWrite,
+        "Audio channel 1 lower 8 bits of the shift register",
+    ),
+    reg(
+        "AUD1TBACK",
+        AUD1TBACK,
+        ReadWrite,
+        "Audio channel 1 timer backup value",
+    ),
+    reg("AUD1CTL", AUD1CTL, ReadWrite, "Audio channel 1 control").fields(AUDIO_CTL_FIELDS),
+    reg(
+        "AUD1COUNT",
+        AUD1COUNT,
+        ReadWrite,
+        "Audio channel 1 timer count",
+    ),
+    reg(
+        "AUD1MISC",
+        AUD1MISC,
+        ReadWrite,
+        "Audio channel 1 other bits",
+    )
+    .fields(AUDIO_MISC_FIELDS),
+    reg(
+        "AUD2VOL",
+        AUD2VOL,
+        ReadWrite,
+        "Audio channel 2 2's complement volume",
+    ),
+    reg(
+        "AUD2SHFTFB",
+        AUD2SHFTFB,
+        ReadWrite,
+        "Audio channel 2 shift register feedback enables",
+    ),
+    reg(
+        "AUD2OUTVAL",
+        AUD2OUTVAL,
+        ReadWrite,
+        "Audio channel 2 output value",
+    ),
+    reg(
+        "AUD2L8SHFT",
+        AUD2L8SHFT,
+        ReadWrite,
+        "Audio channel 2 lower 8 bits of the shift register",
+    ),
+    reg(
+        "AUD2TBACK",
+        AUD2TBACK,
+        ReadWrite,
+        "Audio channel 2 timer backup value",
+    ),
+    reg("AUD2CTL", AUD2CTL, ReadWrite, "Audio channel 2 control").fields(AUDIO_CTL_FIELDS),
+    reg(
+        "AUD2COUNT",
+        AUD2COUNT,
+        ReadWrite,
+        "Audio channel 2 timer count",
+    ),
+    reg(
+        "AUD2MISC",
+        AUD2MISC,
+        ReadWrite,
+        "Audio channel 2 other bits",
+    )
+    .fields(AUDIO_MISC_FIELDS),
+    reg(
+        "AUD3VOL",
+        AUD3VOL,
+        ReadWrite,
+        "Audio channel 3 2's complement volume",
+    ),
+    reg(
+        "AUD3SHFTFB",
+        AUD3SHFTFB,
+        ReadWrite,
+        "Audio channel 3 shift register feedback enables",
+    ),
+    reg(
+        "AUD3OUTVAL",
+        AUD3OUTVAL,
+        ReadWrite,
+        "Audio channel 3 output value",
+    ),
+    reg(
+        "AUD3L8SHFT",
+        AUD3L8SHFT,
+        ReadWrite,
+        "Audio channel 3 lower 8 bits of the shift register",
+    ),
+    reg(
+        "AUD3TBACK",
+        AUD3TBACK,
+        ReadWrite,
+        "Audio channel 3 timer backup value",
+    ),
+    reg("AUD3CTL", AUD3CTL, ReadWrite, "Audio channel 3 control").fields(AUDIO_CTL_FIELDS),
+    reg(
+        "AUD3COUNT",
+        AUD3COUNT,
+        ReadWrite,
+        "Audio channel 3 timer count",
+    ),
+    reg(
+        "AUD3MISC",
+        AUD3MISC,
+        ReadWrite,
+        "Audio channel 3 other bits",
+    )
+    .fields(AUDIO_MISC_FIELDS),
+    reg(
+        "ATTEN_A",
+        ATTEN_A,
+        ReadWrite,
+        "Audio channel 0 stereo attenuation",
+    )
+    .fields(ATTEN_FIELDS),
+    reg(
+        "ATTEN_B",
+        ATTEN_B,
+        ReadWrite,
+        "Audio channel 1 stereo attenuation",
+    )
+    .fields(ATTEN_FIELDS),
+    reg(
+        "ATTEN_C",
+        ATTEN_C,
+        ReadWrite,
+        "Audio channel 2 stereo attenuation",
+    )
+    .fields(ATTEN_FIELDS),
+    reg(
+        "ATTEN_D",
+        ATTEN_D,
+        ReadWrite,
+        "Audio channel 3 stereo attenuation",
+    )
+    .fields(ATTEN_FIELDS),
+    reg("MPAN", MPAN, ReadWrite, "Stereo attenuation enables").fields(STEREO_FIELDS),
+    reg("MSTEREO", MSTEREO, ReadWrite, "Stereo channel enables").fields(STEREO_FIELDS),
+    reg(
+        "INTRST",
+        INTRST,
+        ReadWrite,
+        "Pending interrupts, writing clears them",
+    )
+    .fields(INT_FIELDS),
+    reg(
+        "INTSET",
+        INTSET,
+        ReadWrite,
+        "Pending interrupts, writing sets them",
+    )
+    .fields(INT_FIELDS),
+    reg("MAGRDY0", MAGRDY0, ReadOnly, "Mag tape channel 0 ready"),
+    reg("MAGRDY1", MAGRDY1, ReadOnly, "Mag tape channel 1 ready"),
+    reg("AUDIN", AUDIN, ReadOnly, "Audio in"),
+    reg(
+        "SYSCTL1",
+        SYSCTL1,
+        WriteOnly,
+        "Power and cartridge strobe control",
+    )
+    .fields(SYSCTL1_FIELDS),
+    reg("MIKEYHREV", MIKEYHREV, ReadOnly, "Mikey hardware revision"),
+    reg("MIKEYSREV", MIKEYSREV, WriteOnly, "Mikey software revision"),
+    reg(
+        "IODIR",
+        IODIR,
+        WriteOnly,
+        "Parallel port direction, set bits are outputs",
+    )
+    .fields(IO_FIELDS),
+    reg("IODAT", IODAT, ReadWrite, "Parallel port data").fields(IO_FIELDS),
+    reg("SERCTL", SERCTL, ReadWrite, "Serial control and status")
+        .read_fields(SERCTL_READ_FIELDS)
+        .write_fields(SERCTL_WRITE_FIELDS),
+    reg("SERDAT", SERDAT, ReadWrite, "Serial data"),
+    reg("SDONEACK", SDONEACK, WriteOnly, "Suzy done acknowledge"),
+    reg(
+        "CPUSLEEP",
+        CPUSLEEP,
+        WriteOnly,
+        "Puts the CPU to sleep until an interrupt",
+    ),
+    reg("DISPCTL", DISPCTL, WriteOnly, "Display control").fields(DISPCTL_FIELDS),
+    reg("PBKUP", PBKUP, WriteOnly, "Magic 'P' count"),
+    reg(
+        "DISPADR",
+        DISPADRL,
+        WriteOnly,
+        "Start address of the video display",
+    )
+    .word(),
+    reg("MTEST0", MTEST0, WriteOnly, "Mikey test register 0"),
+    reg("MTEST1", MTEST1, WriteOnly, "Mikey test register 1"),
+    reg("MTEST2", MTEST2, WriteOnly, "Mikey test register 2"),
+    reg("GREEN0", GREEN0, ReadWrite, "Green level of pen 0").fields(GREEN_FIELDS),
+    reg("GREEN1", GREEN1, ReadWrite, "Green level of pen 1").fields(GREEN_FIELDS),
+    reg("GREEN2", GREEN2, ReadWrite, "Green level of pen 2").fields(GREEN_FIELDS),
+    reg("GREEN3", GREEN3, ReadWrite, "Green level of pen 3").fields(GREEN_FIELDS),
+    reg("GREEN4", GREEN4, ReadWrite, "Green level of pen 4").fields(GREEN_FIELDS),
+    reg("GREEN5", GREEN5, ReadWrite, "Green level of pen 5").fields(GREEN_FIELDS),
+    reg("GREEN6", GREEN6, ReadWrite, "Green level of pen 6").fields(GREEN_FIELDS),
+    reg("GREEN7", GREEN7, ReadWrite, "Green level of pen 7").fields(GREEN_FIELDS),
+    reg("GREEN8", GREEN8, ReadWrite, "Green level of pen 8").fields(GREEN_FIELDS),
+    reg("GREEN9", GREEN9, ReadWrite, "Green level of pen 9").fields(GREEN_FIELDS),
+    reg("GREENA", GREENA, ReadWrite, "Green level of pen A").fields(GREEN_FIELDS),
+    reg("GREENB", GREENB, ReadWrite, "Green level of pen B").fields(GREEN_FIELDS),
+    reg("GREENC", GREENC, ReadWrite, "Green level of pen C").fields(GREEN_FIELDS),
+    reg("GREEND", GREEND, ReadWrite, "Green level of pen D").fields(GREEN_FIELDS),
+    reg("GREENE", GREENE, ReadWrite, "Green level of pen E").fields(GREEN_FIELDS),
+    reg("GREENF", GREENF, ReadWrite, "Green level of pen F").fields(GREEN_FIELDS),
+    reg(
+        "BLUERED0",
+        BLUERED0,
+        ReadWrite,
+        "Blue and red levels of pen 0",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUERED1",
+        BLUERED1,
+        ReadWrite,
+        "Blue and red levels of pen 1",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUERED2",
+        BLUERED2,
+        ReadWrite,
+        "Blue and red levels of pen 2",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUERED3",
+        BLUERED3,
+        ReadWrite,
+        "Blue and red levels of pen 3",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUERED4",
+        BLUERED4,
+        ReadWrite,
+        "Blue and red levels of pen 4",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUERED5",
+        BLUERED5,
+        ReadWrite,
+        "Blue and red levels of pen 5",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUERED6",
+        BLUERED6,
+        ReadWrite,
+        "Blue and red levels of pen 6",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUERED7",
+        BLUERED7,
+        ReadWrite,
+        "Blue and red levels of pen 7",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUERED8",
+        BLUERED8,
+        ReadWrite,
+        "Blue and red levels of pen 8",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUERED9",
+        BLUERED9,
+        ReadWrite,
+        "Blue and red levels of pen 9",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUEREDA",
+        BLUEREDA,
+        ReadWrite,
+        "Blue and red levels of pen A",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUEREDB",
+        BLUEREDB,
+        ReadWrite,
+        "Blue and red levels of pen B",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUEREDC",
+        BLUEREDC,
+        ReadWrite,
+        "Blue and red levels of pen C",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUEREDD",
+        BLUEREDD,
+        ReadWrite,
+        "Blue and red levels of pen D",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUEREDE",
+        BLUEREDE,
+        ReadWrite,
+        "Blue and red levels of pen E",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "BLUEREDF",
+        BLUEREDF,
+        ReadWrite,
+        "Blue and red levels of pen F",
+    )
+    .fields(BLUERED_FIELDS),
+    reg(
+        "MAPCTL",
+        MMC_ADDR,
+        ReadWrite,
+        "Memory map, set bits map RAM instead",
+    )
+    .fields(MAPCTL_FIELDS),
+];
+
+/// Index in `REGISTERS` plus one of the register at each address from `SUZ_ADDR`, 0 if none.
+static REGISTER_INDEX: [u8; 0x400] = register_index();
+
+const fn register_index() -> [u8; 0x400] {
+    assert!(REGISTERS.len() < 0x100);
+    let mut index = [0; 0x400];
+    let mut i = 0;
+    while i < REGISTERS.len() {
+        let r = &REGISTERS[i];
+        let mut byte = 0;
+        while byte < r.size as usize {
+            index[(r.address - SUZ_ADDR) as usize + byte] = i as u8 + 1;
+            byte += 1;
+        }
+        i += 1;
+    }
+    index
+}
+
+/// The register at `addr`, either byte of the 16 bits ones.
+#[must_use]
+pub fn register(addr: u16) -> Option<&'static RegisterDescriptor> {
+    let i = REGISTER_INDEX[addr.checked_sub(SUZ_ADDR)? as usize];
+    if i == 0 {
+        None
+    } else {
+        Some(&REGISTERS[i as usize - 1])
+    }
+}
+
+#[must_use]
+pub fn register_by_name(name: &str) -> Option<&'static RegisterDescriptor> {
+    REGISTERS.iter().find(|r| r.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::{ROM_ADDR, SERCTL, SPRCOLL, SPRCTL0, SWITCHES};
+
+    #[test]
+    fn format_sprctl0() {
+        let sprctl0 = register(SPRCTL0).unwrap();
+        assert_eq!(sprctl0.format_write(0xE4), "TYPE_NORMAL | BPP4 | HFLIP");
+        assert_eq!(sprctl0.format_write(0x00), "TYPE_BACKGROUND_SHADOW | BPP1");
+    }
+
+    #[test]
+    fn format_serctl() {
+        let serctl = register_by_name("serctl").unwrap();
+        assert_eq!(serctl.address, SERCTL);
+        assert_eq!(serctl.format_read(0xA0), "TXRDY | TXEMPTY");
+        assert_eq!(serctl.format_write(0x00), "0");
+        assert_eq!(serctl.check_write(0x20), Err("Value sets undefined bits"));
+    }
+
+    #[test]
+    fn word_registers() {
+        let dispadr = register(DISPADRL + 1).unwrap();
+        assert_eq!(dispadr.name, "DISPADR");
+        assert!(!dispadr.readable());
+        assert!(register(JOYSTICK).unwrap().check_write(0).is_err());
+    }
+
+    #[test]
+    fn every_register_found() {
+        for r in REGISTERS {
+            for addr in r.address..r.address + u16::from(r.size) {
+                assert_eq!(register(addr), Some(r), "{} at 0x{addr:04X}", r.name);
+            }
+            assert_eq!(register_by_name(r.name), Some(r));
+        }
+    }
+
+    #[test]
+    fn unmapped_addresses() {
+        assert_eq!(register(0x0000), None);
+        assert_eq!(register(SUZ_ADDR - 1), None);
+        assert_eq!(register(ROM_ADDR), None);
+        assert_eq!(register(MMC_ADDR + 1), None);
+        assert_eq!(register(0xFFFF), None);
+        assert_eq!(register_by_name("NOTAREG"), None);
+    }
+
+    #[test]
+    fn format_values() {
+        let sprcoll = register(SPRCOLL).unwrap();
+        assert_eq!(sprcoll.format_write(0x23), "DONT_COLLIDE | NUMBER=3");
+        assert_eq!(sprcoll.format_write(0x00), "NUMBER=0");
+        let switches = register(SWITCHES).unwrap();
+        assert_eq!(switches.format_read(0x09), "PAUSE | 0x08");
+        assert_eq!(register(MATHA).unwrap().format_write(0x5A), "0x5A");
+    }
+
+    #[test]
+    fn check_writes() {
+        let sprctl0 = register(SPRCTL0).unwrap();
+        assert_eq!(sprctl0.check_write(0xF7), Ok(()));
+        assert_eq!(sprctl0.check_write(0x08), Err("Value sets undefined bits"));
+        assert_eq!(
+            register(SWITCHES).unwrap().check_write(0),
+            Err("Register is read only")
+        );
+        assert_eq!(register(MATHA).unwrap().check_write(0xFF), Ok(()));
+        let mapctl = register(MMC_ADDR).unwrap();
+        assert!(mapctl.readable() && mapctl.writable());
+    }
+}
@@ -98,6 +98,12 @@ impl Rom {
         self.data[d..(d + buf.len())].copy_from_slice(buf);
     }
 
+    /// Drops the read in progress.
+    pub fn reset(&mut self) {
+        self.addr_r = 0;
+        self.ticks_to_done = -1;
+    }
+
     pub fn peek(&mut self, bus: &Bus) {
         if bus.addr() & 0xff00 == self.addr_r & 0xff00 {
             self.ticks_to_done = ROM_PAGE_READ_TICKS;
@@ -1,13 +1,25 @@
 use log::trace;
 
-use super::SuzyRegisters;
+use super::{Deserialize, Serialize, Suzy, SuzyInstruction, SuzyRegisters};
 use crate::{
+    bus::{Bus, BusStatus},
     consts::{MATHA, MATHC, MATHE, MATHL, MATHM, SUZY_MULT_NON_SIGN_TICKS, SUZY_MULT_SIGN_TICKS},
+    ram::Ram,
     suzy::{SprSysR, SprSysW},
 };
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MathMode {
+    /// 0 is a positive number in signed multiplies and every multiply clears the accumulator overflow.
+    #[default]
+    Compatible,
+    /// Reproduces the documented hardware bugs. A divide by zero gives the same
+    /// documented 'FFFFFFFF result in both modes.
+    Accurate,
+}
+
 #[must_use]
-pub fn convert_sign(mut v: u16) -> (u16, i8) {
+pub fn convert_sign(mut v: u16, mode: MathMode) -> (u16, i8) {
     /* "
     In signed multiply, the hardware thinks that 8000 is a positive number. [...]
     In signed multiply, the hardware thinks that 0 is a negative number.
@@ -15,11 +27,21 @@ pub fn convert_sign(mut v: u16) -> (u16, i8) {
     However, since it will set the sign flag, you can not depend on the sign flag to be correct if you just load the lower byte after a multiply by zero.
     " */
     let mut sign: i8 = 1;
-    if v.saturating_sub(1) & 0x8000 != 0 {
-        let mut conversion: u16 = v ^ 0xffff;
-        conversion = conversion.saturating_add(1);
-        sign = -1;
-        v = conversion;
+    match mode {
+        MathMode::Compatible => {
+            if v.saturating_sub(1) & 0x8000 != 0 {
+                let mut conversion: u16 = v ^ 0xffff;
+                conversion = conversion.saturating_add(1);
+                sign = -1;
+                v = conversion;
+            }
+        }
+        MathMode::Accurate => {
+            if v.wrapping_sub(1) & 0x8000 != 0 {
+                sign = -1;
+                v = (v ^ 0xffff).wrapping_add(1);
+            }
+        }
     }
     (v, sign)
 }
@@ -32,17 +54,7 @@ pub fn divide(regs: &mut SuzyRegisters) {
     regs.sprsys_r_disable_flag(SprSysR::math_warning);
     regs.sprsys_r_disable_flag(SprSysR::math_carry);
 
-    if 0 == np {
-        // "The number in the dividend as a result of a divide by zero is 'FFFFFFFF (BigNum)."
-        trace!(
-            "MATH: divide by zero efgh:0x{efgh:08x} / np:0x{np:04x} -> abcd:0xffffffff, jklm: 0x0"
-        );
-        regs.set_abcd(0xffff_ffff);
-        regs.set_jklm(0);
-        regs.sprsys_r_enable_flag(SprSysR::math_warning);
-        regs.sprsys_r_enable_flag(SprSysR::math_carry);
-    } else {
-        let abcd = efgh / np;
+    if let Some(abcd) = efgh.checked_div(np) {
         let jklm = efgh % np;
         trace!("MATH: divide efgh:0x{efgh:08x} / np:0x{np:04x} -> abcd:0x{abcd:08x}, jklm: 0x{jklm:08x}");
         regs.set_abcd(abcd);
@@ -51,18 +63,30 @@ pub fn divide(regs: &mut SuzyRegisters) {
             regs.sprsys_r_enable_flag(SprSysR::math_carry);
         }
         trace!("D;0x{efgh:08X};0x{np:04X};0x{abcd:08X};0x{jklm:08X}\n");
+    } else {
+        // "The number in the dividend as a result of a divide by zero is 'FFFFFFFF (BigNum)."
+        trace!(
+            "MATH: divide by zero efgh:0x{efgh:08x} / np:0x{np:04x} -> abcd:0xffffffff, jklm: 0x0"
+        );
+        regs.set_abcd(0xffff_ffff);
+        regs.set_jklm(0);
+        regs.sprsys_r_enable_flag(SprSysR::math_warning);
+        regs.sprsys_r_enable_flag(SprSysR::math_carry);
     }
 
     regs.sprsys_r_disable_flag(SprSysR::math_working);
 }
 
-pub fn multiply(regs: &mut SuzyRegisters) {
+pub fn multiply(regs: &mut SuzyRegisters, mode: MathMode) {
     let ab = u32::from(regs.ab());
     let cd = u32::from(regs.tmp_cd());
     let mut efgh = ab.overflowing_mul(cd).0;
 
     regs.sprsys_r_enable_flag(SprSysR::unsafe_acces); //"BIG NOTE: Unsafe access is broken for math operations. Please reset it after every math operation or it will not be useful for sprite operations."" 
-    regs.sprsys_r_disable_flag(SprSysR::math_warning);
+    if mode == MathMode::Compatible {
+        // "The write to 'M' will clear the accumulator overflow bit", a multiply doesn't.
+        regs.sprsys_r_disable_flag(SprSysR::math_warning);
+    }
     regs.sprsys_r_disable_flag(SprSysR::math_carry);
 
     if regs.sprsys_w_is_flag_set(SprSysW::sign_math) && 0 == regs.sign_ab() + regs.tmp_sign_cd() {
@@ -102,12 +126,12 @@ pub fn multiply(regs: &mut SuzyRegisters) {
     regs.sprsys_r_disable_flag(SprSysR::math_working);
 }
 
-pub fn set_matha(regs: &mut SuzyRegisters) {
+pub fn set_matha(regs: &mut SuzyRegisters, mode: MathMode) {
     // "The conversion that is performed on the CPU provided starting numbers is done when the upper byte is sent by the CPU."
     trace!("[MATHA] = 0x{:02x}", regs.data_r() as u8);
     regs.set_data(MATHA, regs.data_r() as u8);
     if regs.sprsys_w_is_flag_set(SprSysW::sign_math) {
-        let (v, s) = convert_sign(regs.ab());
+        let (v, s) = convert_sign(regs.ab(), mode);
         regs.set_ab(v);
         regs.set_sign_ab(s);
         regs.set_task_ticks_delay(SUZY_MULT_SIGN_TICKS);
@@ -123,12 +147,12 @@ pub fn set_matha(regs: &mut SuzyRegisters) {
     regs.reset_ir();
 }
 
-pub fn set_mathc(regs: &mut SuzyRegisters) {
+pub fn set_mathc(regs: &mut SuzyRegisters, mode: MathMode) {
     // "The conversion that is performed on the CPU provided starting numbers is done when the upper byte is sent by the CPU."
     trace!("[MATHC] = 0x{:02x}", regs.data_r() as u8);
     regs.set_data(MATHC, regs.data_r() as u8);
     if regs.sprsys_w_is_flag_set(SprSysW::sign_math) {
-        let (v, s) = convert_sign(regs.cd());
+        let (v, s) = convert_sign(regs.cd(), mode);
         regs.set_cd(v);
         regs.set_sign_cd(s);
     } else {
@@ -154,3 +178,193 @@ pub fn set_mathm(regs: &mut SuzyRegisters) {
     regs.sprsys_r_disable_flag(SprSysR::math_warning);
     regs.reset_ir();
 }
+
+/// The Suzy math unit on its own, driven through its registers the way the CPU does.
+///
+/// Accesses go through the emulated Suzy and take as long as a CPU access, the
+/// results are available once [`SuzyMath::busy`] turns `false`.
+#[derive(Serialize, Deserialize)]
+pub struct SuzyMath {
+    suzy: Suzy,
+    bus: Bus,
+    ram: Ram,
+    ticks: u64,
+}
+
+impl SuzyMath {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            suzy: Suzy::new(),
+            bus: Bus::new(),
+            ram: Ram::new(),
+            ticks: 0,
+        }
+    }
+
+    pub fn set_math_mode(&mut self, mode: MathMode) {
+        self.suzy.set_math_mode(mode);
+    }
+
+    /// CPU write to a Suzy register, returns once Suzy took it.
+    pub fn poke(&mut self, addr: u16, data: u8) {
+        self.bus.set_addr(addr);
+        self.bus.set_data(data);
+        self.suzy.poke(&mut self.bus);
+        while self.suzy.registers().ir() != SuzyInstruction::None {
+            self.tick();
+        }
+        self.bus.set_status(BusStatus::None);
+    }
+
+    /// CPU read of a Suzy register, the math unit keeps running meanwhile.
+    pub fn peek(&mut self, addr: u16) -> u8 {
+        self.bus.set_addr(addr);
+        self.suzy.peek(&mut self.bus);
+        while self.bus.status() != BusStatus::PeekDone {
+            self.tick();
+        }
+        self.bus.set_status(BusStatus::None);
+        self.bus.data()
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+        self.suzy.tick(&mut self.bus, &mut self.ram);
+    }
+
+    /// Ticks until the operation in progress is done, returns the number of ticks.
+    pub fn run(&mut self) -> u64 {
+        let start = self.ticks;
+        while self.busy() {
+            self.tick();
+        }
+        self.ticks - start
+    }
+
+    /// "Math in progress", `SPRSYS` read bit 7.
+    #[must_use]
+    pub fn busy(&self) -> bool {
+        self.suzy
+            .registers()
+            .sprsys_r_is_flag_set(SprSysR::math_working)
+    }
+
+    #[must_use]
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    #[must_use]
+    pub fn registers(&self) -> &SuzyRegisters {
+        self.suzy.registers()
+    }
+}
+
+impl Default for SuzyMath {
+    fn default() -> Self {
+        SuzyMath::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::{
+        MATHB, MATHD, MATHF, MATHG, MATHH, MATHJ, MATHN, MATHP, SPRSYS, SUZY_READ_TICKS,
+        SUZY_WRITE_TICKS,
+    };
+    use crate::test_rom::lynx_drawing;
+
+    fn poke_u16(math: &mut SuzyMath, lsb: u16, msb: u16, v: u16) {
+        math.poke(lsb, v as u8);
+        math.poke(msb, (v >> 8) as u8);
+    }
+
+    /// Writes the multiplier then `MATHA`, returns the ticks from the `MATHA` write to the result.
+    fn multiply_ticks(math: &mut SuzyMath, cd: u16, ab: u16) -> u64 {
+        poke_u16(math, MATHD, MATHC, cd);
+        math.poke(MATHB, ab as u8);
+        let start = math.ticks();
+        math.poke(MATHA, (ab >> 8) as u8);
+        assert!(math.busy());
+        math.run();
+        math.ticks() - start
+    }
+
+    #[test]
+    fn test_multiply_timing() {
+        let mut math = SuzyMath::new();
+        // the write, then the multiply starts on the tick Suzy takes it
+        assert_eq!(
+            multiply_ticks(&mut math, 300, 1000),
+            u64::from(SUZY_WRITE_TICKS + 1 + SUZY_MULT_NON_SIGN_TICKS)
+        );
+        assert!(!math.busy());
+        assert_eq!(math.registers().efgh(), 300_000);
+        // 300000 = 0x0004_93E0
+        assert_eq!(math.peek(MATHH), 0xE0);
+        assert_eq!(math.peek(MATHG), 0x93);
+
+        math.poke(SPRSYS, SprSysW::sign_math.bits());
+        assert_eq!(
+            multiply_ticks(&mut math, -300_i16 as u16, 1000),
+            u64::from(SUZY_WRITE_TICKS + 1 + SUZY_MULT_SIGN_TICKS)
+        );
+        assert_eq!(math.registers().efgh(), -300_000_i32 as u32);
+    }
+
+    #[test]
+    fn test_peek_while_busy() {
+        let mut math = SuzyMath::new();
+        poke_u16(&mut math, MATHD, MATHC, 3);
+        poke_u16(&mut math, MATHB, MATHA, 4);
+        let start = math.ticks();
+        assert_eq!(math.peek(SPRSYS) & 0x80, 0x80);
+        assert_eq!(math.ticks() - start, u64::from(SUZY_READ_TICKS + 1));
+        // the multiply went on during the read
+        assert_eq!(
+            math.run(),
+            u64::from(SUZY_MULT_NON_SIGN_TICKS - SUZY_READ_TICKS - 1)
+        );
+        assert_eq!(math.peek(MATHH), 12);
+    }
+
+    #[test]
+    fn test_divide_timing() {
+        let mut math = SuzyMath::new();
+        poke_u16(&mut math, MATHP, MATHN, 7);
+        math.poke(MATHH, 100);
+        math.poke(MATHG, 0);
+        math.poke(MATHF, 0);
+        let start = math.ticks();
+        math.poke(MATHE, 0);
+        math.run();
+        // "Divides take 176 + 14*N ticks where N is the number of most significant zeros in the divisor."
+        assert_eq!(
+            math.ticks() - start,
+            u64::from(SUZY_WRITE_TICKS + 1) + 176 + 14 * 13
+        );
+        assert_eq!(math.registers().abcd(), 14);
+        assert_eq!(math.registers().jklm(), 2);
+        assert_eq!(math.peek(SPRSYS) & 0x20, 0x20);
+
+        poke_u16(&mut math, MATHP, MATHN, 0);
+        math.poke(MATHE, 0);
+        math.run();
+        assert_eq!(math.registers().abcd(), u32::MAX);
+        assert_eq!(math.peek(SPRSYS) & 0x40, 0x40);
+        math.poke(MATHJ, 0);
+        math.poke(MATHM, 0);
+        assert_eq!(math.peek(SPRSYS) & 0x40, 0);
+    }
+
+    #[test]
+    fn test_mode_after_reset() {
+        // an empty SCB chain
+        let mut lynx = lynx_drawing(0);
+        lynx.set_math_mode(MathMode::Accurate);
+        lynx.reset();
+        assert_eq!(lynx.suzy().math_mode(), MathMode::Accurate);
+    }
+}
@@ -2,6 +2,8 @@ pub mod math;
 pub mod registers;
 pub mod renderer;
 pub mod sprite_data;
+pub mod sprite_encoder;
+pub mod sprite_log;
 
 use super::{alloc, bus, consts, mikey, ram};
 use bus::{Bus, BusStatus};
@@ -19,11 +21,12 @@ use consts::{
     VPOSSTRTH, VPOSSTRTL, VSIZACUMH, VSIZACUML, VSIZOFFL,
 };
 use log::trace;
-use math::{divide, multiply, set_matha, set_mathc, set_mathe, set_mathm};
+use math::{divide, multiply, set_matha, set_mathc, set_mathe, set_mathm, MathMode};
 use ram::Ram;
 use registers::{Joystick, SprSysR, SprSysW, SuzyRegisters, Switches, TaskStep};
 use renderer::Renderer;
 use serde::{Deserialize, Serialize};
+use sprite_log::{SpriteCollision, SpriteDraw, SpriteRun};
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SuzyInstruction {
@@ -55,7 +58,8 @@ pub struct Suzy {
     request_monitor: bool,
     pending_bus_request_ticks: i8,
     renderer: Renderer,
-    registers: SuzyRegisters
+    registers: SuzyRegisters,
+    math_mode: MathMode,
 }
 
 impl Suzy {
@@ -66,6 +70,7 @@ impl Suzy {
             pending_bus_request_ticks: -1,
             renderer: Renderer::new(),
             registers: SuzyRegisters::new(),
+            math_mode: MathMode::Compatible,
         };
         s.registers.set_data(SUZYBUSEN, 1);
         s
@@ -243,6 +248,7 @@ impl Suzy {
                 .sprsys_w_disable_flag(SprSysW::sprite_to_stop);
             self.registers.set_task(SuzyTask::SpriteGo);
             self.registers.set_task_step(TaskStep::InitializePainting);
+            self.renderer.start_cost_run();
         }
 
         if self.registers.task() != SuzyTask::None {
@@ -255,6 +261,19 @@ impl Suzy {
         }
     }
 
+    /// Accounts a tick to the sprite cost log if the sprite engine holds the bus,
+    /// `cpu_held` when the CPU got woken up and waits for it.
+    pub fn count_bus_tick(&mut self, bus: &Bus, cpu_held: bool) {
+        if has_bus(bus)
+            && matches!(
+                self.registers.task(),
+                SuzyTask::SpriteGo | SuzyTask::EndSprite
+            )
+        {
+            self.renderer.count_bus_tick(cpu_held);
+        }
+    }
+
     pub fn tick(&mut self, bus: &mut Bus, dma_ram: &mut Ram) {
         self.manage_bus(bus);
         if self.pending_bus_request_ticks >= 0 || self.registers.data(SUZYBUSEN) == 0 {
@@ -275,13 +294,13 @@ impl Suzy {
                 bus.set_status(BusStatus::PokeDone);
             }
             SuzyInstruction::PokeMathA => {
-                set_matha(&mut self.registers);
+                set_matha(&mut self.registers, self.math_mode);
                 self.registers.set_task(SuzyTask::Multiply);
                 trace!("< Poke");
                 bus.set_status(BusStatus::PokeDone);
             }
             SuzyInstruction::PokeMathC => {
-                set_mathc(&mut self.registers);
+                set_mathc(&mut self.registers, self.math_mode);
                 self.registers.reset_ir();
                 trace!("< Poke");
                 bus.set_status(BusStatus::PokeDone);
@@ -360,7 +379,7 @@ impl Suzy {
                 self.registers.set_task(SuzyTask::SpriteGo);
             }
             SuzyTask::Multiply => {
-                multiply(&mut self.registers);
+                multiply(&mut self.registers, self.math_mode);
                 self.registers.reset_task();
                 trace!("< Multiply");
             }
@@ -378,13 +397,94 @@ impl Suzy {
         &self.registers
     }
 
+    #[must_use]
+    pub fn math_mode(&self) -> MathMode {
+        self.math_mode
+    }
+
+    pub fn set_math_mode(&mut self, mode: MathMode) {
+        self.math_mode = mode;
+    }
+
+    #[must_use]
+    pub fn pens(&self) -> &[u8; 16] {
+        self.renderer.pens()
+    }
+
+    pub fn set_dma_log_enabled(&mut self, enabled: bool) {
+        self.renderer.set_dma_log_enabled(enabled);
+    }
+
+    #[must_use]
+    pub fn dma_log_enabled(&self) -> bool {
+        self.renderer.dma_log_enabled()
+    }
+
+    #[must_use]
+    pub fn dma_log(&self) -> &[u16] {
+        self.renderer.dma_log()
+    }
+
+    #[must_use]
+    pub fn dma_write_log(&self) -> &[u16] {
+        self.renderer.dma_write_log()
+    }
+
+    pub fn clear_dma_log(&mut self) {
+        self.renderer.clear_dma_log();
+    }
+
+    pub fn set_collision_log_enabled(&mut self, enabled: bool) {
+        self.renderer.set_collision_log_enabled(enabled);
+    }
+
+    #[must_use]
+    pub fn collision_log(&self) -> &[SpriteCollision] {
+        self.renderer.collision_log()
+    }
+
+    pub fn clear_collision_log(&mut self) {
+        self.renderer.clear_collision_log();
+    }
+
+    pub fn set_draw_log_enabled(&mut self, enabled: bool) {
+        self.renderer.set_draw_log_enabled(enabled);
+    }
+
+    #[must_use]
+    pub fn draw_log(&self) -> &[SpriteDraw] {
+        self.renderer.draw_log()
+    }
+
+    pub fn clear_draw_log(&mut self) {
+        self.renderer.clear_draw_log();
+    }
+
+    pub fn set_cost_log_enabled(&mut self, enabled: bool) {
+        self.renderer.set_cost_log_enabled(enabled);
+    }
+
+    #[must_use]
+    pub fn cost_log(&self) -> &[SpriteRun] {
+        self.renderer.cost_log()
+    }
+
+    pub fn clear_cost_log(&mut self) {
+        self.renderer.clear_cost_log();
+    }
+
+    #[must_use]
+    pub fn sprite_working(&self) -> bool {
+        self.registers.sprsys_r_is_flag_set(SprSysR::sprite_working)
+    }
+
     #[must_use]
     pub fn left_handed(&self) -> bool {
         self.registers.sprsys_w_is_flag_set(SprSysW::left_handed)
     }
 }
 
-fn has_bus(bus: &mut Bus) -> bool {
+fn has_bus(bus: &Bus) -> bool {
     !bus.grant()
 }
 
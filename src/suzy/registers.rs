@@ -283,6 +283,13 @@ impl SuzyRegisters {
         self.sprsys_r.bits()
     }
 
+    /// Last value written to `SPRSYS`.
+    #[inline]
+    #[must_use]
+    pub fn sprsys_w(&self) -> u8 {
+        self.sprsys_w.bits()
+    }
+
     #[inline]
     pub fn set_joystick(&mut self, joy: Joystick) {
         self.set_data(JOYSTICK, joy.bits());
@@ -600,11 +607,13 @@ impl Default for SuzyRegisters {
 
 #[cfg(test)]
 mod tests {
+    use super::super::math::MathMode;
     use super::*;
 
     #[derive(Default)]
     struct TestCore {
         regs: SuzyRegisters,
+        mode: MathMode,
     }
 
     macro_rules! T {
@@ -616,7 +625,7 @@ mod tests {
     macro_rules! MULT {
         ($c: expr) => {
             $c.regs.backup_cd();
-            multiply(&mut $c.regs)
+            multiply(&mut $c.regs, $c.mode)
         };
     }
 
@@ -632,6 +641,12 @@ mod tests {
         };
     }
 
+    macro_rules! ACCURATE {
+        ($c: ident) => {
+            $c.mode = MathMode::Accurate
+        };
+    }
+
     macro_rules! ACC {
         ($c: expr) => {
             $c.regs.sprsys_w_enable_flag(SprSysW::accumulate)
@@ -653,7 +668,7 @@ mod tests {
     macro_rules! SA {
         ($c: expr, $v: expr) => {
             $c.regs.set_data_r($v);
-            set_matha(&mut $c.regs);
+            set_matha(&mut $c.regs, $c.mode);
         };
     }
 
@@ -666,7 +681,7 @@ mod tests {
     macro_rules! SC {
         ($c: expr, $v: expr) => {
             $c.regs.set_data_r($v);
-            set_mathc(&mut $c.regs);
+            set_mathc(&mut $c.regs, $c.mode);
         };
     }
 
@@ -676,6 +691,13 @@ mod tests {
         };
     }
 
+    // CPU write to the LSB only, "Any CPU write to an LSB will set the MSB to 0."
+    macro_rules! SD_ONLY {
+        ($c: expr, $v: expr) => {
+            $c.regs.set_u16(MATHD, $v);
+        };
+    }
+
     macro_rules! SE {
         ($c: expr, $v: expr) => {
             $c.regs.set_data_r($v);
@@ -927,4 +949,142 @@ mod tests {
         T!(!WN!(m));
         T!(!CY!(m));
     }
+
+    #[test]
+    fn convert_sign_modes() {
+        use super::super::math::convert_sign;
+
+        for mode in [MathMode::Compatible, MathMode::Accurate] {
+            T!(convert_sign(1, mode) == (1, 1));
+            T!(convert_sign(0x7FFF, mode) == (0x7FFF, 1));
+            // "In signed multiply, the hardware thinks that 8000 is a positive number."
+            T!(convert_sign(0x8000, mode) == (0x8000, 1));
+            T!(convert_sign(0x8001, mode) == (0x7FFF, -1));
+            T!(convert_sign(0xFFFF, mode) == (1, -1));
+        }
+        T!(convert_sign(0, MathMode::Compatible) == (0, 1));
+        // "In signed multiply, the hardware thinks that 0 is a negative number."
+        T!(convert_sign(0, MathMode::Accurate) == (0, -1));
+    }
+
+    #[test]
+    fn mult_signed_accurate() {
+        let mut m: TestCore = TestCore::default();
+
+        SIGNED!(m);
+        ACCURATE!(m);
+
+        MULT_T!(m, 0, 0, 0);
+        MULT_T!(m, 10, 0, 0);
+        MULT_T!(m, 0, 10, 0);
+        MULT_T!(m, 0, -10_i16, 0);
+        MULT_T!(m, -10_i16, 0, 0);
+        T!(!CY!(m));
+        MULT_T!(m, 10, 10, 100);
+        MULT_T!(m, 10, -10_i16, -100_i32);
+        MULT_T!(m, -10_i16, -10_i16, 100);
+        MULT_T!(m, -10_i16, 10, -100_i32);
+        MULT_T!(m, 512, -512_i16, (-512 * 512));
+        MULT_T!(m, -23768_i16, -23768_i16, -23768 * -23768);
+        MULT_T!(m, -22768_i16, 23768_i16, 22768 * -23768);
+    }
+
+    #[test]
+    fn mult_signed_8000() {
+        for mode in [MathMode::Compatible, MathMode::Accurate] {
+            let mut m: TestCore = TestCore::default();
+            SIGNED!(m);
+            m.mode = mode;
+
+            MULT_T!(m, 0x8000, 2, 0x10000);
+            MULT_T!(m, 2, 0x8000, 0x10000);
+            MULT_T!(m, 0x8000, -1_i16, -0x8000_i32);
+            MULT_T!(m, 0x8000, 0x8000, 0x4000_0000);
+        }
+    }
+
+    #[test]
+    fn mult_signed_zero_sign() {
+        // "since it will set the sign flag, you can not depend on the sign flag to be correct
+        // if you just load the lower byte after a multiply by zero."
+        for (mode, exp) in [(MathMode::Compatible, 15), (MathMode::Accurate, -15_i32)] {
+            let mut m: TestCore = TestCore::default();
+            SIGNED!(m);
+            m.mode = mode;
+
+            MULT_T!(m, 3, 0, 0);
+            SD_ONLY!(m, 5);
+            SAB!(m, 3);
+            MULT!(m);
+            T!(EFGH!(m) == exp as u32);
+        }
+    }
+
+    #[test]
+    fn mult_accumulator_accurate() {
+        let mut m: TestCore = TestCore::default();
+
+        ACC!(m);
+        ACCURATE!(m);
+
+        SJKLM!(m, 0);
+        MULT_T!(m, 50800, 35002, (50800 * 35002));
+        TJKLM!(m, 1_778_101_600, false, false);
+        MULT_T!(m, 50800, 45002, (50800_u32 * 45002_u32));
+        TJKLM!(m, 4_064_203_200, false, false);
+        MULT_T!(m, 50800, 45002, (50800_u32 * 45002_u32));
+        TJKLM!(m, 2_055_337_504, true, true);
+        // The overflow stays set until 'M' is written.
+        MULT_T!(m, 12, 256, (12 * 256));
+        TJKLM!(m, 2_055_340_576, false, true);
+        SJKLM!(m, 0);
+        TJKLM!(m, 0, false, false);
+        MULT_T!(m, 12, 256, (12 * 256));
+        TJKLM!(m, 3072, false, false);
+    }
+
+    #[test]
+    fn mult_signed_accumulator() {
+        for mode in [MathMode::Compatible, MathMode::Accurate] {
+            let mut m: TestCore = TestCore::default();
+            SIGNED!(m);
+            ACC!(m);
+            m.mode = mode;
+
+            SJKLM!(m, 0);
+            MULT_T!(m, 10, -10_i16, -100_i32);
+            TJKLM!(m, -100_i32 as u32, true, false);
+            MULT_T!(m, 5, 5, 25);
+            TJKLM!(m, -75_i32 as u32, false, false);
+            // The accumulator is unsigned, crossing 0 is an overflow.
+            MULT_T!(m, 10, 10, 100);
+            TJKLM!(m, 25, true, true);
+            MULT_T!(m, 1, 1, 1);
+            TJKLM!(m, 26, false, mode == MathMode::Accurate);
+        }
+    }
+
+    #[test]
+    fn div_0_accurate() {
+        let mut m: TestCore = TestCore::default();
+
+        ACC!(m);
+        ACCURATE!(m);
+
+        DIV_T!(m, 456_u32, 0_u32);
+        T!(WN!(m));
+        T!(CY!(m));
+        // The math bit is shared with the accumulator overflow.
+        SJKLM!(m, 0);
+        T!(!WN!(m));
+        DIV_T!(m, 456_u32, 0_u32);
+        MULT_T!(m, 2, 3, 6);
+        T!(WN!(m));
+        T!(!CY!(m));
+        DIV_T!(m, 0xFFFF_FFFF_u32, 3_u32);
+        T!(!WN!(m));
+        DIV_T!(m, 0xFFFF_FFFF_u32, 0xFFFF_u32);
+        DIV_T!(m, 65537_u32, 256_u32);
+        T!(CY!(m));
+    }
 }
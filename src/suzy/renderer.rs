@@ -1,7 +1,11 @@
-use crate::{mikey, suzy};
+use crate::{alloc, mikey, suzy};
+use alloc::vec::Vec;
 use log::trace;
 use mikey::video::{LYNX_SCREEN_HEIGHT, LYNX_SCREEN_WIDTH};
 use sprite_data::SpriteData;
+use suzy::sprite_log::{
+    ScreenBounds, SpriteCollision, SpriteCost, SpriteCostRecord, SpriteDraw, SpriteRun, SpriteType,
+};
 use suzy::{
     sprite_data, Deserialize, Ram, Serialize, SprSysR, SprSysW, SuzyRegisters, SuzyTask, TaskStep,
     COLLADRL, COLLOFFL, HOFFL, HPOSSTRTH, HPOSSTRTL, HSIZOFFL, LINE_END, RAM_PAGE_READ_TICKS,
@@ -13,16 +17,43 @@ use suzy::{
     TILTL, VIDADRL, VOFFL, VPOSSTRTH, VPOSSTRTL, VSIZACUMH, VSIZACUML, VSIZOFFL,
 };
 
+macro_rules! count_cost {
+    ($slf: ident, $field: ident) => {
+        count_cost!($slf, $field, 1)
+    };
+    ($slf: ident, $field: ident, $n: expr) => {
+        if $slf.cost_run.is_some() {
+            $slf.sprite_cost.$field += $n;
+        }
+    };
+}
+
 macro_rules! peek_dma {
-    ($regs: ident, $ram: ident, $addr: expr) => {{
+    ($slf: ident, $regs: ident, $ram: ident, $addr: expr) => {{
+        let addr = $addr;
         $regs.set_task_ticks_delay(RAM_PAGE_READ_TICKS as u16);
-        $ram.get($addr)
+        count_cost!($slf, bytes_read);
+        if let Some(log) = &mut $slf.dma_log {
+            log.push(addr);
+        }
+        $ram.get(addr)
+    }};
+}
+
+macro_rules! poke_dma {
+    ($slf: ident, $ram: ident, $addr: expr, $data: expr) => {{
+        let addr = $addr;
+        if let Some(log) = &mut $slf.dma_write_log {
+            log.push(addr);
+        }
+        $ram.set(addr, $data);
     }};
 }
 
 macro_rules! peek_scb_header {
     ($slf: ident, $regs: ident, $ram: ident) => {{
-        let data = peek_dma!($regs, $ram, $regs.tmp_addr());
+        let data = peek_dma!($slf, $regs, $ram, $regs.tmp_addr());
+        count_cost!($slf, scb_bytes_read);
         $regs.set_tmp_addr($regs.tmp_addr().wrapping_add(1));
         trace!(
             "  SCB Header Step {}: Read 0x{:02X} from 0x{:04X}",
@@ -37,7 +68,7 @@ macro_rules! peek_scb_header {
 
 macro_rules! peek_and_store_scb_data {
     ($slf: ident, $regs: ident, $ram: ident) => {
-        let data = peek_dma!($regs, $ram, $regs.tmp_addr());
+        let data = peek_dma!($slf, $regs, $ram, $regs.tmp_addr());
         $slf.sprite_data.push_data(data);
         $regs.set_tmp_addr($regs.tmp_addr().wrapping_add(1));
     };
@@ -75,6 +106,24 @@ pub struct Renderer {
     pixel_width: u8,
     collision: u8,
     pens: [u8; 16],
+    #[serde(skip)]
+    dma_log: Option<Vec<u16>>,
+    #[serde(skip)]
+    dma_write_log: Option<Vec<u16>>,
+    #[serde(skip)]
+    collision_log: Option<Vec<SpriteCollision>>,
+    #[serde(skip)]
+    draw_log: Option<Vec<SpriteDraw>>,
+    #[serde(skip)]
+    draw_quadrants: Vec<u8>,
+    #[serde(skip)]
+    draw_bounds: Option<ScreenBounds>,
+    #[serde(skip)]
+    cost_log: Option<Vec<SpriteRun>>,
+    #[serde(skip)]
+    cost_run: Option<SpriteRun>,
+    #[serde(skip)]
+    sprite_cost: SpriteCost,
 }
 
 impl Renderer {
@@ -104,6 +153,15 @@ impl Renderer {
             pixel_width: 0,
             collision: 0,
             pens: [0; 16],
+            dma_log: None,
+            dma_write_log: None,
+            collision_log: None,
+            draw_log: None,
+            draw_quadrants: vec![],
+            draw_bounds: None,
+            cost_log: None,
+            cost_run: None,
+            sprite_cost: SpriteCost::default(),
         }
     }
 
@@ -134,6 +192,11 @@ impl Renderer {
         } else {
             regs.set_tmp_addr(scbaddr);
             regs.set_task_ticks_delay(SUZY_SPRITE_SCB_ADDITIONAL_COST);
+            count_cost!(
+                self,
+                scb_overhead_ticks,
+                u32::from(SUZY_SPRITE_SCB_ADDITIONAL_COST)
+            );
             regs.inc_task_step();
         }
     }
@@ -143,6 +206,7 @@ impl Renderer {
         regs.sprsys_r_disable_flag(SprSysR::sprite_working);
         regs.sprsys_r_disable_flag(SprSysR::math_working);
         regs.reset_task();
+        self.end_cost_run();
     }
 
     fn load_scb(&mut self, ram: &mut Ram, regs: &mut SuzyRegisters) {
@@ -157,6 +221,7 @@ impl Renderer {
                 peek_and_store_scb_header!(self, regs, ram, SCBNEXTH);
                 if regs.sprctl1() & SPRCTL1_SKIP_SPRITE != 0 {
                     trace!("Sprite skipped.");
+                    self.end_sprite_cost(regs.scb_addr());
                     self.scb_step = 0;
                     regs.set_task_step(TaskStep::InitializePainting); // next scb if any
                 }
@@ -238,6 +303,8 @@ impl Renderer {
         );
         self.ever_on_screen = false;
         self.collision = 0;
+        self.draw_quadrants.clear();
+        self.draw_bounds = None;
         self.start_quadrant = regs.start_quadrant();
         self.quadrant = self.start_quadrant;
         self.hoff = regs.i16(HOFFL);
@@ -297,6 +364,9 @@ impl Renderer {
 
         self.pixel_height = 0;
         self.sprite_data.reset(regs);
+        if self.draw_log.is_some() {
+            self.draw_quadrants.push(self.quadrant);
+        }
         regs.inc_task_step();
     }
 
@@ -318,14 +388,16 @@ impl Renderer {
 
     pub fn sprite_end(&mut self, regs: &mut SuzyRegisters, ram: &mut Ram) -> u16 {
         let mut mem_count = 0;
+        let mut depository_written = false;
         if regs.sprcoll() & SPRCOLL_DONT_COLLIDE == 0
             && !regs.sprsys_w_is_flag_set(SprSysW::no_collide)
         {
             match regs.sprctl0() & SPRCTL0_SPR_TYPE {
                 2 | 3 | 4 | 6 | 7 => {
                     let coladr = regs.scb_addr().wrapping_add(regs.u16(COLLOFFL));
-                    ram.set(coladr, self.collision);
+                    poke_dma!(self, ram, coladr, self.collision);
                     mem_count += 1;
+                    depository_written = true;
                     trace!("set collision 0x{:04X}=0x{:02X}", coladr, self.collision);
                 }
                 _ => (),
@@ -340,9 +412,31 @@ impl Renderer {
             } else {
                 coldat |= 0x80;
             }
-            ram.set(coladr, coldat);
+            poke_dma!(self, ram, coladr, coldat);
+            depository_written = true;
+        }
+
+        if depository_written {
+            if let Some(log) = &mut self.collision_log {
+                log.push(SpriteCollision {
+                    scb: regs.scb_addr(),
+                    number: regs.sprcoll() & SPRCOLL_NUMBER,
+                    depository: ram.get(regs.scb_addr().wrapping_add(regs.u16(COLLOFFL))),
+                });
+            }
+        }
+
+        if let Some(log) = &mut self.draw_log {
+            log.push(SpriteDraw {
+                scb: regs.scb_addr(),
+                sprite_type: SpriteType::from_sprctl0(regs.sprctl0()),
+                quadrants: core::mem::take(&mut self.draw_quadrants),
+                bounds: self.draw_bounds,
+            });
         }
 
+        self.end_sprite_cost(regs.scb_addr());
+
         if regs.sprsys_w_is_flag_set(SprSysW::sprite_to_stop) {
             regs.inc_task_step();
         } else {
@@ -478,6 +572,11 @@ impl Renderer {
             self.sprite_data.reset(regs);
             peek_and_store_scb_data!(self, regs, ram);
             regs.add_task_ticks_delay(SUZY_SPRITE_VERT_ADDITIONAL_COST);
+            count_cost!(
+                self,
+                line_overhead_ticks,
+                u32::from(SUZY_SPRITE_VERT_ADDITIONAL_COST)
+            );
             regs.set_task_step(TaskStep::RenderPixelHeightStart);
         }
     }
@@ -512,6 +611,13 @@ impl Renderer {
         for _ in 0..self.pixel_width {
             if self.hoff >= 0 && self.hoff < LYNX_SCREEN_WIDTH as i16 {
                 self.ever_on_screen = true;
+                if self.draw_log.is_some() {
+                    let (x, y) = (self.hoff as u8, self.voff as u8);
+                    match &mut self.draw_bounds {
+                        Some(bounds) => bounds.extend(x, y),
+                        None => self.draw_bounds = Some(ScreenBounds::new(x, y)),
+                    }
+                }
                 mem_access_count += self.process_pixel(regs, ram);
                 trace!("- RenderPixel. width:{}", self.pixel_width);
             }
@@ -533,7 +639,8 @@ impl Renderer {
             dest &= 0xf0;
             dest |= pixel;
         }
-        ram.set(scr_addr, dest);
+        poke_dma!(self, ram, scr_addr, dest);
+        count_cost!(self, pixels_written);
         trace!(
             "write_pixel({}, {}) 0x{:04x} = 0x{:02x}",
             self.hoff,
@@ -555,6 +662,7 @@ impl Renderer {
         } else {
             data &= 0x0f;
         }
+        count_cost!(self, pixels_read);
 
         (data, 1)
     }
@@ -571,7 +679,8 @@ impl Renderer {
             dest &= 0xf0;
             dest |= pixel;
         }
-        ram.set(col_addr, dest);
+        poke_dma!(self, ram, col_addr, dest);
+        count_cost!(self, collision_pixels_written);
         trace!("Write collision pixel 0x{col_addr:04x} = 0x{dest:02x}");
         2
     }
@@ -586,6 +695,7 @@ impl Renderer {
         } else {
             data &= 0x0f;
         }
+        count_cost!(self, pixels_read);
 
         (data, 1)
     }
@@ -798,9 +908,130 @@ impl Renderer {
         regs.task() == SuzyTask::None
     }
 
+    fn end_sprite_cost(&mut self, scb: u16) {
+        let cost = core::mem::take(&mut self.sprite_cost);
+        if let Some(run) = &mut self.cost_run {
+            run.sprites.push(SpriteCostRecord { scb, cost });
+            run.total += cost;
+        }
+    }
+
+    /// Starts the cost accounting of a `SPRGO` run.
+    pub fn start_cost_run(&mut self) {
+        self.sprite_cost = SpriteCost::default();
+        if self.cost_log.is_some() {
+            self.cost_run = Some(SpriteRun::default());
+        }
+    }
+
+    fn end_cost_run(&mut self) {
+        let cost = core::mem::take(&mut self.sprite_cost);
+        if let (Some(mut run), Some(log)) = (self.cost_run.take(), &mut self.cost_log) {
+            run.total += cost;
+            log.push(run);
+        }
+    }
+
+    /// Accounts a tick during which the sprite engine holds the bus.
+    pub fn count_bus_tick(&mut self, cpu_held: bool) {
+        if self.cost_run.is_none() {
+            return;
+        }
+        self.sprite_cost.bus_ticks += 1;
+        if cpu_held {
+            self.sprite_cost.cpu_held_ticks += 1;
+        }
+    }
+
     pub fn push_sprite_data(&mut self, data: u8) {
         self.sprite_data.push_data(data);
     }
+
+    /// Pens of the current sprite.
+    #[must_use]
+    pub fn pens(&self) -> &[u8; 16] {
+        &self.pens
+    }
+
+    pub fn set_dma_log_enabled(&mut self, enabled: bool) {
+        self.dma_log = if enabled { Some(Vec::new()) } else { None };
+        self.dma_write_log = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    #[must_use]
+    pub fn dma_log_enabled(&self) -> bool {
+        self.dma_log.is_some()
+    }
+
+    /// Addresses read by the sprite engine since the last `clear_dma_log`.
+    #[must_use]
+    pub fn dma_log(&self) -> &[u16] {
+        self.dma_log.as_deref().unwrap_or_default()
+    }
+
+    /// Addresses written by the sprite engine since the last `clear_dma_log`.
+    #[must_use]
+    pub fn dma_write_log(&self) -> &[u16] {
+        self.dma_write_log.as_deref().unwrap_or_default()
+    }
+
+    pub fn clear_dma_log(&mut self) {
+        if let Some(log) = &mut self.dma_log {
+            log.clear();
+        }
+        if let Some(log) = &mut self.dma_write_log {
+            log.clear();
+        }
+    }
+
+    pub fn set_collision_log_enabled(&mut self, enabled: bool) {
+        self.collision_log = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Collision depositories written since the last `clear_collision_log`.
+    #[must_use]
+    pub fn collision_log(&self) -> &[SpriteCollision] {
+        self.collision_log.as_deref().unwrap_or_default()
+    }
+
+    pub fn clear_collision_log(&mut self) {
+        if let Some(log) = &mut self.collision_log {
+            log.clear();
+        }
+    }
+
+    pub fn set_draw_log_enabled(&mut self, enabled: bool) {
+        self.draw_log = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Sprites processed since the last `clear_draw_log`.
+    #[must_use]
+    pub fn draw_log(&self) -> &[SpriteDraw] {
+        self.draw_log.as_deref().unwrap_or_default()
+    }
+
+    pub fn clear_draw_log(&mut self) {
+        if let Some(log) = &mut self.draw_log {
+            log.clear();
+        }
+    }
+
+    pub fn set_cost_log_enabled(&mut self, enabled: bool) {
+        self.cost_log = if enabled { Some(Vec::new()) } else { None };
+        self.cost_run = None;
+    }
+
+    /// `SPRGO` runs completed since the last `clear_cost_log`.
+    #[must_use]
+    pub fn cost_log(&self) -> &[SpriteRun] {
+        self.cost_log.as_deref().unwrap_or_default()
+    }
+
+    pub fn clear_cost_log(&mut self) {
+        if let Some(log) = &mut self.cost_log {
+            log.clear();
+        }
+    }
 }
 
 impl Default for Renderer {
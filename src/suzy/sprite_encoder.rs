@@ -0,0 +1,253 @@
+use crate::alloc::vec::Vec;
+
+/// Longest run of pixels a packed or literal chunk can hold.
+const CHUNK_MAX_LEN: usize = 16;
+/// Chunk header, the literal flag and the 4 bits count.
+const CHUNK_HEADER_BITS: usize = 5;
+
+/// Sprite data produced by [`encode`], `literal` tells how `SPRCTL1` has to be set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodedSprite {
+    pub literal: bool,
+    pub data: Vec<u8>,
+}
+
+struct BitWriter {
+    data: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            data: vec![],
+            bits: 0,
+        }
+    }
+
+    fn push(&mut self, value: u8, bits: u8) {
+        for i in (0..bits).rev() {
+            if self.bits % 8 == 0 {
+                self.data.push(0);
+            }
+            if value & (1 << i) != 0 {
+                *self.data.last_mut().unwrap() |= 0x80 >> (self.bits % 8);
+            }
+            self.bits += 1;
+        }
+    }
+
+    /// Zero pads the line to `bytes` and prepends the offset to the next line.
+    fn finish_line(mut self, bytes: usize, stream: &mut Vec<u8>) -> Result<(), &'static str> {
+        let offset = u8::try_from(bytes + 1).map_err(|_| "Sprite line too long")?;
+        self.data.resize(bytes, 0);
+        stream.push(offset);
+        stream.extend_from_slice(&self.data);
+        Ok(())
+    }
+}
+
+fn lines(
+    pixels: &[u8],
+    width: usize,
+    bpp: u8,
+) -> Result<core::slice::ChunksExact<'_, u8>, &'static str> {
+    if !(1..=4).contains(&bpp) {
+        return Err("Bits per pixel must be 1 to 4");
+    }
+    if width == 0 || pixels.len() % width != 0 {
+        return Err("Image size doesn't match its width");
+    }
+    if pixels.iter().any(|&p| p >> bpp != 0) {
+        return Err("Pen index doesn't fit in the bits per pixel");
+    }
+    Ok(pixels.chunks_exact(width))
+}
+
+/// Encodes an indexed image, one pen index per pixel line by line, into the
+/// smallest packed sprite data stream.
+///
+/// # Errors
+///
+/// Returns an error if the image doesn't fit the format.
+pub fn encode_packed(pixels: &[u8], width: usize, bpp: u8) -> Result<Vec<u8>, &'static str> {
+    let mut stream = vec![];
+    for line in lines(pixels, width, bpp)? {
+        let bits = |len: usize, packed: bool| {
+            CHUNK_HEADER_BITS + if packed { 1 } else { len } * bpp as usize
+        };
+
+        // Cheapest encoding of every line suffix, as the cost and the first chunk.
+        let n = line.len();
+        let mut best = vec![(0, 0, false); n + 1];
+        for i in (0..n).rev() {
+            best[i] = (usize::MAX, 0, false);
+            for len in 1..=CHUNK_MAX_LEN.min(n - i) {
+                // A packed count of 0 is the end of line, a packed chunk repeats 2 pixels at least.
+                let packed = len > 1 && line[i..i + len].iter().all(|&p| p == line[i]);
+                let cost = bits(len, packed) + best[i + len].0;
+                if cost < best[i].0 {
+                    best[i] = (cost, len, packed);
+                }
+            }
+        }
+
+        let mut writer = BitWriter::new();
+        let mut i = 0;
+        while i < n {
+            let (_, len, packed) = best[i];
+            writer.push(u8::from(!packed), 1);
+            writer.push((len - 1) as u8, 4);
+            if packed {
+                writer.push(line[i], bpp);
+            } else {
+                for &p in &line[i..i + len] {
+                    writer.push(p, bpp);
+                }
+            }
+            i += len;
+        }
+        // The last bit of a line can't be read, the padding also reads as the end of line chunk.
+        let bytes = writer.bits / 8 + 1;
+        writer.finish_line(bytes, &mut stream)?;
+    }
+    stream.push(0);
+    Ok(stream)
+}
+
+/// Encodes an indexed image, one pen index per pixel line by line, into a
+/// literal sprite data stream.
+///
+/// The line length of a literal sprite comes from its size in bytes and a
+/// trailing 0 pixel ends it early, so some widths can't be encoded exactly.
+///
+/// # Errors
+///
+/// Returns an error if the image doesn't fit the format.
+pub fn encode_literal(pixels: &[u8], width: usize, bpp: u8) -> Result<Vec<u8>, &'static str> {
+    let mut stream = vec![];
+    for line in lines(pixels, width, bpp)? {
+        let n = line.len();
+        let bpp_bits = bpp as usize;
+        // The sprite engine reads `8 * bytes / bpp` pixels, the last one is
+        // dropped if 0 and reads as 0 when it ends exactly on the last bit.
+        let mut bytes = (n * bpp_bits).div_ceil(8);
+        loop {
+            let count = bytes * 8 / bpp_bits;
+            if count == n + 1 || (count == n && bytes * 8 > n * bpp_bits && line[n - 1] != 0) {
+                break;
+            }
+            if count > n {
+                return Err("Line width can't be encoded exactly as literal");
+            }
+            bytes += 1;
+        }
+
+        let mut writer = BitWriter::new();
+        for &p in line {
+            writer.push(p, bpp);
+        }
+        writer.finish_line(bytes, &mut stream)?;
+    }
+    stream.push(0);
+    Ok(stream)
+}
+
+/// Encodes an indexed image into the smallest of the packed and literal sprite data streams.
+///
+/// # Errors
+///
+/// Returns an error if the image doesn't fit the format.
+pub fn encode(pixels: &[u8], width: usize, bpp: u8) -> Result<EncodedSprite, &'static str> {
+    let packed = encode_packed(pixels, width, bpp)?;
+    Ok(match encode_literal(pixels, width, bpp) {
+        Ok(data) if data.len() < packed.len() => EncodedSprite {
+            literal: true,
+            data,
+        },
+        _ => EncodedSprite {
+            literal: false,
+            data: packed,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::sprite::SpriteDecoder;
+
+    fn image(width: usize, height: usize, bpp: u8, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..width * height)
+            .map(|i| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                // Runs of the same pen, so that the packed chunks get used too.
+                if i % 5 < 2 {
+                    ((state >> 16) as u8) & ((1 << bpp) - 1)
+                } else {
+                    ((i / 5) as u8) & ((1 << bpp) - 1)
+                }
+            })
+            .collect()
+    }
+
+    fn assert_round_trip(pixels: &[u8], width: usize, bpp: u8, literal: bool, data: &[u8]) {
+        let decoder = SpriteDecoder {
+            bits_per_pixel: bpp,
+            literal,
+            // Background sprites draw pen 0 too.
+            sprite_type: 1,
+            ..SpriteDecoder::new()
+        };
+        let decoded = decoder.decode(data);
+        let height = pixels.len() / width;
+        assert_eq!((decoded.width, decoded.height), (width, height));
+        assert_eq!(decoded.origin, (0, 0));
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(
+                    decoded.pen(x, y),
+                    Some(pixels[y * width + x]),
+                    "bpp:{bpp} literal:{literal} width:{width} x:{x} y:{y}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for bpp in 1..=4 {
+            for width in 1..=40 {
+                let pixels = image(width, 3, bpp, width as u32);
+                let packed = encode_packed(&pixels, width, bpp).unwrap();
+                assert_round_trip(&pixels, width, bpp, false, &packed);
+                if let Ok(literal) = encode_literal(&pixels, width, bpp) {
+                    assert_round_trip(&pixels, width, bpp, true, &literal);
+                }
+                let encoded = encode(&pixels, width, bpp).unwrap();
+                assert!(encoded.data.len() <= packed.len());
+                assert_round_trip(&pixels, width, bpp, encoded.literal, &encoded.data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip_runs() {
+        let pixels = [[3u8; 37], [0; 37], [1; 37]].concat();
+        let encoded = encode(&pixels, 37, 2).unwrap();
+        assert!(!encoded.literal);
+        assert_round_trip(&pixels, 37, 2, false, &encoded.data);
+    }
+
+    #[test]
+    fn test_literal_widths() {
+        // 3 pixels and the dropped 4th one fit exactly in one byte.
+        let literal = encode_literal(&[1, 2, 3], 3, 2).unwrap();
+        assert_eq!(literal, vec![2, 0b0110_1100, 0]);
+        assert_round_trip(&[1, 2, 3], 3, 2, true, &literal);
+        // The last pixel of a byte aligned line is lost.
+        assert!(encode_literal(&[1, 2, 3, 1], 4, 2).is_err());
+        assert!(encode_literal(&[4], 1, 2).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::alloc::vec::Vec;
+use core::ops::AddAssign;
+use serde::{Deserialize, Serialize};
+
+/// Collision depository written back by the sprite engine at `SCB + COLLOFF`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteCollision {
+    pub scb: u16,
+    /// `SPRCOLL` collision number of the sprite.
+    pub number: u8,
+    /// Highest collision number found under the sprite, bit 7 is the `EVERON` "never on screen" flag.
+    pub depository: u8,
+}
+
+/// `SPRCTL0` sprite types, as handled by `Renderer::process_sprite_type`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpriteType {
+    BackgroundShadow = 0,
+    BackgroundNoCollide = 1,
+    BoundaryShadow = 2,
+    Boundary = 3,
+    Normal = 4,
+    NoCollide = 5,
+    XorShadow = 6,
+    Shadow = 7,
+}
+
+impl SpriteType {
+    #[must_use]
+    pub fn from_sprctl0(sprctl0: u8) -> Self {
+        match sprctl0 & 0x07 {
+            0 => SpriteType::BackgroundShadow,
+            1 => SpriteType::BackgroundNoCollide,
+            2 => SpriteType::BoundaryShadow,
+            3 => SpriteType::Boundary,
+            4 => SpriteType::Normal,
+            5 => SpriteType::NoCollide,
+            6 => SpriteType::XorShadow,
+            _ => SpriteType::Shadow,
+        }
+    }
+}
+
+/// Screen area covered by a sprite, inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScreenBounds {
+    pub left: u8,
+    pub top: u8,
+    pub right: u8,
+    pub bottom: u8,
+}
+
+impl ScreenBounds {
+    #[must_use]
+    pub fn new(x: u8, y: u8) -> Self {
+        Self {
+            left: x,
+            top: y,
+            right: x,
+            bottom: y,
+        }
+    }
+
+    pub fn extend(&mut self, x: u8, y: u8) {
+        self.left = self.left.min(x);
+        self.top = self.top.min(y);
+        self.right = self.right.max(x);
+        self.bottom = self.bottom.max(y);
+    }
+}
+
+/// A sprite processed by the sprite engine.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteDraw {
+    pub scb: u16,
+    pub sprite_type: SpriteType,
+    /// Quadrants in drawing order, 0 is down-right, then counter clockwise.
+    pub quadrants: Vec<u8>,
+    /// Pixels processed on screen, transparent ones included. `None` if fully off-screen.
+    pub bounds: Option<ScreenBounds>,
+}
+
+/// Work done by the sprite engine and the bus time it took.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteCost {
+    /// Ticks Suzy held the bus.
+    pub bus_ticks: u32,
+    /// Ticks the CPU, woken up by an interrupt, waited for Suzy to release the bus.
+    pub cpu_held_ticks: u32,
+    /// Bytes read from RAM, SCB and sprite data.
+    pub bytes_read: u32,
+    /// Part of `bytes_read` spent on the SCB.
+    pub scb_bytes_read: u32,
+    /// Pixels written to the video buffer.
+    pub pixels_written: u32,
+    /// Pixels written to the collision buffer.
+    pub collision_pixels_written: u32,
+    /// Pixels read back from the video or the collision buffer.
+    pub pixels_read: u32,
+    /// `SUZY_SPRITE_SCB_ADDITIONAL_COST` ticks.
+    pub scb_overhead_ticks: u32,
+    /// `SUZY_SPRITE_VERT_ADDITIONAL_COST` ticks.
+    pub line_overhead_ticks: u32,
+}
+
+impl SpriteCost {
+    /// Every pixel write is a read-modify-write of the byte holding it.
+    #[must_use]
+    pub fn read_modify_writes(&self) -> u32 {
+        self.pixels_written + self.collision_pixels_written
+    }
+}
+
+impl AddAssign for SpriteCost {
+    fn add_assign(&mut self, other: Self) {
+        self.bus_ticks += other.bus_ticks;
+        self.cpu_held_ticks += other.cpu_held_ticks;
+        self.bytes_read += other.bytes_read;
+        self.scb_bytes_read += other.scb_bytes_read;
+        self.pixels_written += other.pixels_written;
+        self.collision_pixels_written += other.collision_pixels_written;
+        self.pixels_read += other.pixels_read;
+        self.scb_overhead_ticks += other.scb_overhead_ticks;
+        self.line_overhead_ticks += other.line_overhead_ticks;
+    }
+}
+
+/// Cost of a sprite, skipped ones included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteCostRecord {
+    pub scb: u16,
+    pub cost: SpriteCost,
+}
+
+/// A `SPRGO` run, from the start of the sprite engine to its stop.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteRun {
+    /// Sprites in drawing order.
+    pub sprites: Vec<SpriteCostRecord>,
+    /// The sprites and the end of chain overhead.
+    pub total: SpriteCost,
+}
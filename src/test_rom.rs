@@ -0,0 +1,104 @@
+use crate::consts::{
+    COLLBASL, CPUSLEEP, HSIZOFFL, SCBNEXTL, SPRGO, SPRGO_GO, SUZYBUSEN, VIDBASL, VSIZOFFL,
+};
+use crate::lynx::Lynx;
+use alloc::vec::Vec;
+
+pub(crate) fn store(code: &mut Vec<u8>, addr: u16, data: u8) {
+    code.extend_from_slice(&[0xA9, data, 0x8D, addr as u8, (addr >> 8) as u8]);
+}
+
+/// A console setting its UART to 62500 bauds, sending `data`, then reading the received bytes.
+pub(crate) fn lynx_sending(data: &[u8]) -> Lynx {
+    let mut code = vec![];
+    store(&mut code, 0xFD10, 1); // TIM4BKUP
+    store(&mut code, 0xFD11, 0x18); // TIM4CTLA, reload, count, 1us
+    store(&mut code, 0xFD8C, 0x1D); // SERCTL, PAREN | RESETERR | TXOPEN | PAREVEN
+    for &data in data {
+        // SERDAT, then wait for TXEMPTY
+        store(&mut code, 0xFD8D, data);
+        code.extend_from_slice(&[0xAD, 0x8C, 0xFD, 0x29, 0x20, 0xF0, 0xF9]);
+    }
+    // read SERDAT forever
+    let here = 0xFE00 + code.len() as u16;
+    code.extend_from_slice(&[0xAD, 0x8D, 0xFD, 0x4C, here as u8, (here >> 8) as u8]);
+    let mut rom = vec![0u8; 512];
+    rom[..code.len()].copy_from_slice(&code);
+    rom[0x1FC..].copy_from_slice(&[0x00, 0xFE, 0x00, 0xFE]);
+    let mut lynx = Lynx::new();
+    lynx.load_rom_from_slice(&rom).unwrap();
+    lynx
+}
+
+/// A console running `code` from 0xFE00 then looping, interrupts return at once.
+pub(crate) fn lynx_running(code: &[u8]) -> Lynx {
+    let mut rom = vec![0u8; 512];
+    rom[..code.len()].copy_from_slice(code);
+    let here = 0xFE00 + code.len() as u16;
+    rom[code.len()..code.len() + 3].copy_from_slice(&[0x4C, here as u8, (here >> 8) as u8]);
+    rom[0x1F0] = 0x40; // RTI
+    rom[0x1FC..].copy_from_slice(&[0x00, 0xFE, 0xF0, 0xFF]);
+    let mut lynx = Lynx::new();
+    lynx.load_rom_from_slice(&rom).unwrap();
+    lynx
+}
+
+/// A console with a blank cartridge, which `Lynx::reset` needs, starting the sprite engine
+/// on the SCB chain at `scb` then sleeping. The video buffer is at 0x2000 and the
+/// collision buffer at 0x4000, the chain has to be put in RAM before it runs.
+pub(crate) fn lynx_drawing(scb: u16) -> Lynx {
+    lynx_drawing_after(&[], scb)
+}
+
+/// Where [`draw_sprite`] puts its SCB.
+pub(crate) const SCB: u16 = 0x1000;
+
+/// Puts at [`SCB`] a 1 bpp literal sprite at (10, 10), of the `sprctl1` control bits and
+/// collision number `number`, each of its two lines of two pixels of pen 1 drawn `vsize`
+/// times, then runs 20000 ticks, showing the console to `on_tick` after each one.
+pub(crate) fn draw_sprite(
+    lynx: &mut Lynx,
+    sprctl1: u8,
+    number: u8,
+    vsize: u8,
+    mut on_tick: impl FnMut(&Lynx),
+) {
+    let ram = lynx.ram_mut();
+    // normal, reload HV
+    ram.copy(SCB, &[0x04, sprctl1, number, 0x00, 0x00, 0x00, 0x30]);
+    ram.copy(SCB + 7, &[10, 0, 10, 0, 0x00, 0x01, 0x00, vsize]);
+    // unread when reusing the palette
+    ram.copy(SCB + 15, &[0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF]);
+    // the last pixel of a literal line is dropped
+    ram.copy(0x3000, &[0x02, 0b1100_0000, 0x02, 0b1100_0000, 0x00]);
+    for _ in 0..20_000 {
+        lynx.tick();
+        on_tick(lynx);
+    }
+}
+
+/// Same as [`lynx_drawing`], running `setup` first. Woken up, the CPU goes back to sleep.
+pub(crate) fn lynx_drawing_after(setup: &[u8], scb: u16) -> Lynx {
+    let mut code = setup.to_vec();
+    for (addr, data) in [
+        (VIDBASL, 0x2000),
+        (COLLBASL, 0x4000),
+        (HSIZOFFL, 0x007F),
+        (VSIZOFFL, 0x007F),
+        (SCBNEXTL, scb),
+    ] {
+        let [lo, hi] = u16::to_le_bytes(data);
+        store(&mut code, addr, lo);
+        store(&mut code, addr + 1, hi);
+    }
+    store(&mut code, SUZYBUSEN, 1);
+    store(&mut code, SPRGO, SPRGO_GO);
+    let sleep = 0xFE00 + code.len() as u16;
+    store(&mut code, CPUSLEEP, 0);
+    code.extend_from_slice(&[0x4C, sleep as u8, (sleep >> 8) as u8]);
+    let mut lynx = lynx_running(&code);
+    let mut cart = vec![0x80, 0x08, 0x02, 0x00, 0x00, 0x0B];
+    cart.extend_from_slice(b"BS93\x00");
+    lynx.load_cart_from_slice(&cart).unwrap();
+    lynx
+}